@@ -1,11 +1,19 @@
 pub mod types;
 pub mod properties;
+pub mod registry;
+pub mod state;
 
+use anyhow::Result;
 use serde::{Serialize, Deserialize};
 use crate::math::vector::WorldVector;
 
+pub use properties::BlockProperties;
+pub use registry::BlockRegistry;
+pub use state::{Axis, BlockState, Facing, StateValue};
+pub use types::BlockType;
+
 /// A block in the game world
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Block {
     /// The type of the block
     pub block_type: u16,
@@ -15,21 +23,36 @@ pub struct Block {
 
 impl Block {
     /// Create a new block
-    pub fn new(block_type: u16) -> Self {
+    pub fn new(block_type: impl Into<u16>) -> Self {
         Self {
-            block_type,
+            block_type: block_type.into(),
             metadata: 0,
         }
     }
     
     /// Create a new block with metadata
-    pub fn with_metadata(block_type: u16, metadata: u16) -> Self {
+    pub fn with_metadata(block_type: impl Into<u16>, metadata: u16) -> Self {
         Self {
-            block_type,
+            block_type: block_type.into(),
             metadata,
         }
     }
     
+    /// Get the block type, or None if the ID is unknown
+    pub fn block_type(&self) -> Option<BlockType> {
+        BlockType::from_id(self.block_type)
+    }
+    
+    /// Get the properties of this block, or None if the ID is unknown
+    pub fn properties(&self) -> Option<&'static BlockProperties> {
+        BlockRegistry::global().get(self.block_type)
+    }
+    
+    /// Get the state of this block, or an error if its metadata isn't valid for its type
+    pub fn state(&self) -> Result<BlockState> {
+        BlockState::from_block(*self)
+    }
+    
     /// Check if this block is air
     pub fn is_air(&self) -> bool {
         self.block_type == 0
@@ -40,8 +63,7 @@ impl Block {
         if self.is_air() {
             return false;
         }
-        let props = types::BlockType::from_id(self.block_type).properties();
-        props.solid
+        self.properties().is_some_and(|props| props.solid)
     }
     
     /// Check if this block is transparent
@@ -49,8 +71,7 @@ impl Block {
         if self.is_air() {
             return true;
         }
-        let props = types::BlockType::from_id(self.block_type).properties();
-        props.transparent
+        self.properties().is_none_or(|props| props.transparent)
     }
     
     /// Check if this block is a fluid
@@ -58,8 +79,7 @@ impl Block {
         if self.is_air() {
             return false;
         }
-        let props = types::BlockType::from_id(self.block_type).properties();
-        props.fluid
+        self.properties().is_some_and(|props| props.fluid)
     }
     
     /// Get the light emission level of this block (0-15)
@@ -67,8 +87,7 @@ impl Block {
         if self.is_air() {
             return 0;
         }
-        let props = types::BlockType::from_id(self.block_type).properties();
-        props.light_emission
+        self.properties().map_or(0, |props| props.light_emission)
     }
     
     /// Get the blast resistance of this block
@@ -76,8 +95,7 @@ impl Block {
         if self.is_air() {
             return 0.0;
         }
-        let props = types::BlockType::from_id(self.block_type).properties();
-        props.blast_resistance
+        self.properties().map_or(0.0, |props| props.blast_resistance)
     }
     
     /// Get the hardness of this block
@@ -85,8 +103,7 @@ impl Block {
         if self.is_air() {
             return 0.0;
         }
-        let props = types::BlockType::from_id(self.block_type).properties();
-        props.hardness
+        self.properties().map_or(0.0, |props| props.hardness)
     }
     
     /// Pack the block into a single u32
@@ -175,5 +192,4 @@ impl BlockFace {
             BlockFace::West,
         ]
     }
-} 
-} 
\ No newline at end of file
+}
@@ -1,11 +1,11 @@
 use serde::{Serialize, Deserialize};
-use super::types::BlockType;
+use crate::item::ToolTier;
 
 /// Properties of a block type
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockProperties {
     /// The name of the block
-    pub name: &'static str,
+    pub name: String,
     /// Whether the block is solid (can be collided with)
     pub solid: bool,
     /// Whether the block is transparent (light passes through)
@@ -21,7 +21,11 @@ pub struct BlockProperties {
     /// The hardness of the block (time to break)
     pub hardness: f32,
     /// The preferred tool type for breaking this block
-    pub tool_type: Option<&'static str>,
+    pub tool_type: Option<String>,
+    /// Whether the block only drops items when broken with its preferred tool
+    pub requires_tool: bool,
+    /// The lowest tier of the preferred tool the block drops items for
+    pub tool_tier: Option<ToolTier>,
     /// Whether the block can catch fire
     pub flammable: bool,
     /// The items dropped when the block is broken
@@ -0,0 +1,275 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use crate::item::{ToolTier, FIRST_ITEM_ID};
+use super::properties::BlockProperties;
+use super::types::BlockType;
+
+/// The built-in block definitions, a copy of which ships as the default `config/blocks.json`
+const BUILTIN_BLOCKS: &str = include_str!("blocks.json");
+
+/// The highest light level a block can give off
+const MAX_LIGHT_EMISSION: u8 = 15;
+
+/// The registry used by the game, set once at startup
+static GLOBAL: OnceLock<BlockRegistry> = OnceLock::new();
+
+/// A block as written in a block definitions file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockDefinition {
+    /// The numeric ID the block is stored as
+    pub id: u16,
+    /// The name of the block
+    pub name: String,
+    /// Whether the block is solid (can be collided with)
+    pub solid: bool,
+    /// Whether the block is transparent (light passes through)
+    pub transparent: bool,
+    /// Whether the block is a fluid
+    pub fluid: bool,
+    /// Whether the block is affected by gravity
+    #[serde(default)]
+    pub gravity_affected: bool,
+    /// The amount of light emitted by the block (0-15)
+    #[serde(default)]
+    pub light: u8,
+    /// The resistance to explosions
+    #[serde(default)]
+    pub blast_resistance: f32,
+    /// The hardness of the block (negative for unbreakable)
+    #[serde(default)]
+    pub hardness: f32,
+    /// The preferred tool type for breaking this block
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Whether the block only drops items when broken with its preferred tool
+    #[serde(default)]
+    pub requires_tool: bool,
+    /// The lowest tier of the preferred tool the block drops items for
+    #[serde(default)]
+    pub tier: Option<ToolTier>,
+    /// Whether the block can catch fire
+    #[serde(default)]
+    pub flammable: bool,
+    /// The items dropped when the block is broken
+    #[serde(default)]
+    pub drops: Vec<BlockDrop>,
+}
+
+/// An item a block drops when broken
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockDrop {
+    /// The ID of the dropped item: a block type ID, or the ID of an item
+    /// that isn't a block, which the item registry checks
+    pub id: u16,
+    /// How many are dropped
+    pub count: u8,
+}
+
+/// The layout of a block definitions file
+#[derive(Debug, Serialize, Deserialize)]
+struct BlockFile {
+    blocks: Vec<BlockDefinition>,
+}
+
+/// The properties of every block the game knows about
+///
+/// Properties are kept in a table indexed by block type ID, so looking them
+/// up is a bounds check and nothing more. Every built-in `BlockType` must be
+/// defined; IDs past those are free for new blocks, up to `FIRST_ITEM_ID`
+/// where the IDs of items that aren't blocks begin.
+#[derive(Debug, Clone)]
+pub struct BlockRegistry {
+    /// The properties of each block, indexed by block type ID
+    table: Vec<Option<BlockProperties>>,
+}
+
+impl BlockRegistry {
+    /// Builds a registry from a list of definitions
+    pub fn new(definitions: Vec<BlockDefinition>) -> Result<Self> {
+        let ids: HashSet<u16> = definitions.iter().map(|definition| definition.id).collect();
+        let size = definitions.iter().map(|definition| definition.id as usize + 1).max().unwrap_or(0);
+        let mut table = vec![None; size];
+
+        for definition in definitions {
+            if definition.id >= FIRST_ITEM_ID {
+                return Err(anyhow!("Block ID {} is not below {}, where item IDs begin", definition.id, FIRST_ITEM_ID));
+            }
+            if table[definition.id as usize].is_some() {
+                return Err(anyhow!("Block ID {} is defined more than once", definition.id));
+            }
+            if definition.name.is_empty() {
+                return Err(anyhow!("Block {} has no name", definition.id));
+            }
+            if definition.light > MAX_LIGHT_EMISSION {
+                return Err(anyhow!("Block '{}' gives off more than {} light", definition.name, MAX_LIGHT_EMISSION));
+            }
+            if !definition.blast_resistance.is_finite() || definition.blast_resistance < 0.0 {
+                return Err(anyhow!("Block '{}' has an invalid blast resistance", definition.name));
+            }
+            if (definition.requires_tool || definition.tier.is_some()) && definition.tool.is_none() {
+                return Err(anyhow!("Block '{}' requires a tool but doesn't say which", definition.name));
+            }
+            let unknown = |drop: &&BlockDrop| drop.id < FIRST_ITEM_ID && !ids.contains(&drop.id);
+            if let Some(drop) = definition.drops.iter().find(unknown) {
+                return Err(anyhow!("Block '{}' drops unknown block {}", definition.name, drop.id));
+            }
+
+            table[definition.id as usize] = Some(BlockProperties {
+                name: definition.name,
+                solid: definition.solid,
+                transparent: definition.transparent,
+                fluid: definition.fluid,
+                gravity_affected: definition.gravity_affected,
+                light_emission: definition.light,
+                blast_resistance: definition.blast_resistance,
+                hardness: definition.hardness,
+                tool_type: definition.tool,
+                requires_tool: definition.requires_tool || definition.tier.is_some(),
+                tool_tier: definition.tier,
+                flammable: definition.flammable,
+                drops: definition.drops.iter().map(|drop| (drop.id, drop.count)).collect(),
+            });
+        }
+
+        if let Some(missing) = BlockType::all().into_iter().find(|block_type| !ids.contains(&(*block_type as u16))) {
+            return Err(anyhow!("Built-in block {:?} ({}) is not defined", missing, missing as u16));
+        }
+
+        Ok(Self { table })
+    }
+
+    /// Parses a registry from the contents of a block definitions file
+    pub fn from_json(json: &str) -> Result<Self> {
+        let file: BlockFile = serde_json::from_str(json)?;
+        Self::new(file.blocks)
+    }
+
+    /// Loads a registry from a block definitions file
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read block definitions from {}", path.display()))?;
+        Self::from_json(&json).with_context(|| format!("Invalid block definitions in {}", path.display()))
+    }
+
+    /// Returns the registry of the blocks built into the game
+    pub fn builtin() -> Result<Self> {
+        Self::from_json(BUILTIN_BLOCKS).context("Invalid built-in block definitions")
+    }
+
+    /// Returns the registry the game uses
+    ///
+    /// This is the registry passed to `install`, or the built-in blocks if
+    /// nothing was installed before the first lookup. The built-in blocks are
+    /// checked by the tests, so building them here can't fail.
+    pub fn global() -> &'static BlockRegistry {
+        GLOBAL.get_or_init(|| Self::builtin().expect("built-in block definitions are valid"))
+    }
+
+    /// Makes a registry the one the game uses
+    ///
+    /// This has to happen before any block properties are looked up, since
+    /// the registry can't change once it's in use.
+    pub fn install(registry: BlockRegistry) -> Result<()> {
+        GLOBAL
+            .set(registry)
+            .map_err(|_| anyhow!("A block registry is already in use"))
+    }
+
+    /// Returns the properties of a block type, or None if the ID is unknown
+    pub fn get(&self, id: u16) -> Option<&BlockProperties> {
+        self.table.get(id as usize).and_then(Option::as_ref)
+    }
+
+    /// Returns every defined block type ID with its properties
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &BlockProperties)> {
+        self.table
+            .iter()
+            .enumerate()
+            .filter_map(|(id, properties)| properties.as_ref().map(|properties| (id as u16, properties)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Returns the definitions of the built-in blocks
+    fn builtin_definitions() -> Vec<BlockDefinition> {
+        serde_json::from_str::<BlockFile>(BUILTIN_BLOCKS).unwrap().blocks
+    }
+
+    /// Returns a definition by block ID
+    fn definition(definitions: &mut [BlockDefinition], id: BlockType) -> &mut BlockDefinition {
+        definitions.iter_mut().find(|definition| definition.id == id as u16).unwrap()
+    }
+
+    /// Builds a registry from definitions that should be rejected, returning the error message
+    fn rejection(definitions: Vec<BlockDefinition>) -> String {
+        BlockRegistry::new(definitions).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn shipped_config_matches_builtin_blocks() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../config/blocks.json");
+        assert_eq!(fs::read_to_string(path).unwrap(), BUILTIN_BLOCKS);
+    }
+
+    #[test]
+    fn builtin_blocks_are_valid() {
+        let registry = BlockRegistry::builtin().unwrap();
+        let stone = registry.get(BlockType::Stone as u16).unwrap();
+        assert_eq!(stone.name, "Stone");
+        assert!(registry.get(u16::MAX).is_none());
+    }
+
+    #[test]
+    fn rejects_duplicate_ids() {
+        let mut definitions = builtin_definitions();
+        let mut copy = definition(&mut definitions, BlockType::Dirt).clone();
+        copy.name = "Other Dirt".into();
+        definitions.push(copy);
+        assert!(rejection(definitions).contains("defined more than once"));
+    }
+
+    #[test]
+    fn rejects_missing_builtin_types() {
+        let mut definitions = builtin_definitions();
+        definitions.retain(|definition| definition.id != BlockType::Torch as u16);
+        assert!(rejection(definitions).contains("Torch"));
+    }
+
+    #[test]
+    fn rejects_unknown_drops() {
+        let mut definitions = builtin_definitions();
+        definition(&mut definitions, BlockType::Stone).drops.push(BlockDrop { id: 200, count: 1 });
+        assert!(rejection(definitions).contains("unknown block 200"));
+
+        // Drops past the block IDs are items, which the item registry checks
+        let mut definitions = builtin_definitions();
+        definition(&mut definitions, BlockType::Stone).drops.push(BlockDrop { id: 999, count: 1 });
+        assert!(BlockRegistry::new(definitions).is_ok());
+    }
+
+    #[test]
+    fn rejects_ids_reserved_for_items() {
+        let mut definitions = builtin_definitions();
+        let mut copy = definition(&mut definitions, BlockType::Dirt).clone();
+        copy.id = FIRST_ITEM_ID;
+        definitions.push(copy);
+        assert!(rejection(definitions).contains("item IDs begin"));
+    }
+
+    #[test]
+    fn rejects_light_above_maximum() {
+        let mut definitions = builtin_definitions();
+        definition(&mut definitions, BlockType::Torch).light = MAX_LIGHT_EMISSION + 1;
+        assert!(rejection(definitions).contains("more than 15 light"));
+    }
+}
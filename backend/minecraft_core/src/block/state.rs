@@ -0,0 +1,568 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::str::FromStr;
+use super::registry::BlockRegistry;
+use super::types::BlockType;
+use super::Block;
+
+/// The axis a block such as a log lies along
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    /// Returns the name of the axis used in block state strings
+    pub fn name(&self) -> &'static str {
+        match self {
+            Axis::X => "x",
+            Axis::Y => "y",
+            Axis::Z => "z",
+        }
+    }
+}
+
+/// The direction a block such as a torch or chest faces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Facing {
+    North,
+    South,
+    East,
+    West,
+    Up,
+}
+
+impl Facing {
+    /// Returns the name of the direction used in block state strings
+    pub fn name(&self) -> &'static str {
+        match self {
+            Facing::North => "north",
+            Facing::South => "south",
+            Facing::East => "east",
+            Facing::West => "west",
+            Facing::Up => "up",
+        }
+    }
+}
+
+/// The value of one property of a block's state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateValue {
+    Axis(Axis),
+    Facing(Facing),
+    Int(u8),
+    Bool(bool),
+}
+
+impl fmt::Display for StateValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateValue::Axis(axis) => f.write_str(axis.name()),
+            StateValue::Facing(facing) => f.write_str(facing.name()),
+            StateValue::Int(value) => write!(f, "{}", value),
+            StateValue::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// The values a block state property can take
+///
+/// The first value is the default, stored as zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateKind {
+    /// One of the listed axes
+    Axis(&'static [Axis]),
+    /// One of the listed directions
+    Facing(&'static [Facing]),
+    /// A number from zero up to and including the maximum
+    Int(u8),
+    /// False or true
+    Bool,
+}
+
+/// A property of a block's state, such as the axis of a log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateProperty {
+    /// The name of the property used in block state strings
+    pub name: &'static str,
+    /// The values the property can take
+    pub kind: StateKind,
+}
+
+impl StateProperty {
+    /// Returns how many values the property can take
+    pub fn count(&self) -> u16 {
+        match self.kind {
+            StateKind::Axis(axes) => axes.len() as u16,
+            StateKind::Facing(directions) => directions.len() as u16,
+            StateKind::Int(max) => max as u16 + 1,
+            StateKind::Bool => 2,
+        }
+    }
+
+    /// Returns how many bits of metadata the property takes up
+    pub fn bits(&self) -> u32 {
+        self.count().next_power_of_two().trailing_zeros()
+    }
+
+    /// Returns the value stored as the specified index, or None if it's out of range
+    pub fn value(&self, index: u16) -> Option<StateValue> {
+        match self.kind {
+            StateKind::Axis(axes) => axes.get(index as usize).map(|axis| StateValue::Axis(*axis)),
+            StateKind::Facing(directions) => directions.get(index as usize).map(|facing| StateValue::Facing(*facing)),
+            StateKind::Int(max) => (index <= max as u16).then_some(StateValue::Int(index as u8)),
+            StateKind::Bool => (index <= 1).then_some(StateValue::Bool(index == 1)),
+        }
+    }
+
+    /// Returns the index a value is stored as, or None if the property can't take it
+    pub fn index(&self, value: StateValue) -> Option<u16> {
+        match (self.kind, value) {
+            (StateKind::Axis(axes), StateValue::Axis(axis)) => axes.iter().position(|a| *a == axis).map(|i| i as u16),
+            (StateKind::Facing(directions), StateValue::Facing(facing)) => {
+                directions.iter().position(|d| *d == facing).map(|i| i as u16)
+            }
+            (StateKind::Int(max), StateValue::Int(value)) => (value <= max).then_some(value as u16),
+            (StateKind::Bool, StateValue::Bool(value)) => Some(value as u16),
+            _ => None,
+        }
+    }
+
+    /// Parses a value of the property from a block state string
+    pub fn parse(&self, text: &str) -> Option<StateValue> {
+        (0..self.count())
+            .filter_map(|index| self.value(index))
+            .find(|value| value.to_string() == text)
+    }
+}
+
+/// The directions a block standing on the floor or hanging on a wall can face
+const TORCH_FACING: &[Facing] = &[Facing::Up, Facing::North, Facing::South, Facing::West, Facing::East];
+
+/// The directions a block placed facing the player can face
+const HORIZONTAL_FACING: &[Facing] = &[Facing::North, Facing::South, Facing::West, Facing::East];
+
+/// The state of logs
+const LOG_STATE: &[StateProperty] = &[StateProperty {
+    name: "axis",
+    kind: StateKind::Axis(&[Axis::Y, Axis::X, Axis::Z]),
+}];
+
+/// The state of torches
+const TORCH_STATE: &[StateProperty] = &[StateProperty {
+    name: "facing",
+    kind: StateKind::Facing(TORCH_FACING),
+}];
+
+/// The state of chests and furnaces
+const HORIZONTAL_STATE: &[StateProperty] = &[StateProperty {
+    name: "facing",
+    kind: StateKind::Facing(HORIZONTAL_FACING),
+}];
+
+/// The state of water and lava: how far the fluid has flowed sideways from a
+/// source, and whether it's falling from the block above
+const FLUID_STATE: &[StateProperty] = &[
+    StateProperty {
+        name: "distance",
+        kind: StateKind::Int(7),
+    },
+    StateProperty {
+        name: "falling",
+        kind: StateKind::Bool,
+    },
+];
+
+/// The state of leaves: whether they were placed by a player, which keeps
+/// them from decaying away from logs
+const LEAVES_STATE: &[StateProperty] = &[StateProperty {
+    name: "persistent",
+    kind: StateKind::Bool,
+}];
+
+/// The state of fire
+const FIRE_STATE: &[StateProperty] = &[StateProperty {
+    name: "age",
+    kind: StateKind::Int(15),
+}];
+
+impl BlockType {
+    /// Returns the properties making up the state of this block type, in the
+    /// order they're packed into metadata from the lowest bit up
+    pub fn state_properties(&self) -> &'static [StateProperty] {
+        match self {
+            BlockType::OakLog | BlockType::BirchLog | BlockType::SpruceLog => LOG_STATE,
+            BlockType::OakLeaves | BlockType::BirchLeaves | BlockType::SpruceLeaves => LEAVES_STATE,
+            BlockType::Torch => TORCH_STATE,
+            BlockType::Chest | BlockType::Furnace => HORIZONTAL_STATE,
+            BlockType::Water | BlockType::Lava => FLUID_STATE,
+            BlockType::Fire => FIRE_STATE,
+            _ => &[],
+        }
+    }
+}
+
+/// A block with its state properties unpacked from its metadata
+///
+/// Blocks from the registry that aren't built-in types have no state
+/// properties. The string form is the block's name in snake case followed by
+/// its properties, such as `oak_log[axis=y]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlockState {
+    /// The type of the block
+    block_type: u16,
+    /// The value of each state property, in the order the block type declares them
+    values: Vec<StateValue>,
+}
+
+impl BlockState {
+    /// Creates the default state of a block type
+    pub fn new(block_type: BlockType) -> Self {
+        let values = block_type
+            .state_properties()
+            .iter()
+            .filter_map(|property| property.value(0))
+            .collect();
+        Self {
+            block_type: block_type as u16,
+            values,
+        }
+    }
+
+    /// Unpacks the state of a block, checking its metadata is valid for its type
+    pub fn from_block(block: Block) -> Result<Self> {
+        if BlockRegistry::global().get(block.block_type).is_none() {
+            return Err(anyhow!("Unknown block type {}", block.block_type));
+        }
+
+        let properties = state_properties(block.block_type);
+        let mut values = Vec::with_capacity(properties.len());
+        let mut shift = 0;
+        for property in properties {
+            let mask = (1u32 << property.bits()) - 1;
+            let index = ((block.metadata as u32 >> shift) & mask) as u16;
+            let value = property.value(index).ok_or_else(|| {
+                anyhow!("Invalid {} {} for block type {}", property.name, index, block.block_type)
+            })?;
+            values.push(value);
+            shift += property.bits();
+        }
+
+        if shift < 16 && block.metadata >> shift != 0 {
+            return Err(anyhow!("Invalid metadata {} for block type {}", block.metadata, block.block_type));
+        }
+
+        Ok(Self {
+            block_type: block.block_type,
+            values,
+        })
+    }
+
+    /// Returns the ID of the block type
+    pub fn block_type(&self) -> u16 {
+        self.block_type
+    }
+
+    /// Returns the properties making up the state
+    pub fn properties(&self) -> &'static [StateProperty] {
+        state_properties(self.block_type)
+    }
+
+    /// Returns the value of a property, or None if the block doesn't have it
+    pub fn get(&self, name: &str) -> Option<StateValue> {
+        self.properties()
+            .iter()
+            .position(|property| property.name == name)
+            .map(|i| self.values[i])
+    }
+
+    /// Changes the value of a property, checking the block has it and can take the value
+    pub fn set(&mut self, name: &str, value: StateValue) -> Result<()> {
+        let i = self
+            .properties()
+            .iter()
+            .position(|property| property.name == name)
+            .ok_or_else(|| anyhow!("{} has no property '{}'", self.key(), name))?;
+        if self.properties()[i].index(value).is_none() {
+            return Err(anyhow!("{} can't have {}={}", self.key(), name, value));
+        }
+
+        self.values[i] = value;
+        Ok(())
+    }
+
+    /// Returns the state with a property changed, checking as `set` does
+    pub fn with(mut self, name: &str, value: StateValue) -> Result<Self> {
+        self.set(name, value)?;
+        Ok(self)
+    }
+
+    /// Returns the axis the block lies along, if it has one
+    pub fn axis(&self) -> Option<Axis> {
+        self.values.iter().find_map(|value| match value {
+            StateValue::Axis(axis) => Some(*axis),
+            _ => None,
+        })
+    }
+
+    /// Returns the direction the block faces, if it has one
+    pub fn facing(&self) -> Option<Facing> {
+        self.values.iter().find_map(|value| match value {
+            StateValue::Facing(facing) => Some(*facing),
+            _ => None,
+        })
+    }
+
+    /// Returns how far a fluid has flowed from its source, if the block is one
+    pub fn distance(&self) -> Option<u8> {
+        match self.get("distance") {
+            Some(StateValue::Int(distance)) => Some(distance),
+            _ => None,
+        }
+    }
+
+    /// Returns whether a fluid is falling from the block above, if the block is one
+    pub fn falling(&self) -> Option<bool> {
+        match self.get("falling") {
+            Some(StateValue::Bool(falling)) => Some(falling),
+            _ => None,
+        }
+    }
+
+    /// Returns how old a fire is, if the block is one
+    pub fn age(&self) -> Option<u8> {
+        match self.get("age") {
+            Some(StateValue::Int(age)) => Some(age),
+            _ => None,
+        }
+    }
+
+    /// Returns whether leaves were placed by a player, if the block is leaves
+    pub fn persistent(&self) -> Option<bool> {
+        match self.get("persistent") {
+            Some(StateValue::Bool(persistent)) => Some(persistent),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the block type used in block state strings
+    fn key(&self) -> String {
+        BlockRegistry::global()
+            .get(self.block_type)
+            .map_or_else(|| format!("block_{}", self.block_type), |properties| block_key(&properties.name))
+    }
+}
+
+impl From<&BlockState> for Block {
+    fn from(state: &BlockState) -> Self {
+        let mut metadata = 0u32;
+        let mut shift = 0;
+        for (property, value) in state.properties().iter().zip(&state.values) {
+            metadata |= (property.index(*value).unwrap_or(0) as u32) << shift;
+            shift += property.bits();
+        }
+        Block::with_metadata(state.block_type, metadata as u16)
+    }
+}
+
+impl From<BlockState> for Block {
+    fn from(state: BlockState) -> Self {
+        Block::from(&state)
+    }
+}
+
+impl fmt::Display for BlockState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.key())?;
+        if self.values.is_empty() {
+            return Ok(());
+        }
+
+        f.write_str("[")?;
+        for (i, (property, value)) in self.properties().iter().zip(&self.values).enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}={}", property.name, value)?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl FromStr for BlockState {
+    type Err = anyhow::Error;
+
+    /// Parses a block state string such as `oak_log[axis=y]`
+    ///
+    /// Properties left out keep their default values.
+    fn from_str(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let (key, properties) = match text.split_once('[') {
+            Some((key, rest)) => {
+                let properties = rest
+                    .strip_suffix(']')
+                    .ok_or_else(|| anyhow!("Missing ']' in block state '{}'", text))?;
+                (key.trim(), Some(properties))
+            }
+            None => (text, None),
+        };
+
+        let (block_type, _) = BlockRegistry::global()
+            .iter()
+            .find(|(_, properties)| block_key(&properties.name) == key)
+            .ok_or_else(|| anyhow!("Unknown block '{}'", key))?;
+        let mut state = BlockState::from_block(Block::new(block_type))?;
+
+        let Some(properties) = properties else {
+            return Ok(state);
+        };
+        let mut seen = Vec::new();
+        for pair in properties.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair
+                .split_once('=')
+                .map(|(name, value)| (name.trim(), value.trim()))
+                .ok_or_else(|| anyhow!("Expected name=value in block state '{}'", text))?;
+            if seen.contains(&name) {
+                return Err(anyhow!("Property '{}' is given more than once in '{}'", name, text));
+            }
+            seen.push(name);
+
+            let property = state
+                .properties()
+                .iter()
+                .find(|property| property.name == name)
+                .ok_or_else(|| anyhow!("{} has no property '{}'", key, name))?;
+            let value = property
+                .parse(value)
+                .ok_or_else(|| anyhow!("{} can't have {}={}", key, name, value))?;
+            state.set(name, value)?;
+        }
+
+        Ok(state)
+    }
+}
+
+/// Returns the state properties of a block type ID, none for types that aren't built in
+fn state_properties(block_type: u16) -> &'static [StateProperty] {
+    BlockType::from_id(block_type).map_or(&[], |block_type| block_type.state_properties())
+}
+
+/// Turns a block name such as "Oak Log" into the form used in block state strings
+fn block_key(name: &str) -> String {
+    name.trim().to_lowercase().replace(' ', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns every metadata value a block type's state properties can pack into
+    fn packable_metadata(block_type: BlockType) -> impl Iterator<Item = u16> {
+        let bits: u32 = block_type.state_properties().iter().map(|property| property.bits()).sum();
+        0..1u16 << bits
+    }
+
+    #[test]
+    fn every_state_round_trips() {
+        for block_type in BlockType::all() {
+            let expected: u16 = block_type.state_properties().iter().map(|property| property.count()).product();
+            let mut valid = 0;
+
+            for metadata in packable_metadata(block_type) {
+                let block = Block::with_metadata(block_type, metadata);
+                let Ok(state) = BlockState::from_block(block) else {
+                    continue;
+                };
+                valid += 1;
+
+                assert_eq!(Block::from(&state), block);
+                let parsed: BlockState = state.to_string().parse().unwrap();
+                assert_eq!(parsed, state, "{} did not parse back", state);
+            }
+
+            assert_eq!(valid, expected, "{:?} has the wrong number of states", block_type);
+        }
+    }
+
+    #[test]
+    fn default_state_is_zero_metadata() {
+        for block_type in BlockType::all() {
+            assert_eq!(Block::from(BlockState::new(block_type)), Block::new(block_type));
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        // Logs have three axes, torches five directions and chests four
+        assert!(BlockState::from_block(Block::with_metadata(BlockType::OakLog, 3)).is_err());
+        assert!(BlockState::from_block(Block::with_metadata(BlockType::Torch, 5)).is_err());
+        assert!(BlockState::from_block(Block::with_metadata(BlockType::Torch, 7)).is_err());
+
+        let state = BlockState::new(BlockType::Water);
+        assert!(state.clone().with("distance", StateValue::Int(8)).is_err());
+        assert!(state.clone().with("distance", StateValue::Bool(true)).is_err());
+        assert!(state.with("age", StateValue::Int(1)).is_err());
+    }
+
+    #[test]
+    fn rejects_extra_metadata_bits() {
+        assert!(BlockState::from_block(Block::with_metadata(BlockType::Stone, 1)).is_err());
+        assert!(BlockState::from_block(Block::with_metadata(BlockType::OakLog, 1 << 2)).is_err());
+        assert!(BlockState::from_block(Block::with_metadata(BlockType::Water, 1 << 4)).is_err());
+        assert!(BlockState::from_block(Block::with_metadata(BlockType::Fire, u16::MAX)).is_err());
+    }
+
+    #[test]
+    fn fluid_state_splits_distance_and_falling() {
+        let state = BlockState::from_block(Block::with_metadata(BlockType::Water, 0b1011)).unwrap();
+        assert_eq!(state.distance(), Some(3));
+        assert_eq!(state.falling(), Some(true));
+        assert_eq!(state.to_string(), "water[distance=3,falling=true]");
+
+        let parsed: BlockState = "lava[falling=true]".parse().unwrap();
+        assert_eq!(Block::from(parsed), Block::with_metadata(BlockType::Lava, 0b1000));
+    }
+
+    #[test]
+    fn leaves_default_to_decaying() {
+        let state = BlockState::from_block(Block::new(BlockType::OakLeaves)).unwrap();
+        assert_eq!(state.persistent(), Some(false));
+
+        let parsed: BlockState = "birch_leaves[persistent=true]".parse().unwrap();
+        assert_eq!(Block::from(parsed), Block::with_metadata(BlockType::BirchLeaves, 1));
+    }
+
+    #[test]
+    fn fire_state_holds_its_age() {
+        let state = BlockState::new(BlockType::Fire).with("age", StateValue::Int(12)).unwrap();
+        assert_eq!(state.age(), Some(12));
+        assert_eq!(Block::from(state), Block::with_metadata(BlockType::Fire, 12));
+        assert_eq!(BlockState::new(BlockType::Water).age(), None);
+    }
+
+    #[test]
+    fn parses_with_defaults_and_spacing() {
+        let state: BlockState = " oak_log [ axis = x ] ".parse().unwrap();
+        assert_eq!(state.axis(), Some(Axis::X));
+
+        let state: BlockState = "furnace".parse().unwrap();
+        assert_eq!(state.facing(), Some(Facing::North));
+    }
+
+    #[test]
+    fn rejects_malformed_strings() {
+        for text in [
+            "oak_log[axis=x,axis=y]",
+            "oak_log[axis=x",
+            "oak_log[axis]",
+            "oak_log[axis=w]",
+            "oak_log[facing=up]",
+            "stone[axis=x]",
+            "not_a_block",
+        ] {
+            assert!(text.parse::<BlockState>().is_err(), "{} parsed", text);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use super::properties::BlockProperties;
+use super::registry::BlockRegistry;
 
 /// All block types available in the game
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -53,457 +54,18 @@ pub enum BlockType {
     Chest = 71,
     Crafting = 72,
     Furnace = 73,
+    Fire = 74,
 }
 
 impl BlockType {
     /// Returns the block properties for this block type
-    pub fn properties(&self) -> BlockProperties {
-        match self {
-            // Basic blocks
-            BlockType::Air => BlockProperties {
-                name: "Air",
-                solid: false,
-                transparent: true,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 0.0,
-                hardness: 0.0,
-                tool_type: None,
-                flammable: false,
-                drops: None,
-            },
-            BlockType::Stone => BlockProperties {
-                name: "Stone",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 6.0,
-                hardness: 1.5,
-                tool_type: Some("pickaxe"),
-                flammable: false,
-                drops: Some(BlockType::Cobblestone),
-            },
-            BlockType::Dirt => BlockProperties {
-                name: "Dirt",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 0.5,
-                hardness: 0.5,
-                tool_type: Some("shovel"),
-                flammable: false,
-                drops: Some(BlockType::Dirt),
-            },
-            BlockType::Grass => BlockProperties {
-                name: "Grass Block",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 0.6,
-                hardness: 0.6,
-                tool_type: Some("shovel"),
-                flammable: false,
-                drops: Some(BlockType::Dirt),
-            },
-            BlockType::Sand => BlockProperties {
-                name: "Sand",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: true,
-                light_emission: 0,
-                blast_resistance: 0.5,
-                hardness: 0.5,
-                tool_type: Some("shovel"),
-                flammable: false,
-                drops: Some(BlockType::Sand),
-            },
-            BlockType::Gravel => BlockProperties {
-                name: "Gravel",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: true,
-                light_emission: 0,
-                blast_resistance: 0.6,
-                hardness: 0.6,
-                tool_type: Some("shovel"),
-                flammable: false,
-                drops: Some(BlockType::Gravel),
-            },
-            BlockType::Bedrock => BlockProperties {
-                name: "Bedrock",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 3600000.0,
-                hardness: -1.0, // Unbreakable
-                tool_type: None,
-                flammable: false,
-                drops: None,
-            },
-            
-            // Wood types
-            BlockType::OakLog => BlockProperties {
-                name: "Oak Log",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 2.0,
-                hardness: 2.0,
-                tool_type: Some("axe"),
-                flammable: true,
-                drops: Some(BlockType::OakLog),
-            },
-            BlockType::BirchLog => BlockProperties {
-                name: "Birch Log",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 2.0,
-                hardness: 2.0,
-                tool_type: Some("axe"),
-                flammable: true,
-                drops: Some(BlockType::BirchLog),
-            },
-            BlockType::SpruceLog => BlockProperties {
-                name: "Spruce Log",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 2.0,
-                hardness: 2.0,
-                tool_type: Some("axe"),
-                flammable: true,
-                drops: Some(BlockType::SpruceLog),
-            },
-            BlockType::OakPlanks => BlockProperties {
-                name: "Oak Planks",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 3.0,
-                hardness: 2.0,
-                tool_type: Some("axe"),
-                flammable: true,
-                drops: Some(BlockType::OakPlanks),
-            },
-            BlockType::BirchPlanks => BlockProperties {
-                name: "Birch Planks",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 3.0,
-                hardness: 2.0,
-                tool_type: Some("axe"),
-                flammable: true,
-                drops: Some(BlockType::BirchPlanks),
-            },
-            BlockType::SprucePlanks => BlockProperties {
-                name: "Spruce Planks",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 3.0,
-                hardness: 2.0,
-                tool_type: Some("axe"),
-                flammable: true,
-                drops: Some(BlockType::SprucePlanks),
-            },
-            
-            // Leaves
-            BlockType::OakLeaves => BlockProperties {
-                name: "Oak Leaves",
-                solid: true,
-                transparent: true,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 0.2,
-                hardness: 0.2,
-                tool_type: Some("shears"),
-                flammable: true,
-                drops: None, // Can drop saplings randomly
-            },
-            BlockType::BirchLeaves => BlockProperties {
-                name: "Birch Leaves",
-                solid: true,
-                transparent: true,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 0.2,
-                hardness: 0.2,
-                tool_type: Some("shears"),
-                flammable: true,
-                drops: None, // Can drop saplings randomly
-            },
-            BlockType::SpruceLeaves => BlockProperties {
-                name: "Spruce Leaves",
-                solid: true,
-                transparent: true,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 0.2,
-                hardness: 0.2,
-                tool_type: Some("shears"),
-                flammable: true,
-                drops: None, // Can drop saplings randomly
-            },
-            
-            // Ores and minerals
-            BlockType::CoalOre => BlockProperties {
-                name: "Coal Ore",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 3.0,
-                hardness: 3.0,
-                tool_type: Some("pickaxe"),
-                flammable: false,
-                drops: Some(BlockType::CoalOre), // Actually drops coal item
-            },
-            BlockType::IronOre => BlockProperties {
-                name: "Iron Ore",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 3.0,
-                hardness: 3.0,
-                tool_type: Some("pickaxe"),
-                flammable: false,
-                drops: Some(BlockType::IronOre), // Actually drops iron ore
-            },
-            BlockType::GoldOre => BlockProperties {
-                name: "Gold Ore",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 3.0,
-                hardness: 3.0,
-                tool_type: Some("pickaxe"),
-                flammable: false,
-                drops: Some(BlockType::GoldOre), // Actually drops gold ore
-            },
-            BlockType::RedstoneOre => BlockProperties {
-                name: "Redstone Ore",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 3.0,
-                hardness: 3.0,
-                tool_type: Some("pickaxe"),
-                flammable: false,
-                drops: Some(BlockType::RedstoneOre), // Actually drops redstone dust
-            },
-            BlockType::DiamondOre => BlockProperties {
-                name: "Diamond Ore",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 3.0,
-                hardness: 3.0,
-                tool_type: Some("pickaxe"),
-                flammable: false,
-                drops: Some(BlockType::DiamondOre), // Actually drops diamond
-            },
-            
-            // Processed materials
-            BlockType::Glass => BlockProperties {
-                name: "Glass",
-                solid: true,
-                transparent: true,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 0.3,
-                hardness: 0.3,
-                tool_type: None,
-                flammable: false,
-                drops: None, // Doesn't drop anything
-            },
-            BlockType::Brick => BlockProperties {
-                name: "Bricks",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 6.0,
-                hardness: 2.0,
-                tool_type: Some("pickaxe"),
-                flammable: false,
-                drops: Some(BlockType::Brick),
-            },
-            BlockType::Cobblestone => BlockProperties {
-                name: "Cobblestone",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 6.0,
-                hardness: 2.0,
-                tool_type: Some("pickaxe"),
-                flammable: false,
-                drops: Some(BlockType::Cobblestone),
-            },
-            BlockType::StoneBrick => BlockProperties {
-                name: "Stone Bricks",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 6.0,
-                hardness: 1.5,
-                tool_type: Some("pickaxe"),
-                flammable: false,
-                drops: Some(BlockType::StoneBrick),
-            },
-            
-            // Fluids
-            BlockType::Water => BlockProperties {
-                name: "Water",
-                solid: false,
-                transparent: true,
-                fluid: true,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 100.0,
-                hardness: 100.0,
-                tool_type: None,
-                flammable: false,
-                drops: None,
-            },
-            BlockType::Lava => BlockProperties {
-                name: "Lava",
-                solid: false,
-                transparent: true,
-                fluid: true,
-                gravity_affected: false,
-                light_emission: 15,
-                blast_resistance: 100.0,
-                hardness: 100.0,
-                tool_type: None,
-                flammable: false,
-                drops: None,
-            },
-            
-            // Plants
-            BlockType::TallGrass => BlockProperties {
-                name: "Tall Grass",
-                solid: false,
-                transparent: true,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 0.0,
-                hardness: 0.0,
-                tool_type: None,
-                flammable: true,
-                drops: None,
-            },
-            BlockType::Flower => BlockProperties {
-                name: "Flower",
-                solid: false,
-                transparent: true,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 0.0,
-                hardness: 0.0,
-                tool_type: None,
-                flammable: true,
-                drops: Some(BlockType::Flower),
-            },
-            
-            // Special
-            BlockType::Torch => BlockProperties {
-                name: "Torch",
-                solid: false,
-                transparent: true,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 14,
-                blast_resistance: 0.0,
-                hardness: 0.0,
-                tool_type: None,
-                flammable: false,
-                drops: Some(BlockType::Torch),
-            },
-            BlockType::Chest => BlockProperties {
-                name: "Chest",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 2.5,
-                hardness: 2.5,
-                tool_type: Some("axe"),
-                flammable: true,
-                drops: Some(BlockType::Chest),
-            },
-            BlockType::Crafting => BlockProperties {
-                name: "Crafting Table",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 2.5,
-                hardness: 2.5,
-                tool_type: Some("axe"),
-                flammable: true,
-                drops: Some(BlockType::Crafting),
-            },
-            BlockType::Furnace => BlockProperties {
-                name: "Furnace",
-                solid: true,
-                transparent: false,
-                fluid: false,
-                gravity_affected: false,
-                light_emission: 0,
-                blast_resistance: 3.5,
-                hardness: 3.5,
-                tool_type: Some("pickaxe"),
-                flammable: false,
-                drops: Some(BlockType::Furnace),
-            },
-        }
+    ///
+    /// Panics if the installed block registry doesn't define the type, which
+    /// `BlockRegistry` refuses to allow.
+    pub fn properties(&self) -> &'static BlockProperties {
+        BlockRegistry::global()
+            .get(*self as u16)
+            .expect("block registry defines every built-in block type")
     }
     
     /// Returns the block type from a numeric ID
@@ -542,6 +104,7 @@ impl BlockType {
             71 => Some(BlockType::Chest),
             72 => Some(BlockType::Crafting),
             73 => Some(BlockType::Furnace),
+            74 => Some(BlockType::Fire),
             _ => None,
         }
     }
@@ -582,11 +145,18 @@ impl BlockType {
             BlockType::Chest,
             BlockType::Crafting,
             BlockType::Furnace,
+            BlockType::Fire,
         ]
     }
     
     /// Returns the name of the block type
     pub fn name(&self) -> &'static str {
-        self.properties().name
+        &self.properties().name
     }
-} 
\ No newline at end of file
+}
+
+impl From<BlockType> for u16 {
+    fn from(block_type: BlockType) -> Self {
+        block_type as u16
+    }
+}
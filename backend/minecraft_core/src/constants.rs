@@ -1,4 +1,4 @@
-/// Constants for the Minecraft clone
+//! Constants for the Minecraft clone
 
 /// The size of a chunk in blocks (x, y, z)
 pub const CHUNK_SIZE: (usize, usize, usize) = (16, 16, 16);
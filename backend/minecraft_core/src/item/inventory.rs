@@ -0,0 +1,490 @@
+use anyhow::{anyhow, Result};
+use serde::{Serialize, Deserialize};
+use std::ops::Range;
+use super::stack::ItemStack;
+
+/// The number of slots in a player's inventory, hotbar included
+pub const PLAYER_SLOTS: usize = 36;
+
+/// The number of slots in a player's hotbar, which come first in their inventory
+pub const HOTBAR_SLOTS: usize = 9;
+
+/// A fixed number of slots, each holding up to one item stack
+///
+/// This is the storage behind every container, such as a chest or a
+/// player's inventory. Stacks in a slot never go over their item's stack
+/// limit, and a slot with no items left is empty.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    /// The stack in each slot
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl Inventory {
+    /// Create an empty inventory with the specified number of slots
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: vec![None; size],
+        }
+    }
+
+    /// Returns the number of slots
+    pub fn size(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the stack in every slot
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    /// Returns the stack in a slot, or None if it's empty or out of range
+    pub fn get(&self, slot: usize) -> Option<&ItemStack> {
+        self.slots.get(slot).and_then(Option::as_ref)
+    }
+
+    /// Puts a stack in a slot, returning what was there before
+    pub fn set(&mut self, slot: usize, stack: Option<ItemStack>) -> Result<Option<ItemStack>> {
+        self.check_slot(slot)?;
+        if let Some(stack) = &stack {
+            if stack.count > stack.max_stack() {
+                return Err(anyhow!("A stack of item {} can't hold {} items", stack.item, stack.count));
+            }
+        }
+
+        let stack = stack.filter(|stack| !stack.is_empty());
+        Ok(std::mem::replace(&mut self.slots[slot], stack))
+    }
+
+    /// Empties a slot, returning the stack that was in it
+    pub fn take(&mut self, slot: usize) -> Result<Option<ItemStack>> {
+        self.check_slot(slot)?;
+        Ok(self.slots[slot].take())
+    }
+
+    /// Takes up to `count` items out of a slot, returning None if it was empty
+    pub fn split(&mut self, slot: usize, count: u8) -> Result<Option<ItemStack>> {
+        self.check_slot(slot)?;
+        let Some(stack) = &mut self.slots[slot] else {
+            return Ok(None);
+        };
+
+        let taken = stack.split(count);
+        if stack.is_empty() {
+            self.slots[slot] = None;
+        }
+        Ok(Some(taken).filter(|taken| !taken.is_empty()))
+    }
+
+    /// Adds a stack wherever it fits, returning any items left over
+    ///
+    /// Items go onto matching stacks first, then into empty slots, both in
+    /// slot order.
+    pub fn insert(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        self.insert_into(stack, 0..self.slots.len())
+    }
+
+    /// Moves the whole stack in one slot to another
+    ///
+    /// A stack of the same items there takes as many as fit and the rest
+    /// stays behind; a stack of different items swaps places with it.
+    pub fn move_stack(&mut self, from: usize, to: usize) -> Result<()> {
+        self.check_slot(from)?;
+        self.check_slot(to)?;
+        if from == to {
+            return Ok(());
+        }
+
+        let (source, target) = self.pair(from, to);
+        match (source.as_ref(), target.as_ref()) {
+            (Some(moving), Some(existing)) if !existing.can_stack_with(moving) => std::mem::swap(source, target),
+            _ => {
+                move_between(source, target, u8::MAX)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves up to `count` items from one slot to another
+    ///
+    /// The target slot has to be empty or hold the same items, and only as
+    /// many items as fit are moved. Returns how many moved.
+    pub fn move_items(&mut self, from: usize, to: usize, count: u8) -> Result<u8> {
+        self.check_slot(from)?;
+        self.check_slot(to)?;
+        if from == to {
+            return Ok(0);
+        }
+
+        let (source, target) = self.pair(from, to);
+        move_between(source, target, count)
+    }
+
+    /// Moves up to `count` items from a slot of this inventory to a slot of another
+    ///
+    /// This works the same way as `move_items`, for moving items between
+    /// containers, such as from a chest to a player.
+    pub fn transfer(&mut self, from: usize, target: &mut Inventory, to: usize, count: u8) -> Result<u8> {
+        self.check_slot(from)?;
+        target.check_slot(to)?;
+        move_between(&mut self.slots[from], &mut target.slots[to], count)
+    }
+
+    /// Returns how many items of a type the inventory holds in total
+    pub fn count(&self, item: u16) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count as u32)
+            .sum()
+    }
+
+    /// Removes up to `count` items of a type, whatever data they carry, returning how many were removed
+    pub fn remove(&mut self, item: u16, count: u32) -> u32 {
+        let mut removed = 0;
+        for slot in &mut self.slots {
+            let Some(stack) = slot else {
+                continue;
+            };
+            if stack.item != item {
+                continue;
+            }
+
+            let taken = (count - removed).min(stack.count as u32);
+            stack.count -= taken as u8;
+            removed += taken;
+            if stack.is_empty() {
+                *slot = None;
+            }
+            if removed == count {
+                break;
+            }
+        }
+        removed
+    }
+
+    /// Adds a stack to a range of slots, returning any items left over
+    fn insert_into(&mut self, mut stack: ItemStack, range: Range<usize>) -> Option<ItemStack> {
+        for existing in self.slots[range.clone()].iter_mut().flatten() {
+            if stack.is_empty() {
+                break;
+            }
+            existing.merge(&mut stack);
+        }
+
+        for slot in &mut self.slots[range] {
+            if stack.is_empty() {
+                break;
+            }
+            if slot.is_none() {
+                let count = stack.max_stack();
+                *slot = Some(stack.split(count));
+            }
+        }
+
+        Some(stack).filter(|stack| !stack.is_empty())
+    }
+
+    /// Returns two different slots to work on at once
+    fn pair(&mut self, first: usize, second: usize) -> (&mut Option<ItemStack>, &mut Option<ItemStack>) {
+        if first < second {
+            let (low, high) = self.slots.split_at_mut(second);
+            (&mut low[first], &mut high[0])
+        } else {
+            let (low, high) = self.slots.split_at_mut(first);
+            (&mut high[0], &mut low[second])
+        }
+    }
+
+    /// Checks a slot is in range
+    fn check_slot(&self, slot: usize) -> Result<()> {
+        if slot >= self.slots.len() {
+            return Err(anyhow!("Slot {} is out of range for an inventory of {} slots", slot, self.slots.len()));
+        }
+        Ok(())
+    }
+}
+
+/// A player's inventory: 36 slots, the first nine of which make up the hotbar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerInventory {
+    /// Every slot, hotbar first
+    items: Inventory,
+    /// The hotbar slot the player is holding
+    selected: usize,
+}
+
+impl PlayerInventory {
+    /// Create an empty player inventory holding the first hotbar slot
+    pub fn new() -> Self {
+        Self {
+            items: Inventory::new(PLAYER_SLOTS),
+            selected: 0,
+        }
+    }
+
+    /// Returns the slots of the inventory
+    pub fn items(&self) -> &Inventory {
+        &self.items
+    }
+
+    /// Returns the slots of the inventory for changing
+    pub fn items_mut(&mut self) -> &mut Inventory {
+        &mut self.items
+    }
+
+    /// Returns the hotbar slots
+    pub fn hotbar(&self) -> &[Option<ItemStack>] {
+        &self.items.slots()[..HOTBAR_SLOTS]
+    }
+
+    /// Returns the hotbar slot the player is holding
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Changes the hotbar slot the player is holding
+    pub fn select(&mut self, slot: usize) -> Result<()> {
+        if slot >= HOTBAR_SLOTS {
+            return Err(anyhow!("Hotbar slot {} is out of range", slot));
+        }
+        self.selected = slot;
+        Ok(())
+    }
+
+    /// Returns the stack the player is holding
+    pub fn held(&self) -> Option<&ItemStack> {
+        self.items.get(self.selected)
+    }
+
+    /// Takes up to `count` items out of the stack the player is holding
+    pub fn take_held(&mut self, count: u8) -> Option<ItemStack> {
+        self.items.split(self.selected, count).ok().flatten()
+    }
+
+    /// Adds a stack wherever it fits, hotbar first, returning any items left over
+    pub fn insert(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        self.items.insert(stack)
+    }
+
+    /// Moves a stack between the hotbar and the rest of the inventory
+    ///
+    /// Stacks in the hotbar go to the main inventory and the other way
+    /// round, onto matching stacks first and then into empty slots. Whatever
+    /// doesn't fit stays where it was.
+    pub fn quick_move(&mut self, slot: usize) -> Result<()> {
+        let Some(stack) = self.items.take(slot)? else {
+            return Ok(());
+        };
+
+        let range = if slot < HOTBAR_SLOTS { HOTBAR_SLOTS..PLAYER_SLOTS } else { 0..HOTBAR_SLOTS };
+        let leftover = self.items.insert_into(stack, range);
+        self.items.slots[slot] = leftover;
+        Ok(())
+    }
+}
+
+impl Default for PlayerInventory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Moves up to `count` items from one slot to another, which has to be empty or hold the same items
+fn move_between(source: &mut Option<ItemStack>, target: &mut Option<ItemStack>, count: u8) -> Result<u8> {
+    let Some(stack) = source else {
+        return Ok(0);
+    };
+
+    let moved = match target {
+        None => {
+            let moving = stack.split(count.min(stack.max_stack()));
+            let moved = moving.count;
+            *target = Some(moving).filter(|moving| !moving.is_empty());
+            moved
+        }
+        Some(existing) if existing.can_stack_with(stack) => {
+            let mut moving = stack.split(count);
+            let moved = existing.merge(&mut moving);
+            stack.count += moving.count;
+            moved
+        }
+        Some(_) => return Err(anyhow!("The target slot holds different items")),
+    };
+
+    if stack.is_empty() {
+        *source = None;
+    }
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::BlockType;
+    use crate::item::Tag;
+
+    fn stone(count: u8) -> ItemStack {
+        ItemStack::new(BlockType::Stone, count)
+    }
+
+    fn dirt(count: u8) -> ItemStack {
+        ItemStack::new(BlockType::Dirt, count)
+    }
+
+    #[test]
+    fn set_rejects_overfull_stacks() {
+        let mut inventory = Inventory::new(3);
+        assert!(inventory.set(0, Some(stone(65))).is_err());
+        assert!(inventory.set(3, Some(stone(1))).is_err());
+        assert_eq!(inventory.set(0, Some(stone(64))).unwrap(), None);
+        assert_eq!(inventory.set(0, Some(dirt(0))).unwrap(), Some(stone(64)));
+        assert_eq!(inventory.get(0), None);
+    }
+
+    #[test]
+    fn insert_merges_before_filling_empty_slots() {
+        let mut inventory = Inventory::new(3);
+        inventory.set(1, Some(stone(60))).unwrap();
+
+        assert_eq!(inventory.insert(stone(10)), None);
+        assert_eq!(inventory.get(0), Some(&stone(6)));
+        assert_eq!(inventory.get(1), Some(&stone(64)));
+        assert_eq!(inventory.count(BlockType::Stone as u16), 70);
+    }
+
+    #[test]
+    fn insert_returns_what_does_not_fit() {
+        let mut inventory = Inventory::new(2);
+        inventory.set(0, Some(dirt(1))).unwrap();
+
+        assert_eq!(inventory.insert(stone(100)), Some(stone(36)));
+        assert_eq!(inventory.get(1), Some(&stone(64)));
+    }
+
+    #[test]
+    fn split_takes_part_of_a_slot() {
+        let mut inventory = Inventory::new(1);
+        inventory.set(0, Some(stone(5))).unwrap();
+
+        assert_eq!(inventory.split(0, 2).unwrap(), Some(stone(2)));
+        assert_eq!(inventory.get(0), Some(&stone(3)));
+        assert_eq!(inventory.split(0, 10).unwrap(), Some(stone(3)));
+        assert_eq!(inventory.get(0), None);
+        assert_eq!(inventory.split(0, 1).unwrap(), None);
+    }
+
+    #[test]
+    fn move_stack_merges_up_to_the_limit() {
+        let mut inventory = Inventory::new(2);
+        inventory.set(0, Some(stone(40))).unwrap();
+        inventory.set(1, Some(stone(40))).unwrap();
+
+        inventory.move_stack(0, 1).unwrap();
+        assert_eq!(inventory.get(0), Some(&stone(16)));
+        assert_eq!(inventory.get(1), Some(&stone(64)));
+    }
+
+    #[test]
+    fn move_stack_swaps_different_items() {
+        let mut inventory = Inventory::new(2);
+        inventory.set(0, Some(stone(3))).unwrap();
+        inventory.set(1, Some(dirt(7))).unwrap();
+
+        inventory.move_stack(0, 1).unwrap();
+        assert_eq!(inventory.get(0), Some(&dirt(7)));
+        assert_eq!(inventory.get(1), Some(&stone(3)));
+    }
+
+    #[test]
+    fn move_items_moves_part_of_a_stack() {
+        let mut inventory = Inventory::new(3);
+        inventory.set(0, Some(stone(10))).unwrap();
+        inventory.set(2, Some(dirt(1))).unwrap();
+
+        assert_eq!(inventory.move_items(0, 1, 4).unwrap(), 4);
+        assert_eq!(inventory.get(0), Some(&stone(6)));
+        assert_eq!(inventory.get(1), Some(&stone(4)));
+        assert!(inventory.move_items(0, 2, 1).is_err());
+        assert_eq!(inventory.get(0), Some(&stone(6)));
+    }
+
+    #[test]
+    fn move_items_keeps_data_apart() {
+        let named = stone(1).with_data("Name", Tag::String("Rock".into()));
+        let mut inventory = Inventory::new(2);
+        inventory.set(0, Some(named.clone())).unwrap();
+        inventory.set(1, Some(stone(1))).unwrap();
+
+        assert!(inventory.move_items(0, 1, 1).is_err());
+        inventory.move_stack(0, 1).unwrap();
+        assert_eq!(inventory.get(1), Some(&named));
+    }
+
+    #[test]
+    fn transfer_moves_between_containers() {
+        let mut chest = Inventory::new(27);
+        let mut player = PlayerInventory::new();
+        chest.set(5, Some(stone(64))).unwrap();
+        player.items_mut().set(0, Some(stone(50))).unwrap();
+
+        assert_eq!(chest.transfer(5, player.items_mut(), 0, 64).unwrap(), 14);
+        assert_eq!(chest.get(5), Some(&stone(50)));
+        assert_eq!(player.held(), Some(&stone(64)));
+    }
+
+    #[test]
+    fn remove_takes_from_several_stacks() {
+        let mut inventory = Inventory::new(3);
+        inventory.set(0, Some(stone(5))).unwrap();
+        inventory.set(1, Some(dirt(5))).unwrap();
+        inventory.set(2, Some(stone(5))).unwrap();
+
+        assert_eq!(inventory.remove(BlockType::Stone as u16, 7), 7);
+        assert_eq!(inventory.get(0), None);
+        assert_eq!(inventory.get(2), Some(&stone(3)));
+        assert_eq!(inventory.remove(BlockType::Stone as u16, 10), 3);
+    }
+
+    #[test]
+    fn player_inventory_fills_hotbar_first() {
+        let mut player = PlayerInventory::new();
+        assert_eq!(player.items().size(), PLAYER_SLOTS);
+
+        for _ in 0..HOTBAR_SLOTS + 1 {
+            assert_eq!(player.insert(stone(64)), None);
+        }
+        assert!(player.hotbar().iter().all(|slot| slot == &Some(stone(64))));
+        assert_eq!(player.items().get(HOTBAR_SLOTS), Some(&stone(64)));
+        assert_eq!(player.items().get(HOTBAR_SLOTS + 1), None);
+    }
+
+    #[test]
+    fn player_holds_the_selected_slot() {
+        let mut player = PlayerInventory::new();
+        player.items_mut().set(3, Some(dirt(2))).unwrap();
+        assert!(player.select(HOTBAR_SLOTS).is_err());
+
+        player.select(3).unwrap();
+        assert_eq!(player.held(), Some(&dirt(2)));
+        assert_eq!(player.take_held(1), Some(dirt(1)));
+        assert_eq!(player.take_held(5), Some(dirt(1)));
+        assert_eq!(player.held(), None);
+    }
+
+    #[test]
+    fn quick_move_crosses_the_hotbar() {
+        let mut player = PlayerInventory::new();
+        player.items_mut().set(0, Some(stone(30))).unwrap();
+        player.items_mut().set(20, Some(stone(50))).unwrap();
+
+        player.quick_move(0).unwrap();
+        assert_eq!(player.items().get(0), None);
+        assert_eq!(player.items().get(HOTBAR_SLOTS), Some(&stone(16)));
+        assert_eq!(player.items().get(20), Some(&stone(64)));
+
+        player.quick_move(HOTBAR_SLOTS).unwrap();
+        assert_eq!(player.items().get(0), Some(&stone(16)));
+    }
+}
@@ -0,0 +1,9 @@
+pub mod inventory;
+pub mod properties;
+pub mod registry;
+pub mod stack;
+
+pub use inventory::{Inventory, PlayerInventory, HOTBAR_SLOTS, PLAYER_SLOTS};
+pub use properties::{ItemProperties, ToolProperties, ToolTier};
+pub use registry::{ItemRegistry, FIRST_ITEM_ID};
+pub use stack::{ItemStack, Tag};
@@ -0,0 +1,77 @@
+use serde::{Serialize, Deserialize};
+
+/// Properties of an item type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemProperties {
+    /// The name of the item
+    pub name: String,
+    /// The most items of this type a single stack can hold
+    pub max_stack: u8,
+    /// The block the item places, if it's a block item
+    pub block: Option<u16>,
+    /// What the item does as a tool, if it is one
+    pub tool: Option<ToolProperties>,
+    /// How many uses the item has before it breaks, if it wears out
+    pub durability: Option<u16>,
+}
+
+/// What an item does as a tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolProperties {
+    /// The type of tool, matching the tool type of the blocks it's best at breaking
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    /// The material the tool is made from, if it comes in several
+    #[serde(default)]
+    pub tier: Option<ToolTier>,
+    /// How many times faster than a bare hand the tool breaks blocks it suits,
+    /// if not the usual speed for its tier
+    #[serde(default)]
+    pub speed: Option<f32>,
+}
+
+impl ToolProperties {
+    /// Returns how many times faster than a bare hand the tool breaks blocks it suits
+    pub fn speed(&self) -> f32 {
+        self.speed
+            .or(self.tier.map(|tier| tier.speed()))
+            .unwrap_or(1.0)
+    }
+}
+
+/// The material a tool is made from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolTier {
+    Wood,
+    Stone,
+    Iron,
+    Gold,
+    Diamond,
+}
+
+impl ToolTier {
+    /// Returns how many times faster than a bare hand tools of this tier break blocks they suit
+    pub fn speed(&self) -> f32 {
+        match self {
+            ToolTier::Wood => 2.0,
+            ToolTier::Stone => 4.0,
+            ToolTier::Iron => 6.0,
+            ToolTier::Gold => 12.0,
+            ToolTier::Diamond => 8.0,
+        }
+    }
+
+    /// Returns the harvest level of the tier, which decides the blocks it can break for drops
+    ///
+    /// Gold tools are fast but only as strong as wooden ones.
+    pub fn level(&self) -> u8 {
+        match self {
+            ToolTier::Wood | ToolTier::Gold => 0,
+            ToolTier::Stone => 1,
+            ToolTier::Iron => 2,
+            ToolTier::Diamond => 3,
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use crate::block::BlockRegistry;
+use super::properties::{ItemProperties, ToolProperties};
+
+/// The built-in item definitions, a copy of which ships as the default `config/items.json`
+const BUILTIN_ITEMS: &str = include_str!("items.json");
+
+/// The lowest ID items that aren't blocks can use
+///
+/// IDs below this belong to blocks, whose items share their IDs, and IDs from
+/// here up belong to other items. Block and item definitions are both checked
+/// against it, so the built-in items never clash with the blocks in use.
+pub const FIRST_ITEM_ID: u16 = 256;
+
+/// The most items a stack holds unless an item says otherwise
+pub const DEFAULT_MAX_STACK: u8 = 64;
+
+/// The registry used by the game, set once at startup
+static GLOBAL: OnceLock<ItemRegistry> = OnceLock::new();
+
+/// An item that isn't a block, as written in an item definitions file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ItemDefinition {
+    /// The numeric ID of the item, `FIRST_ITEM_ID` or above
+    pub id: u16,
+    /// The name of the item
+    pub name: String,
+    /// The most items of this type a single stack can hold
+    #[serde(default = "default_max_stack")]
+    pub max_stack: u8,
+    /// What the item does as a tool, if it is one
+    #[serde(default)]
+    pub tool: Option<ToolProperties>,
+    /// How many uses the item has before it breaks, if it wears out
+    #[serde(default)]
+    pub durability: Option<u16>,
+}
+
+/// The layout of an item definitions file
+#[derive(Debug, Serialize, Deserialize)]
+struct ItemFile {
+    items: Vec<ItemDefinition>,
+}
+
+/// The properties of every item the game knows about
+///
+/// Every block other than air has an item with the same ID that places it.
+/// Other items, such as tools, come from item definitions and take IDs no
+/// block uses. Every block drop has to be one of these items, so drops can
+/// be handed out as items directly.
+#[derive(Debug, Clone)]
+pub struct ItemRegistry {
+    /// The properties of each item, indexed by item ID
+    table: Vec<Option<ItemProperties>>,
+}
+
+impl ItemRegistry {
+    /// Builds a registry from the blocks and a list of other item definitions
+    pub fn new(blocks: &BlockRegistry, definitions: Vec<ItemDefinition>) -> Result<Self> {
+        let size = blocks
+            .iter()
+            .map(|(id, _)| id as usize + 1)
+            .chain(definitions.iter().map(|definition| definition.id as usize + 1))
+            .max()
+            .unwrap_or(0);
+        let mut table = vec![None; size];
+
+        for (id, block) in blocks.iter().filter(|(id, _)| *id != 0) {
+            table[id as usize] = Some(ItemProperties {
+                name: block.name.clone(),
+                max_stack: DEFAULT_MAX_STACK,
+                block: Some(id),
+                tool: None,
+                durability: None,
+            });
+        }
+
+        for definition in definitions {
+            if definition.id < FIRST_ITEM_ID {
+                return Err(anyhow!("Item ID {} is below {}, which is reserved for blocks", definition.id, FIRST_ITEM_ID));
+            }
+            if table[definition.id as usize].is_some() {
+                return Err(anyhow!("Item ID {} is defined more than once", definition.id));
+            }
+            if definition.name.is_empty() {
+                return Err(anyhow!("Item {} has no name", definition.id));
+            }
+            if definition.max_stack == 0 {
+                return Err(anyhow!("Item '{}' can't be stacked at all", definition.name));
+            }
+
+            table[definition.id as usize] = Some(ItemProperties {
+                name: definition.name,
+                max_stack: definition.max_stack,
+                block: None,
+                tool: definition.tool,
+                durability: definition.durability,
+            });
+        }
+
+        for (_, block) in blocks.iter() {
+            let unknown = block.drops.iter().find(|(item, _)| table.get(*item as usize).is_none_or(Option::is_none));
+            if let Some((item, _)) = unknown {
+                return Err(anyhow!("Block '{}' drops unknown item {}", block.name, item));
+            }
+        }
+
+        Ok(Self { table })
+    }
+
+    /// Parses a registry from the blocks and the contents of an item definitions file
+    pub fn from_json(blocks: &BlockRegistry, json: &str) -> Result<Self> {
+        let file: ItemFile = serde_json::from_str(json)?;
+        Self::new(blocks, file.items)
+    }
+
+    /// Loads a registry from the blocks and an item definitions file
+    pub fn load(blocks: &BlockRegistry, path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read item definitions from {}", path.display()))?;
+        Self::from_json(blocks, &json).with_context(|| format!("Invalid item definitions in {}", path.display()))
+    }
+
+    /// Returns the registry of the built-in items for the blocks in use
+    pub fn builtin() -> Result<Self> {
+        Self::from_json(BlockRegistry::global(), BUILTIN_ITEMS).context("Invalid built-in item definitions")
+    }
+
+    /// Returns the registry the game uses
+    ///
+    /// This is the registry passed to `install`, or the built-in items if
+    /// nothing was installed before the first lookup. The built-in items only
+    /// use IDs from `FIRST_ITEM_ID` up, which no valid block registry can
+    /// take, so building them here can't fail.
+    pub fn global() -> &'static ItemRegistry {
+        GLOBAL.get_or_init(|| Self::builtin().expect("built-in items don't clash with blocks"))
+    }
+
+    /// Makes a registry the one the game uses
+    ///
+    /// This has to happen before any item properties are looked up, since
+    /// the registry can't change once it's in use.
+    pub fn install(registry: ItemRegistry) -> Result<()> {
+        GLOBAL
+            .set(registry)
+            .map_err(|_| anyhow!("An item registry is already in use"))
+    }
+
+    /// Returns the properties of an item, or None if the ID is unknown
+    pub fn get(&self, id: u16) -> Option<&ItemProperties> {
+        self.table.get(id as usize).and_then(Option::as_ref)
+    }
+
+    /// Returns every defined item ID with its properties
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &ItemProperties)> {
+        self.table
+            .iter()
+            .enumerate()
+            .filter_map(|(id, properties)| properties.as_ref().map(|properties| (id as u16, properties)))
+    }
+}
+
+/// Returns the stack size items get when their definition leaves it out
+fn default_max_stack() -> u8 {
+    DEFAULT_MAX_STACK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::BlockType;
+    use crate::item::ToolTier;
+    use std::path::PathBuf;
+
+    #[test]
+    fn shipped_config_matches_builtin_items() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../config/items.json");
+        assert_eq!(fs::read_to_string(path).unwrap(), BUILTIN_ITEMS);
+    }
+
+    #[test]
+    fn blocks_have_items() {
+        let registry = ItemRegistry::builtin().unwrap();
+        let stone = registry.get(BlockType::Stone as u16).unwrap();
+        assert_eq!(stone.block, Some(BlockType::Stone as u16));
+        assert_eq!(stone.max_stack, DEFAULT_MAX_STACK);
+        assert!(registry.get(BlockType::Air as u16).is_none());
+    }
+
+    #[test]
+    fn tools_are_unstackable() {
+        let registry = ItemRegistry::builtin().unwrap();
+        let (_, pickaxe) = registry.iter().find(|(_, item)| item.name == "Iron Pickaxe").unwrap();
+        assert_eq!(pickaxe.max_stack, 1);
+        assert_eq!(pickaxe.block, None);
+        let tool = pickaxe.tool.as_ref().unwrap();
+        assert_eq!(tool.tool_type, "pickaxe");
+        assert_eq!(tool.tier, Some(ToolTier::Iron));
+    }
+
+    #[test]
+    fn rejects_ids_reserved_for_blocks() {
+        let json = r#"{ "items": [{ "id": 1, "name": "Not Stone" }] }"#;
+        assert!(ItemRegistry::from_json(BlockRegistry::global(), json).is_err());
+
+        // Even where there's no block yet
+        let json = r#"{ "items": [{ "id": 255, "name": "Almost An Item" }] }"#;
+        assert!(ItemRegistry::from_json(BlockRegistry::global(), json).is_err());
+    }
+
+    #[test]
+    fn rejects_duplicate_ids() {
+        let json = r#"{ "items": [{ "id": 300, "name": "A" }, { "id": 300, "name": "B" }] }"#;
+        assert!(ItemRegistry::from_json(BlockRegistry::global(), json).is_err());
+    }
+
+    #[test]
+    fn ores_drop_their_items() {
+        let registry = ItemRegistry::builtin().unwrap();
+        for (ore, name) in [
+            (BlockType::CoalOre, "Coal"),
+            (BlockType::RedstoneOre, "Redstone"),
+            (BlockType::DiamondOre, "Diamond"),
+        ] {
+            let (item, _) = BlockRegistry::global().get(ore as u16).unwrap().drops[0];
+            assert_eq!(registry.get(item).unwrap().name, name);
+        }
+    }
+
+    #[test]
+    fn rejects_block_drops_without_items() {
+        // The built-in ores drop items that aren't blocks
+        let error = ItemRegistry::from_json(BlockRegistry::global(), r#"{ "items": [] }"#).unwrap_err();
+        assert!(error.to_string().contains("drops unknown item"));
+    }
+}
@@ -0,0 +1,175 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use super::registry::{ItemRegistry, DEFAULT_MAX_STACK};
+
+/// A value in the extra data carried by an item stack
+///
+/// This follows the tag types of Minecraft's NBT format, so enchantments,
+/// custom names and the like can be stored the same way.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    List(Vec<Tag>),
+    Compound(BTreeMap<String, Tag>),
+}
+
+/// A number of items of one type, with any extra data they carry
+///
+/// Stacks only merge with stacks of the same item carrying the same data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    /// The ID of the item
+    pub item: u16,
+    /// How many items are in the stack
+    pub count: u8,
+    /// Extra data, such as damage or a custom name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub data: BTreeMap<String, Tag>,
+}
+
+impl ItemStack {
+    /// Create a new stack without extra data
+    pub fn new(item: impl Into<u16>, count: u8) -> Self {
+        Self {
+            item: item.into(),
+            count,
+            data: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the stack with a piece of extra data set
+    pub fn with_data(mut self, key: impl Into<String>, value: Tag) -> Self {
+        self.data.insert(key.into(), value);
+        self
+    }
+
+    /// Returns the most items a stack of this item can hold
+    ///
+    /// Items the registry doesn't know stack as far as items usually do.
+    pub fn max_stack(&self) -> u8 {
+        ItemRegistry::global()
+            .get(self.item)
+            .map_or(DEFAULT_MAX_STACK, |properties| properties.max_stack)
+    }
+
+    /// Check if the stack has no items left
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Check if the stack can't take any more items
+    pub fn is_full(&self) -> bool {
+        self.count >= self.max_stack()
+    }
+
+    /// Check if another stack holds the same items, so the two can merge
+    pub fn can_stack_with(&self, other: &ItemStack) -> bool {
+        self.item == other.item && self.data == other.data
+    }
+
+    /// Takes up to `count` items off the stack into a new stack
+    ///
+    /// The new stack carries the same data. It holds fewer items than asked
+    /// for if the stack doesn't have enough.
+    pub fn split(&mut self, count: u8) -> ItemStack {
+        let taken = count.min(self.count);
+        self.count -= taken;
+        ItemStack {
+            item: self.item,
+            count: taken,
+            data: self.data.clone(),
+        }
+    }
+
+    /// Moves as many items from another stack onto this one as fit
+    ///
+    /// Nothing moves if the stacks hold different items. Returns how many
+    /// items moved; whatever doesn't fit stays in the other stack.
+    pub fn merge(&mut self, other: &mut ItemStack) -> u8 {
+        if !self.can_stack_with(other) {
+            return 0;
+        }
+
+        let space = self.max_stack().saturating_sub(self.count);
+        let moved = space.min(other.count);
+        self.count += moved;
+        other.count -= moved;
+        moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::BlockType;
+    use crate::item::ItemRegistry;
+
+    /// Returns the ID of a built-in item by name
+    fn item(name: &str) -> u16 {
+        ItemRegistry::global()
+            .iter()
+            .find(|(_, properties)| properties.name == name)
+            .map(|(id, _)| id)
+            .unwrap()
+    }
+
+    #[test]
+    fn split_takes_at_most_the_stack() {
+        let mut stack = ItemStack::new(BlockType::Dirt, 10);
+        let half = stack.split(4);
+        assert_eq!((stack.count, half.count), (6, 4));
+
+        let rest = stack.split(20);
+        assert_eq!(rest.count, 6);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn split_keeps_data() {
+        let mut stack = ItemStack::new(BlockType::Chest, 2).with_data("Name", Tag::String("Loot".into()));
+        let taken = stack.split(1);
+        assert!(taken.can_stack_with(&stack));
+    }
+
+    #[test]
+    fn merge_respects_stack_limit() {
+        let mut target = ItemStack::new(BlockType::Stone, 60);
+        let mut source = ItemStack::new(BlockType::Stone, 10);
+        assert_eq!(target.merge(&mut source), 4);
+        assert_eq!((target.count, source.count), (64, 6));
+        assert!(target.is_full());
+    }
+
+    #[test]
+    fn merge_needs_matching_items_and_data() {
+        let mut stone = ItemStack::new(BlockType::Stone, 1);
+        let mut dirt = ItemStack::new(BlockType::Dirt, 1);
+        assert_eq!(stone.merge(&mut dirt), 0);
+
+        let mut named = ItemStack::new(BlockType::Stone, 1).with_data("Name", Tag::String("Rock".into()));
+        assert_eq!(stone.merge(&mut named), 0);
+        assert_eq!((stone.count, named.count), (1, 1));
+    }
+
+    #[test]
+    fn tools_do_not_stack() {
+        let pickaxe = item("Diamond Pickaxe");
+        let mut held = ItemStack::new(pickaxe, 1);
+        let mut other = ItemStack::new(pickaxe, 1);
+        assert!(held.is_full());
+        assert_eq!(held.merge(&mut other), 0);
+    }
+
+    #[test]
+    fn data_survives_serialization() {
+        let stack = ItemStack::new(item("Iron Axe"), 1).with_data("Damage", Tag::Int(12));
+        let json = serde_json::to_string(&stack).unwrap();
+        assert_eq!(serde_json::from_str::<ItemStack>(&json).unwrap(), stack);
+    }
+}
@@ -32,9 +32,9 @@ pub fn block_to_chunk(block_x: i32, block_y: i32, block_z: i32) -> (i32, i32, i3
 #[inline]
 pub fn block_to_local(block_x: i32, block_y: i32, block_z: i32) -> (usize, usize, usize) {
     // Apply modulo to get local coordinates
-    let local_x = ((block_x % 16) + 16) % 16;
-    let local_y = ((block_y % 16) + 16) % 16;
-    let local_z = ((block_z % 16) + 16) % 16;
+    let local_x = block_x.rem_euclid(16);
+    let local_y = block_y.rem_euclid(16);
+    let local_z = block_z.rem_euclid(16);
     
     (local_x as usize, local_y as usize, local_z as usize)
 }
@@ -168,9 +168,9 @@ impl BlockVector {
     /// Convert to a chunk vector
     pub fn to_chunk(&self) -> ChunkVector {
         ChunkVector {
-            x: (self.x >> 4) as i32,
-            y: (self.y >> 4) as i32,
-            z: (self.z >> 4) as i32,
+            x: (self.x >> 4),
+            y: (self.y >> 4),
+            z: (self.z >> 4),
         }
     }
     
@@ -228,6 +228,28 @@ impl ChunkVector {
             z: self.z << 4,
         }
     }
+    
+    /// Packs the chunk position into a single key for hash maps
+    /// Each axis is stored in 21 bits, which covers ±1 million chunks
+    pub fn to_key(&self) -> i64 {
+        let x = (self.x as i64) & 0x1F_FFFF;
+        let y = (self.y as i64) & 0x1F_FFFF;
+        let z = (self.z as i64) & 0x1F_FFFF;
+        (x << 42) | (y << 21) | z
+    }
+    
+    /// Unpacks a chunk position from a key created with `to_key`
+    pub fn from_key(key: i64) -> Self {
+        // Shift left then arithmetic shift right to sign-extend each 21-bit field
+        let x = ((key >> 42) << 43) >> 43;
+        let y = ((key >> 21) << 43) >> 43;
+        let z = (key << 43) >> 43;
+        Self {
+            x: x as i32,
+            y: y as i32,
+            z: z as i32,
+        }
+    }
 }
 
 // Implement mathematical operators for WorldVector
@@ -355,5 +377,4 @@ impl Sub for ChunkVector {
             z: self.z - other.z,
         }
     }
-} 
-} 
\ No newline at end of file
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+use crate::block::{Block, BlockProperties};
+use crate::constants::TICK_LENGTH_MS;
+use crate::item::{ItemRegistry, ItemStack, ToolProperties};
+
+/// How many times slower blocks break while the player isn't on the ground
+const AIRBORNE_PENALTY: f32 = 5.0;
+
+/// How many times slower blocks break while the player is in water
+const UNDERWATER_PENALTY: f32 = 5.0;
+
+/// Hardness is multiplied by this to give the ticks a block takes to break by hand when it drops items
+const HARVEST_FACTOR: f32 = 30.0;
+
+/// Hardness is multiplied by this to give the ticks a block takes to break by hand when it drops nothing
+const NO_HARVEST_FACTOR: f32 = 100.0;
+
+/// The share of the expected break time a break may come in under and still count, to allow for latency
+pub const BREAK_TIME_TOLERANCE: f32 = 0.2;
+
+/// The conditions a player breaks a block under
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Miner<'a> {
+    /// The stack the player is holding, if any
+    pub tool: Option<&'a ItemStack>,
+    /// Whether the player is standing on the ground
+    pub on_ground: bool,
+    /// Whether the player is in water
+    pub in_water: bool,
+}
+
+/// Returns how many ticks a player takes to break a block, or None if it can't be broken
+///
+/// This follows Minecraft: the held tool speeds things up if it's the kind
+/// the block wants, and blocks that won't drop anything take over three
+/// times longer. Breaking blocks mid-air or in water is five times slower
+/// each. Air, fluids and blocks with negative hardness can't be broken.
+pub fn break_ticks(block: Block, miner: &Miner) -> Option<u32> {
+    if block.is_air() {
+        return None;
+    }
+    let properties = block.properties()?;
+    if properties.fluid || properties.hardness < 0.0 {
+        return None;
+    }
+    if properties.hardness == 0.0 {
+        return Some(0);
+    }
+
+    let mut speed = suited_tool(properties, miner.tool).map_or(1.0, |tool| tool.speed());
+    if !miner.on_ground {
+        speed /= AIRBORNE_PENALTY;
+    }
+    if miner.in_water {
+        speed /= UNDERWATER_PENALTY;
+    }
+
+    let factor = if can_harvest(block, miner.tool) { HARVEST_FACTOR } else { NO_HARVEST_FACTOR };
+    let progress = speed / properties.hardness / factor;
+    if progress >= 1.0 {
+        // Blocks that break in a single tick break as soon as they're hit
+        return Some(0);
+    }
+    Some((1.0 / progress).ceil() as u32)
+}
+
+/// Returns how long a player takes to break a block, or None if it can't be broken
+pub fn break_time(block: Block, miner: &Miner) -> Option<Duration> {
+    break_ticks(block, miner).map(|ticks| Duration::from_millis(ticks as u64 * TICK_LENGTH_MS))
+}
+
+/// Checks whether a player took long enough breaking a block
+///
+/// Breaks may finish up to `BREAK_TIME_TOLERANCE` early to allow for
+/// latency. Blocks that can't be broken never check out.
+pub fn is_valid_break(block: Block, miner: &Miner, elapsed: Duration) -> bool {
+    break_time(block, miner).is_some_and(|expected| elapsed >= expected.mul_f32(1.0 - BREAK_TIME_TOLERANCE))
+}
+
+/// Checks whether a block drops its items when broken with a tool (or by hand)
+///
+/// Blocks that require a tool only drop items when broken with the right
+/// kind, of at least the tier they ask for.
+pub fn can_harvest(block: Block, tool: Option<&ItemStack>) -> bool {
+    let Some(properties) = block.properties() else {
+        return false;
+    };
+    if !properties.requires_tool {
+        return true;
+    }
+
+    let Some(tool) = suited_tool(properties, tool) else {
+        return false;
+    };
+    match properties.tool_tier {
+        Some(required) => tool.tier.is_some_and(|tier| tier.level() >= required.level()),
+        None => true,
+    }
+}
+
+/// Returns the items a block drops when broken with a tool (or by hand)
+///
+/// Nothing drops if the block needs a better tool.
+pub fn drops(block: Block, tool: Option<&ItemStack>) -> Vec<ItemStack> {
+    if !can_harvest(block, tool) {
+        return Vec::new();
+    }
+
+    block
+        .properties()
+        .map(|properties| {
+            properties
+                .drops
+                .iter()
+                .map(|(item, count)| ItemStack::new(*item, *count))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns what the held item does as a tool, if it's the kind the block wants
+fn suited_tool(properties: &BlockProperties, held: Option<&ItemStack>) -> Option<&'static ToolProperties> {
+    let wanted = properties.tool_type.as_deref()?;
+    let tool = ItemRegistry::global().get(held?.item)?.tool.as_ref()?;
+    (tool.tool_type == wanted).then_some(tool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::BlockType;
+
+    /// Returns a single built-in item, found by name
+    fn item(name: &str) -> ItemStack {
+        let (id, _) = ItemRegistry::global()
+            .iter()
+            .find(|(_, properties)| properties.name == name)
+            .unwrap();
+        ItemStack::new(id, 1)
+    }
+
+    /// Returns how many ticks a player standing on dry ground takes to break a block
+    fn ticks(block_type: BlockType, tool: Option<&ItemStack>) -> Option<u32> {
+        let miner = Miner { tool, on_ground: true, in_water: false };
+        break_ticks(Block::new(block_type), &miner)
+    }
+
+    /// Checks whether breaking a block drops anything
+    fn harvests(block_type: BlockType, tool: Option<&ItemStack>) -> bool {
+        !drops(Block::new(block_type), tool).is_empty()
+    }
+
+    #[test]
+    fn right_tool_is_faster_than_hand_or_wrong_tool() {
+        let shovel = item("Wooden Shovel");
+        let pickaxe = item("Wooden Pickaxe");
+        assert_eq!(ticks(BlockType::Dirt, None), Some(15));
+        assert_eq!(ticks(BlockType::Dirt, Some(&shovel)), Some(8));
+        assert_eq!(ticks(BlockType::Dirt, Some(&pickaxe)), Some(15));
+
+        let axe = item("Iron Axe");
+        assert_eq!(ticks(BlockType::OakPlanks, None), Some(60));
+        assert_eq!(ticks(BlockType::OakPlanks, Some(&axe)), Some(10));
+    }
+
+    #[test]
+    fn blocks_needing_a_tool_drop_nothing_without_it() {
+        let pickaxe = item("Wooden Pickaxe");
+        let axe = item("Wooden Axe");
+        assert_eq!(ticks(BlockType::Stone, None), Some(150));
+        assert_eq!(ticks(BlockType::Stone, Some(&axe)), Some(150));
+        assert_eq!(ticks(BlockType::Stone, Some(&pickaxe)), Some(23));
+        assert_eq!(ticks(BlockType::Stone, Some(&item("Iron Pickaxe"))), Some(8));
+
+        assert!(!harvests(BlockType::Stone, None));
+        assert!(!harvests(BlockType::Stone, Some(&axe)));
+        assert_eq!(
+            drops(Block::new(BlockType::Stone), Some(&pickaxe)),
+            vec![ItemStack::new(BlockType::Cobblestone, 1)]
+        );
+        assert!(harvests(BlockType::Dirt, None));
+    }
+
+    #[test]
+    fn tiers_gate_drops() {
+        let wood = item("Wooden Pickaxe");
+        let stone = item("Stone Pickaxe");
+        let iron = item("Iron Pickaxe");
+        let gold = item("Golden Pickaxe");
+
+        // Iron ore needs at least stone
+        assert_eq!(ticks(BlockType::IronOre, Some(&wood)), Some(150));
+        assert!(!harvests(BlockType::IronOre, Some(&wood)));
+        assert_eq!(ticks(BlockType::IronOre, Some(&stone)), Some(23));
+        assert!(harvests(BlockType::IronOre, Some(&stone)));
+
+        // Gold tools are fast, but only as strong as wooden ones
+        assert_eq!(ticks(BlockType::IronOre, Some(&gold)), Some(25));
+        assert!(!harvests(BlockType::IronOre, Some(&gold)));
+        assert!(harvests(BlockType::CoalOre, Some(&gold)));
+
+        // Diamond ore needs at least iron
+        assert_eq!(ticks(BlockType::DiamondOre, Some(&stone)), Some(75));
+        assert!(!harvests(BlockType::DiamondOre, Some(&stone)));
+        assert_eq!(ticks(BlockType::DiamondOre, Some(&iron)), Some(15));
+        assert!(harvests(BlockType::DiamondOre, Some(&iron)));
+    }
+
+    #[test]
+    fn airborne_and_underwater_are_slower() {
+        let pickaxe = item("Iron Pickaxe");
+        let stone = Block::new(BlockType::Stone);
+        let miner = |on_ground, in_water| Miner { tool: Some(&pickaxe), on_ground, in_water };
+
+        assert_eq!(break_ticks(stone, &miner(true, false)), Some(8));
+        assert_eq!(break_ticks(stone, &miner(false, false)), Some(38));
+        assert_eq!(break_ticks(stone, &miner(true, true)), Some(38));
+        assert_eq!(break_ticks(stone, &miner(false, true)), Some(188));
+    }
+
+    #[test]
+    fn unbreakable_blocks() {
+        let pickaxe = item("Diamond Pickaxe");
+        for block_type in [BlockType::Bedrock, BlockType::Water, BlockType::Lava, BlockType::Air] {
+            assert_eq!(ticks(block_type, None), None, "{:?}", block_type);
+            assert_eq!(ticks(block_type, Some(&pickaxe)), None, "{:?}", block_type);
+        }
+        assert!(!is_valid_break(Block::new(BlockType::Bedrock), &Miner::default(), Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn instant_breaks() {
+        assert_eq!(ticks(BlockType::Torch, None), Some(0));
+        assert_eq!(ticks(BlockType::TallGrass, None), Some(0));
+        assert!(harvests(BlockType::Torch, None));
+
+        // Shears are fast enough to break leaves in under a tick
+        assert_eq!(ticks(BlockType::OakLeaves, None), Some(6));
+        assert_eq!(ticks(BlockType::OakLeaves, Some(&item("Shears"))), Some(0));
+        assert!(is_valid_break(Block::new(BlockType::Torch), &Miner::default(), Duration::ZERO));
+    }
+
+    #[test]
+    fn breaks_may_come_in_a_little_early() {
+        let stone = Block::new(BlockType::Stone);
+        let miner = Miner { tool: None, on_ground: true, in_water: false };
+        let expected = break_time(stone, &miner).unwrap();
+        assert_eq!(expected, Duration::from_millis(150 * TICK_LENGTH_MS));
+
+        assert!(is_valid_break(stone, &miner, expected));
+        assert!(is_valid_break(stone, &miner, expected.mul_f32(1.0 - BREAK_TIME_TOLERANCE)));
+        assert!(!is_valid_break(stone, &miner, expected.mul_f32(0.5)));
+    }
+}
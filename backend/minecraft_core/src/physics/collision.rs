@@ -161,7 +161,7 @@ pub fn detect_block_collision(entity_aabb: &AABB, block_x: i32, block_y: i32, bl
         ),
     };
     
-    CollisionResult::new(block.clone(), dir, min_depth, point)
+    CollisionResult::new(*block, dir, min_depth, point)
 }
 
 /// Detects collisions between an entity and a set of blocks
@@ -195,9 +195,6 @@ pub fn resolve_collision(
     let direction = result.direction.unwrap();
     let normal = direction.unit_vector();
     
-    // Calculate the reflection vector for the velocity
-    let dot_product = velocity.x * normal.x + velocity.y * normal.y + velocity.z * normal.z;
-    
     // Move the position out of the collision
     position.x += normal.x * result.depth;
     position.y += normal.y * result.depth;
@@ -1,3 +1,4 @@
+use crate::block::BlockType;
 use crate::math::WorldVector;
 use crate::physics::{GRAVITY, MAX_FALL_SPEED, PhysicsState, TERMINAL_VELOCITY};
 use crate::physics::collision::{CollisionDirection, CollisionResult, resolve_collision};
@@ -62,7 +63,7 @@ pub fn update_physics(
     }
 
     // Apply drag to velocity
-    let drag_factor = if state.in_water || state.in_lava {
+    let drag_factor: f32 = if state.in_water || state.in_lava {
         0.8
     } else if state.on_ground {
         0.6
@@ -80,9 +81,6 @@ pub fn update_physics(
         state.velocity.z *= scale;
     }
 
-    // Save old position for collision resolution
-    let old_position = state.position.clone();
-
     // Update position based on velocity
     state.position.x += state.velocity.x * dt;
     state.position.y += state.velocity.y * dt;
@@ -146,7 +144,7 @@ fn handle_collisions(
     blocks_to_check: &[(i32, i32, i32, crate::block::Block)],
 ) {
     // Get the bounding box for the entity
-    let mut entity_aabb = state.bounding_box();
+    let entity_aabb = state.bounding_box();
 
     // Check for collisions with blocks
     let mut collisions = Vec::new();
@@ -155,10 +153,10 @@ fn handle_collisions(
         // Skip non-solid blocks
         if !block.is_solid() {
             // Check if in water or lava
-            if block.block_type.id() == 8 || block.block_type.id() == 9 {
+            if block.block_type == BlockType::Water as u16 {
                 state.in_water = true;
                 state.in_lava = false;
-            } else if block.block_type.id() == 10 || block.block_type.id() == 11 {
+            } else if block.block_type == BlockType::Lava as u16 {
                 state.in_water = false;
                 state.in_lava = true;
             }
@@ -243,7 +241,7 @@ fn handle_collisions(
 
         let result = CollisionResult {
             collision: true,
-            block: Some(block.clone()),
+            block: Some(*block),
             direction: Some(dir),
             depth: min_depth,
             point: Some(point),
@@ -259,9 +257,6 @@ fn handle_collisions(
     for collision in collisions {
         resolve_collision(&mut state.position, &mut state.velocity, &collision);
 
-        // Update the entity AABB for subsequent collision checks
-        entity_aabb = state.bounding_box();
-
         // Update on_ground flag
         if let Some(direction) = collision.direction {
             if direction == CollisionDirection::Bottom {
@@ -0,0 +1,3 @@
+pub mod protocol;
+
+pub use protocol::{ClientMessage, Encoding, ServerMessage, PROTOCOL_VERSION};
@@ -0,0 +1,169 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Errors that can occur while encoding or decoding messages
+#[derive(Debug, thiserror::Error)]
+pub enum CodecError {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("MessagePack encode error: {0}")]
+    MessagePackEncode(#[from] rmp_serde::encode::Error),
+    #[error("MessagePack decode error: {0}")]
+    MessagePackDecode(#[from] rmp_serde::decode::Error),
+}
+
+/// The wire encodings a connection can use
+///
+/// Both encodings carry exactly the same message types, so a connection
+/// can pick either one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// Compact binary encoding (MessagePack, fields by name)
+    MessagePack,
+    /// Human-readable encoding for debugging
+    Json,
+}
+
+impl Encoding {
+    /// Encodes a message
+    pub fn encode<T: Serialize>(&self, message: &T) -> Result<Vec<u8>, CodecError> {
+        match self {
+            Encoding::MessagePack => Ok(rmp_serde::to_vec_named(message)?),
+            Encoding::Json => Ok(serde_json::to_vec(message)?),
+        }
+    }
+
+    /// Decodes a message
+    pub fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, CodecError> {
+        match self {
+            Encoding::MessagePack => Ok(rmp_serde::from_slice(data)?),
+            Encoding::Json => Ok(serde_json::from_slice(data)?),
+        }
+    }
+
+    /// Returns whether this encoding is binary (sent as binary WebSocket frames)
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Encoding::MessagePack)
+    }
+}
+
+/// Computes the Adler-32 checksum of a payload
+///
+/// Used for the `checksum` field of chunk data messages so clients can
+/// detect truncated or corrupted payloads.
+pub fn checksum(data: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+
+    let mut a = 1u32;
+    let mut b = 0u32;
+    // 5552 bytes is the most that can be summed before `b` could overflow
+    for block in data.chunks(5552) {
+        for &byte in block {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MODULUS;
+        b %= MODULUS;
+    }
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::messages::{
+        BlockAction, ChunkFormat, ClientMessage, EntityKind, MovementFlags, MovementKeys, ServerMessage,
+    };
+    use minecraft_core::block::{Block, BlockFace, BlockType};
+    use minecraft_core::math::{BlockVector, ChunkVector, WorldVector};
+    use uuid::Uuid;
+
+    const ENCODINGS: [Encoding; 2] = [Encoding::MessagePack, Encoding::Json];
+
+    /// Encodes and decodes a message with every encoding, checking it comes back unchanged
+    fn assert_round_trip<T>(message: &T)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        for encoding in ENCODINGS {
+            let data = encoding.encode(message).unwrap();
+            let decoded: T = encoding.decode(&data).unwrap();
+            assert_eq!(&decoded, message, "{:?} round trip", encoding);
+        }
+    }
+
+    #[test]
+    fn client_messages_round_trip() {
+        assert_round_trip(&ClientMessage::Join {
+            protocol_version: 1,
+            name: "Steve".to_string(),
+        });
+        assert_round_trip(&ClientMessage::Input {
+            sequence: 42,
+            keys: MovementKeys {
+                forward: true,
+                left: true,
+                ..MovementKeys::default()
+            },
+            orientation: [1.5, -0.25],
+            jump: true,
+            flags: MovementFlags {
+                sprinting: true,
+                ..MovementFlags::default()
+            },
+        });
+        assert_round_trip(&ClientMessage::BlockInteract {
+            timestamp: 1000,
+            position: BlockVector::new(-3, 64, 7),
+            face: BlockFace::North,
+            action: BlockAction::Place,
+            item_id: Some(BlockType::Stone as u16),
+            sequence: 3,
+        });
+        assert_round_trip(&ClientMessage::Disconnect);
+    }
+
+    #[test]
+    fn server_messages_round_trip() {
+        assert_round_trip(&ServerMessage::ChunkData {
+            timestamp: 123_456,
+            position: ChunkVector::new(1, 2, -3),
+            format: ChunkFormat::Compressed,
+            data: vec![0, 1, 2, 254, 255],
+            checksum: checksum(&[0, 1, 2, 254, 255]),
+        });
+        assert_round_trip(&ServerMessage::BlockUpdate {
+            position: BlockVector::new(0, 5, 0),
+            block: Block::with_metadata(BlockType::Water, 9),
+        });
+        assert_round_trip(&ServerMessage::EntitySpawned {
+            entity_id: Uuid::from_u128(7),
+            entity: EntityKind::FallingBlock {
+                block: Block::new(BlockType::Sand),
+            },
+            position: WorldVector::new(0.5, 10.0, 0.5),
+            velocity: WorldVector::new(0.0, -1.0, 0.0),
+        });
+    }
+
+    #[test]
+    fn message_pack_keeps_field_names_and_tags() {
+        let data = Encoding::MessagePack.encode(&ClientMessage::KeepAlive { id: 9 }).unwrap();
+        let value: serde_json::Value = rmp_serde::from_slice(&data).unwrap();
+        assert_eq!(value, serde_json::json!({ "type": "keep_alive", "id": 9 }));
+    }
+
+    #[test]
+    fn decode_rejects_the_other_encoding() {
+        let json = Encoding::Json.encode(&ClientMessage::Disconnect).unwrap();
+        assert!(Encoding::MessagePack.decode::<ClientMessage>(&json).is_err());
+    }
+
+    #[test]
+    fn checksum_matches_adler32() {
+        assert_eq!(checksum(b""), 1);
+        assert_eq!(checksum(b"Wikipedia"), 0x11E6_0398);
+    }
+}
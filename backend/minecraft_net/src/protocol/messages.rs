@@ -0,0 +1,229 @@
+use minecraft_core::block::{Block, BlockFace};
+use minecraft_core::math::{BlockVector, ChunkVector, WorldVector};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The movement keys held by the player
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MovementKeys {
+    pub forward: bool,
+    pub backward: bool,
+    pub left: bool,
+    pub right: bool,
+    /// Fly up (only used while flying)
+    pub up: bool,
+    /// Fly down (only used while flying)
+    pub down: bool,
+}
+
+/// Movement state flags sent with an input message
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MovementFlags {
+    pub sprinting: bool,
+    pub sneaking: bool,
+    pub flying: bool,
+}
+
+/// The kind of block interaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockAction {
+    /// Break the block
+    Break,
+    /// Place a block against the face
+    Place,
+    /// Use the block (open a chest, etc.)
+    Interact,
+}
+
+/// How the payload of a chunk data message is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkFormat {
+    /// Run-length encoded block data
+    Rle,
+    /// `Chunk::serialize` output
+    Plain,
+    /// `Chunk::serialize` output compressed with zstd
+    Compressed,
+}
+
+/// Public information about a connected player
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerInfo {
+    /// The player's unique ID
+    pub id: Uuid,
+    /// The player's name
+    pub name: String,
+    /// The player's position
+    pub position: WorldVector,
+}
+
+/// What kind of entity an entity is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EntityKind {
+    /// A block such as sand falling under gravity
+    FallingBlock {
+        block: Block,
+    },
+}
+
+/// Messages sent by clients to the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// The handshake, which must be the first message on a connection
+    Join {
+        /// The protocol version the client speaks
+        protocol_version: u32,
+        /// The requested player name
+        name: String,
+    },
+    /// A reply to a keepalive sent by the server
+    KeepAlive {
+        /// The ID of the keepalive being answered
+        id: u64,
+    },
+    /// The player's input for one tick
+    ///
+    /// The server simulates movement from these inputs; the client only
+    /// predicts, and reconciles using the sequence echoed in `PlayerState`.
+    Input {
+        sequence: u32,
+        keys: MovementKeys,
+        /// Pitch and yaw in radians
+        orientation: [f32; 2],
+        jump: bool,
+        flags: MovementFlags,
+    },
+    /// The client breaks, places or uses a block
+    BlockInteract {
+        /// Client time in milliseconds
+        timestamp: u64,
+        position: BlockVector,
+        face: BlockFace,
+        action: BlockAction,
+        /// The held item, if any
+        item_id: Option<u16>,
+        sequence: u32,
+    },
+    /// The client asks for specific chunks
+    ChunkRequest {
+        /// Client time in milliseconds
+        timestamp: u64,
+        positions: Vec<ChunkVector>,
+        /// 0 (highest) to 3 (lowest)
+        priority: u8,
+    },
+    /// A chat message
+    Chat {
+        message: String,
+    },
+    /// The client is leaving
+    Disconnect,
+}
+
+/// Messages sent by the server to clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// The handshake was accepted
+    Welcome {
+        /// The protocol version the server speaks
+        protocol_version: u32,
+        /// The ID assigned to the player
+        player_id: Uuid,
+        /// The name of the world the player joined
+        world_name: String,
+        /// The world spawn position
+        spawn_position: BlockVector,
+        /// The current time of day in ticks
+        time: u64,
+        /// The message of the day
+        motd: String,
+    },
+    /// The full list of connected players
+    Players {
+        players: Vec<PlayerInfo>,
+    },
+    /// Another player joined
+    PlayerJoined {
+        player: PlayerInfo,
+    },
+    /// Another player left
+    PlayerLeft {
+        player_id: Uuid,
+        name: String,
+    },
+    /// Another player moved
+    PlayerMoved {
+        player_id: Uuid,
+        position: WorldVector,
+        velocity: WorldVector,
+        /// Pitch and yaw in radians
+        orientation: [f32; 2],
+    },
+    /// The authoritative state of the receiving player
+    PlayerState {
+        /// The sequence of the last input applied
+        sequence: u32,
+        position: WorldVector,
+        velocity: WorldVector,
+        on_ground: bool,
+    },
+    /// A keepalive the client must answer with the same ID
+    KeepAlive {
+        id: u64,
+    },
+    /// The contents of a chunk
+    ChunkData {
+        /// Server time in milliseconds
+        timestamp: u64,
+        position: ChunkVector,
+        format: ChunkFormat,
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+        /// A checksum of `data`
+        checksum: u32,
+    },
+    /// A chunk the client should drop
+    ChunkUnload {
+        position: ChunkVector,
+    },
+    /// A single block changed
+    BlockUpdate {
+        position: BlockVector,
+        block: Block,
+    },
+    /// An entity appeared
+    EntitySpawned {
+        entity_id: Uuid,
+        entity: EntityKind,
+        position: WorldVector,
+        velocity: WorldVector,
+    },
+    /// An entity moved
+    EntityMoved {
+        entity_id: Uuid,
+        position: WorldVector,
+        velocity: WorldVector,
+    },
+    /// An entity disappeared
+    EntityRemoved {
+        entity_id: Uuid,
+    },
+    /// The time of day changed
+    TimeUpdate {
+        time: u64,
+    },
+    /// A chat message
+    Chat {
+        sender: String,
+        message: String,
+    },
+    /// The server is closing the connection
+    Disconnect {
+        reason: String,
+    },
+}
@@ -0,0 +1,10 @@
+pub mod codec;
+pub mod messages;
+
+pub use codec::{checksum, CodecError, Encoding};
+pub use messages::*;
+
+/// The version of the client/server protocol
+///
+/// Bump this whenever a message changes in a way older peers can't read.
+pub const PROTOCOL_VERSION: u32 = 3;
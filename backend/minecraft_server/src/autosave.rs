@@ -0,0 +1,60 @@
+use crate::server::GameServer;
+use log::{debug, error, info};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::MissedTickBehavior;
+
+/// Periodically writes the chunks modified since the last save
+///
+/// Only dirty chunks are written, so an idle world costs nothing. Each save
+/// logs how many chunks were written and how long it took, and is recorded
+/// in the server profiler under `autosave`.
+pub struct Autosave {
+    /// The server whose world is saved
+    server: Arc<GameServer>,
+    /// The time between saves
+    interval: Duration,
+}
+
+impl Autosave {
+    /// Creates an autosave task using the configured interval, or None if autosave is disabled
+    pub fn new(server: Arc<GameServer>) -> Option<Self> {
+        match server.settings.world.autosave_interval {
+            0 => None,
+            seconds => Some(Self {
+                server,
+                interval: Duration::from_secs(seconds),
+            }),
+        }
+    }
+
+    /// Runs the autosave loop forever
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + self.interval, self.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            self.save().await;
+        }
+    }
+
+    /// Saves the dirty chunks once and reports the result
+    async fn save(&self) {
+        let start = Instant::now();
+        let result = {
+            let _profile = self.server.profiler.measure("autosave");
+            self.server.world.save_dirty_chunks().await
+        };
+
+        match result {
+            Ok(0) => debug!("Autosave: no modified chunks"),
+            Ok(count) => info!(
+                "Autosaved {} chunks in {:.1} ms",
+                count,
+                start.elapsed().as_secs_f64() * 1000.0
+            ),
+            Err(err) => error!("Autosave failed: {}", err),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::entity::Entity;
+use crate::movement::nearby_blocks;
+use crate::server::GameServer;
+use anyhow::Result;
+use log::warn;
+use minecraft_core::block::Block;
+use minecraft_core::constants::MIN_BUILD_HEIGHT;
+use minecraft_core::math::{BlockVector, WorldVector};
+use minecraft_core::physics::movement::update_physics;
+use minecraft_net::protocol::{EntityKind, ServerMessage};
+use std::time::Duration;
+
+/// How many ticks a block waits after an update before it starts to fall
+pub const FALL_DELAY: u64 = 2;
+
+/// How many ticks a block waits before checking again when the block below it isn't loaded
+const UNLOADED_RETRY_DELAY: u64 = 20;
+
+/// How far (blocks) below the bottom of the world a falling block is removed
+const VOID_DEPTH: f32 = 64.0;
+
+/// How many blocks above where it lands a falling block may settle if that spot is taken
+///
+/// Falling blocks don't collide with each other, so a column of them can
+/// land on the same spot in the same tick.
+const LANDING_SEARCH: i32 = 2;
+
+/// Checks whether a block falls when nothing holds it up
+pub fn is_affected(block: Block) -> bool {
+    block.properties().is_some_and(|properties| properties.gravity_affected)
+}
+
+/// Checks whether a falling block passes through a block
+pub fn can_fall_through(block: Block) -> bool {
+    block.is_air() || block.is_fluid()
+}
+
+/// Schedules a check for whether a block has lost its support
+pub fn neighbour_update(server: &GameServer, position: BlockVector) {
+    server.world.schedule_block_tick(position, FALL_DELAY);
+}
+
+/// Turns a block into a falling entity if there is room below it
+///
+/// If the block below isn't loaded yet, the check is scheduled again for
+/// later instead of leaving the block hanging until something else updates it.
+pub async fn scheduled_tick(server: &GameServer, position: BlockVector, block: Block) -> Result<()> {
+    if position.y <= MIN_BUILD_HEIGHT {
+        return Ok(());
+    }
+
+    let below = BlockVector::new(position.x, position.y - 1, position.z);
+    let Some(below_block) = server.world.get_block_if_loaded(below) else {
+        server.world.schedule_block_tick(position, UNLOADED_RETRY_DELAY);
+        return Ok(());
+    };
+    if !can_fall_through(below_block) {
+        return Ok(());
+    }
+
+    server.set_block(position, Block::default()).await?;
+    let center = WorldVector::new(position.x as f32 + 0.5, position.y as f32, position.z as f32 + 0.5);
+    server.spawn_entity(Entity::falling_block(block, center)).await;
+
+    Ok(())
+}
+
+/// Moves every falling block for one tick, placing the ones that land
+///
+/// A block that lands where it can't be placed, such as on a torch, settles
+/// just above if there is room and is lost otherwise.
+pub async fn step(server: &GameServer, delta_time: Duration) {
+    let mut entities = server.entities.lock().await;
+    let mut finished = Vec::new();
+    let no_input = WorldVector::new(0.0, 0.0, 0.0).to_vector3();
+
+    for entity in entities.values_mut() {
+        let EntityKind::FallingBlock { block } = entity.kind;
+        let state = &mut entity.physics;
+
+        // The fluid flags are only ever set by the physics step, so clear them first
+        state.in_water = false;
+        state.in_lava = false;
+
+        let blocks = match nearby_blocks(&server.world, &state.position).await {
+            Ok(blocks) => blocks,
+            Err(err) => {
+                warn!("Failed to move falling block {}: {}", entity.id, err);
+                continue;
+            }
+        };
+        update_physics(state, delta_time, no_input, false, &blocks);
+
+        if state.on_ground {
+            let position = state.position;
+            let target = BlockVector::new(position.x.floor() as i32, position.y.round() as i32, position.z.floor() as i32);
+            finished.push((entity.id, Some((target, block))));
+        } else if state.position.y < MIN_BUILD_HEIGHT as f32 - VOID_DEPTH {
+            finished.push((entity.id, None));
+        } else {
+            server.sessions.broadcast(entity.move_message());
+        }
+    }
+
+    for (id, landing) in finished {
+        entities.remove(&id);
+        server.sessions.broadcast(ServerMessage::EntityRemoved { entity_id: id });
+
+        let Some((target, block)) = landing else {
+            continue;
+        };
+
+        if let Err(err) = land(server, target, block).await {
+            warn!("Failed to place falling block at {:?}: {}", target, err);
+        }
+    }
+}
+
+/// Places a falling block where it landed, or just above if that spot is taken
+async fn land(server: &GameServer, target: BlockVector, block: Block) -> Result<()> {
+    for offset in 0..=LANDING_SEARCH {
+        let position = BlockVector::new(target.x, target.y + offset, target.z);
+        if can_fall_through(server.world.get_block(position).await?) {
+            return server.set_block(position, block).await;
+        }
+    }
+    Ok(())
+}
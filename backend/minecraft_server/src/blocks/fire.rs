@@ -0,0 +1,364 @@
+use crate::config::GameplaySection;
+use crate::server::GameServer;
+use anyhow::Result;
+use minecraft_core::block::{Block, BlockState, BlockType, StateValue};
+use minecraft_core::math::BlockVector;
+use minecraft_world::chunk::light::LightKind;
+use minecraft_world::world::World;
+use minecraft_world::world::light::MAX_LIGHT;
+use rand::Rng;
+
+/// The oldest a fire gets, stored in its `age` state
+pub const MAX_AGE: u8 = 15;
+
+/// The fewest ticks between two steps of a fire
+const FIRE_DELAY: u64 = 30;
+
+/// The most extra ticks, picked at random, added to the fire delay
+const FIRE_DELAY_SPREAD: u64 = 10;
+
+/// The six blocks touching a block
+const NEIGHBOURS: [(i32, i32, i32); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+/// The chance each step that a fire burns up a flammable block it touches
+const BURN_CHANCE: f64 = 0.15;
+
+/// The chance each step that a fire spreads to a spot beside a flammable block
+///
+/// Spots more than a block above the fire are less likely to catch.
+const SPREAD_CHANCE: f64 = 0.05;
+
+/// The base chance each step that rain puts out a fire under open sky, before age is added
+const RAIN_CHANCE: f64 = 0.2;
+
+/// How many blocks above itself lava can set alight
+const LAVA_REACH: i32 = 3;
+
+/// Checks whether a block is fire
+pub fn is_affected(block: Block) -> bool {
+    block.block_type() == Some(BlockType::Fire)
+}
+
+/// Puts out a fire that has lost what it stood on, otherwise makes sure it's burning
+pub async fn neighbour_update(server: &GameServer, position: BlockVector) -> Result<()> {
+    if !can_burn_at(&server.world, position) {
+        return server.set_block(position, Block::default()).await;
+    }
+
+    server.world.schedule_block_tick(position, fire_delay());
+    Ok(())
+}
+
+/// Runs one step of a fire: it ages, burns up the blocks around it and spreads
+///
+/// Fire only spreads and destroys blocks if `fire_damage` is on, and only
+/// destroys blocks if `mob_griefing` is on as well; otherwise it burns out
+/// where it is. Rain puts out fires under open sky.
+pub async fn scheduled_tick(server: &GameServer, position: BlockVector, block: Block) -> Result<()> {
+    let changes = burn(&server.world, &server.settings.gameplay, position, age_of(block))?;
+    let burnt_out = changes.iter().any(|(changed, block)| *changed == position && !is_affected(*block));
+
+    for (changed, block) in changes {
+        server.set_block(changed, block).await?;
+    }
+    if !burnt_out {
+        server.world.schedule_block_tick(position, fire_delay());
+    }
+    Ok(())
+}
+
+/// Lets lava set fire to flammable blocks above and around it
+///
+/// A few random spots up to three blocks above the lava are tried; the
+/// search stops at the first solid block.
+pub async fn lava_tick(server: &GameServer, position: BlockVector) -> Result<()> {
+    if !server.settings.gameplay.fire_damage {
+        return Ok(());
+    }
+
+    if let Some(target) = lava_target(&server.world, position) {
+        server.set_block(target, Block::new(BlockType::Fire)).await?;
+    }
+    Ok(())
+}
+
+/// Works out the blocks one step of a fire changes, including the fire itself
+fn burn(world: &World, gameplay: &GameplaySection, position: BlockVector, age: u8) -> Result<Vec<(BlockVector, Block)>> {
+    let mut rng = rand::thread_rng();
+    let mut changes = Vec::new();
+
+    let out = Ok(vec![(position, Block::default())]);
+    if !can_burn_at(world, position) {
+        return out;
+    }
+    if world.is_raining && is_exposed(world, position) && rng.gen_bool((RAIN_CHANCE + age as f64 * 0.03).min(1.0)) {
+        return out;
+    }
+
+    let new_age = (age + rng.gen_range(0..3) / 2).min(MAX_AGE);
+    let fuel_below = is_flammable(world.get_block_if_loaded(offset(position, 0, -1, 0)));
+    if !has_fuel(world, position) {
+        let supported = world.get_block_if_loaded(offset(position, 0, -1, 0)).is_some_and(|below| below.is_solid());
+        if !supported || age > 3 {
+            return out;
+        }
+    } else if (!fuel_below || !gameplay.mob_griefing) && age == MAX_AGE && rng.gen_bool(0.25) {
+        // Old fires die down unless they're eating into the block below
+        return out;
+    }
+
+    if new_age != age {
+        changes.push((position, fire(new_age)?));
+    }
+    if !gameplay.fire_damage {
+        // Without fire damage a fire only burns itself out
+        if age == MAX_AGE {
+            return out;
+        }
+        return Ok(changes);
+    }
+
+    if gameplay.mob_griefing {
+        for (dx, dy, dz) in NEIGHBOURS {
+            let neighbour = offset(position, dx, dy, dz);
+            if !is_flammable(world.get_block_if_loaded(neighbour)) || !rng.gen_bool(BURN_CHANCE) {
+                continue;
+            }
+
+            // Young fires leave flames behind in the blocks they burn up
+            let replacement = if rng.gen_range(0..age + 10) < 5 && !(world.is_raining && is_exposed(world, neighbour)) {
+                fire(age + rng.gen_range(0..5) / 4)?
+            } else {
+                Block::default()
+            };
+            changes.push((neighbour, replacement));
+        }
+    }
+
+    for dx in -1..=1 {
+        for dz in -1..=1 {
+            for dy in -1..=4 {
+                if dx == 0 && dy == 0 && dz == 0 {
+                    continue;
+                }
+
+                let target = offset(position, dx, dy, dz);
+                if changes.iter().any(|(changed, _)| *changed == target) {
+                    continue;
+                }
+                let empty = world.get_block_if_loaded(target).is_some_and(|block| block.is_air());
+                if !empty || !has_fuel(world, target) || (world.is_raining && is_exposed(world, target)) {
+                    continue;
+                }
+
+                let chance = if dy > 1 { SPREAD_CHANCE / (dy - 1) as f64 } else { SPREAD_CHANCE };
+                if rng.gen_bool(chance) {
+                    let spread_age = age + rng.gen_range(0..5) / 4;
+                    changes.push((target, fire(spread_age)?));
+                }
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Returns a fire block of the specified age, capped at the oldest a fire gets
+fn fire(age: u8) -> Result<Block> {
+    let state = BlockState::new(BlockType::Fire).with("age", StateValue::Int(age.min(MAX_AGE)))?;
+    Ok(Block::from(state))
+}
+
+/// Reads the age of a fire block
+///
+/// Blocks whose metadata isn't a valid fire state count as the oldest fire,
+/// so they burn out soon.
+fn age_of(block: Block) -> u8 {
+    block.state().ok().and_then(|state| state.age()).unwrap_or(MAX_AGE)
+}
+
+/// Picks an empty spot near lava to set alight, if it finds one
+fn lava_target(world: &World, position: BlockVector) -> Option<BlockVector> {
+    let mut rng = rand::thread_rng();
+
+    let climb = rng.gen_range(0..=LAVA_REACH);
+    if climb > 0 {
+        // Rise through the air above the lava, drifting sideways
+        let mut target = position;
+        for _ in 0..climb {
+            target = offset(target, rng.gen_range(-1..=1), 1, rng.gen_range(-1..=1));
+            let block = world.get_block_if_loaded(target)?;
+            if block.is_air() {
+                if has_fuel(world, target) {
+                    return Some(target);
+                }
+            } else if block.is_solid() {
+                return None;
+            }
+        }
+        return None;
+    }
+
+    // Otherwise try to light the tops of flammable blocks beside the lava
+    for _ in 0..3 {
+        let fuel = offset(position, rng.gen_range(-1..=1), 0, rng.gen_range(-1..=1));
+        let above = offset(fuel, 0, 1, 0);
+        let empty = world.get_block_if_loaded(above).is_some_and(|block| block.is_air());
+        if empty && is_flammable(world.get_block_if_loaded(fuel)) {
+            return Some(above);
+        }
+    }
+    None
+}
+
+/// Checks whether fire can stay at a position: on a solid block or beside something flammable
+fn can_burn_at(world: &World, position: BlockVector) -> bool {
+    world.get_block_if_loaded(offset(position, 0, -1, 0)).is_some_and(|below| below.is_solid()) || has_fuel(world, position)
+}
+
+/// Checks whether any block touching a position is flammable
+fn has_fuel(world: &World, position: BlockVector) -> bool {
+    NEIGHBOURS
+        .iter()
+        .any(|(dx, dy, dz)| is_flammable(world.get_block_if_loaded(offset(position, *dx, *dy, *dz))))
+}
+
+/// Checks whether a block (if its chunk is loaded) can catch fire
+fn is_flammable(block: Option<Block>) -> bool {
+    block.and_then(|block| block.properties()).is_some_and(|properties| properties.flammable)
+}
+
+/// Checks whether a position is under open sky, so rain falls on it
+fn is_exposed(world: &World, position: BlockVector) -> bool {
+    world.get_light(LightKind::Sky, position) == Some(MAX_LIGHT)
+}
+
+/// Picks how many ticks a fire waits before its next step
+fn fire_delay() -> u64 {
+    FIRE_DELAY + rand::thread_rng().gen_range(0..=FIRE_DELAY_SPREAD)
+}
+
+/// Returns the position offset from another by the specified amounts
+fn offset(position: BlockVector, dx: i32, dy: i32, dz: i32) -> BlockVector {
+    BlockVector::new(position.x + dx, position.y + dy, position.z + dz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerSettings;
+    use crate::testing::{block_at, flat_world, run_ticks, server, GROUND};
+
+    /// How many times the random steps are tried
+    const TRIES: usize = 200;
+
+    /// The spot the fires in these tests burn at, on the ground of a flat world
+    const FIRE: BlockVector = BlockVector { x: 8, y: GROUND, z: 8 };
+
+    /// Returns gameplay settings with the specified fire rules
+    fn gameplay(fire_damage: bool, mob_griefing: bool) -> GameplaySection {
+        let mut gameplay = ServerSettings::default().gameplay;
+        gameplay.fire_damage = fire_damage;
+        gameplay.mob_griefing = mob_griefing;
+        gameplay
+    }
+
+    /// Surrounds the fire with planks on every side but the top and bottom
+    async fn surround_with_planks(server: &GameServer) {
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let position = offset(FIRE, dx, 0, dz);
+            server.set_block(position, Block::new(BlockType::OakPlanks)).await.unwrap();
+        }
+    }
+
+    /// Runs many steps of a fire of the specified age and gathers every block they'd change
+    fn burn_many(server: &GameServer, gameplay: &GameplaySection, age: u8) -> Vec<Vec<(BlockVector, Block)>> {
+        (0..TRIES).map(|_| burn(&server.world, gameplay, FIRE, age).unwrap()).collect()
+    }
+
+    /// Checks whether a step put the fire out
+    fn goes_out(changes: &[(BlockVector, Block)]) -> bool {
+        changes.contains(&(FIRE, Block::default()))
+    }
+
+    #[tokio::test]
+    async fn fire_without_fire_damage_only_burns_itself() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        surround_with_planks(&server).await;
+
+        for changes in burn_many(&server, &gameplay(false, true), 0) {
+            assert!(changes.iter().all(|(position, _)| *position == FIRE));
+        }
+        // The oldest fires burn out instead of living on forever
+        for changes in burn_many(&server, &gameplay(false, true), MAX_AGE) {
+            assert!(goes_out(&changes));
+        }
+    }
+
+    #[tokio::test]
+    async fn fire_without_mob_griefing_spreads_but_destroys_nothing() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        surround_with_planks(&server).await;
+
+        let all_changes: Vec<_> = burn_many(&server, &gameplay(true, false), 0).into_iter().flatten().collect();
+        assert!(all_changes.iter().any(|(position, block)| *position != FIRE && is_affected(*block)));
+        for (position, _) in all_changes {
+            assert!(position == FIRE || block_at(&server, position.x, position.y, position.z).is_air());
+        }
+
+        // With mob griefing the planks get burnt up
+        let griefing: Vec<_> = burn_many(&server, &gameplay(true, true), 0).into_iter().flatten().collect();
+        let is_planks = |position: &BlockVector| {
+            block_at(&server, position.x, position.y, position.z).block_type() == Some(BlockType::OakPlanks)
+        };
+        assert!(griefing.iter().any(|(position, _)| is_planks(position)));
+    }
+
+    #[tokio::test]
+    async fn rain_puts_out_fires_under_open_sky() {
+        let mut world = flat_world();
+        world.is_raining = true;
+        let server = server(world, ServerSettings::default()).await;
+        let gameplay = gameplay(true, true);
+
+        assert!(burn_many(&server, &gameplay, 0).iter().any(|changes| goes_out(changes)));
+
+        // A roof keeps the rain off
+        server.set_block(offset(FIRE, 0, 2, 0), Block::new(BlockType::Stone)).await.unwrap();
+        assert!(!burn_many(&server, &gameplay, 0).iter().any(|changes| goes_out(changes)));
+    }
+
+    #[tokio::test]
+    async fn fire_without_fuel_burns_out() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        server.set_block(FIRE, fire(0).unwrap()).await.unwrap();
+        run_ticks(&server, 30).await;
+        assert!(is_affected(block_at(&server, FIRE.x, FIRE.y, FIRE.z)));
+
+        run_ticks(&server, 3000).await;
+        assert!(block_at(&server, FIRE.x, FIRE.y, FIRE.z).is_air());
+    }
+
+    #[tokio::test]
+    async fn fire_goes_out_without_support() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        let floating = offset(FIRE, 0, 3, 0);
+        server.set_block(floating, fire(0).unwrap()).await.unwrap();
+        run_ticks(&server, 1).await;
+        assert!(block_at(&server, floating.x, floating.y, floating.z).is_air());
+    }
+
+    #[test]
+    fn reads_and_caps_ages() {
+        assert_eq!(age_of(fire(4).unwrap()), 4);
+        assert_eq!(age_of(fire(MAX_AGE + 5).unwrap()), MAX_AGE);
+        assert_eq!(age_of(Block::with_metadata(BlockType::Fire, u16::MAX)), MAX_AGE);
+    }
+}
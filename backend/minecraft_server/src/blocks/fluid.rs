@@ -0,0 +1,546 @@
+use super::fire;
+use crate::server::GameServer;
+use anyhow::Result;
+use minecraft_core::block::{Block, BlockState, BlockType, StateValue};
+use minecraft_core::math::BlockVector;
+use minecraft_world::world::World;
+
+/// The furthest a fluid can flow sideways from its source
+pub const MAX_DISTANCE: u8 = 7;
+
+/// The four horizontal directions fluid spreads in
+const HORIZONTAL: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// What `slope_distance` returns when there is no drop within reach
+const NO_DROP: i32 = i32::MAX;
+
+/// The level of a fluid block, read from its `distance` and `falling` state
+///
+/// The distance is how far the fluid has flowed from a source (0 for the
+/// source itself, up to 7). Blocks placed without metadata are sources, which
+/// is what the terrain generator fills oceans with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FluidLevel {
+    /// How far the fluid has flowed sideways from a source
+    pub distance: u8,
+    /// Whether the fluid is falling from the block above
+    pub falling: bool,
+}
+
+impl FluidLevel {
+    /// A source block
+    pub const SOURCE: FluidLevel = FluidLevel { distance: 0, falling: false };
+
+    /// Fluid falling from above
+    pub const FALLING: FluidLevel = FluidLevel { distance: 0, falling: true };
+
+    /// Fluid that has flowed the specified distance sideways
+    pub fn flowing(distance: u8) -> Self {
+        Self { distance, falling: false }
+    }
+
+    /// Reads the level from a fluid block's state
+    ///
+    /// Blocks whose metadata isn't a valid fluid state count as flowing as
+    /// far as fluid can reach, so they drain away instead of becoming
+    /// endless sources.
+    pub fn of(block: Block) -> Self {
+        let Ok(state) = block.state() else {
+            return Self::flowing(MAX_DISTANCE);
+        };
+        Self {
+            distance: state.distance().unwrap_or(0),
+            falling: state.falling().unwrap_or(false),
+        }
+    }
+
+    /// Returns the state of a fluid block at this level
+    pub fn state(self, block_type: BlockType) -> Result<BlockState> {
+        BlockState::new(block_type)
+            .with("distance", StateValue::Int(self.distance))?
+            .with("falling", StateValue::Bool(self.falling))
+    }
+
+    /// Checks whether this is a source block
+    pub fn is_source(self) -> bool {
+        self == Self::SOURCE
+    }
+
+    /// Returns the distance fluid spreading sideways from here starts at
+    ///
+    /// Falling fluid spreads as if it were a source where it lands.
+    fn spread_distance(self) -> u8 {
+        if self.falling { 0 } else { self.distance }
+    }
+}
+
+/// The fluids that flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fluid {
+    Water,
+    Lava,
+}
+
+impl Fluid {
+    /// Returns the fluid a block is made of, if any
+    fn of(block: Block) -> Option<Fluid> {
+        match block.block_type()? {
+            BlockType::Water => Some(Fluid::Water),
+            BlockType::Lava => Some(Fluid::Lava),
+            _ => None,
+        }
+    }
+
+    /// Returns a block of this fluid at the specified level
+    ///
+    /// Fails for levels flowed further than the fluid can reach.
+    fn block(self, level: FluidLevel) -> Result<Block> {
+        let block_type = match self {
+            Fluid::Water => BlockType::Water,
+            Fluid::Lava => BlockType::Lava,
+        };
+        Ok(Block::from(level.state(block_type)?))
+    }
+
+    /// Returns how many ticks the fluid waits between steps
+    fn delay(self) -> u64 {
+        match self {
+            Fluid::Water => 5,
+            Fluid::Lava => 30,
+        }
+    }
+
+    /// Returns how much the distance grows per block of sideways flow
+    fn falloff(self) -> u8 {
+        match self {
+            Fluid::Water => 1,
+            Fluid::Lava => 2,
+        }
+    }
+
+    /// Returns how far (blocks) the fluid looks for a drop to flow towards
+    fn slope_reach(self) -> i32 {
+        match self {
+            Fluid::Water => 4,
+            Fluid::Lava => 2,
+        }
+    }
+}
+
+/// Checks whether a block is water or lava
+pub fn is_affected(block: Block) -> bool {
+    Fluid::of(block).is_some()
+}
+
+/// Schedules the next flow step for a fluid whose surroundings changed
+///
+/// Lava touching water hardens straight away.
+pub async fn neighbour_update(server: &GameServer, position: BlockVector, block: Block) -> Result<()> {
+    let Some(fluid) = Fluid::of(block) else {
+        return Ok(());
+    };
+    if fluid == Fluid::Lava && harden(server, position, block).await? {
+        return Ok(());
+    }
+
+    server.world.schedule_block_tick(position, fluid.delay());
+    Ok(())
+}
+
+/// Runs one flow step: settles the block's own level, then spreads it
+///
+/// Flowing lava may also set fire to what's around it.
+pub async fn scheduled_tick(server: &GameServer, position: BlockVector, block: Block) -> Result<()> {
+    let Some(fluid) = Fluid::of(block) else {
+        return Ok(());
+    };
+    if fluid == Fluid::Lava && harden(server, position, block).await? {
+        return Ok(());
+    }
+
+    let mut level = FluidLevel::of(block);
+    if !level.is_source() {
+        match expected_level(&server.world, position, fluid) {
+            Some(expected) if expected == level => {}
+            Some(expected) => {
+                server.set_block(position, fluid.block(expected)?).await?;
+                level = expected;
+            }
+            None => {
+                // Nothing feeds this block any more, so it drains away
+                return server.set_block(position, Block::default()).await;
+            }
+        }
+    }
+
+    spread(server, position, fluid, level).await?;
+    if fluid == Fluid::Lava {
+        fire::lava_tick(server, position).await?;
+    }
+    Ok(())
+}
+
+/// Works out the level a flowing block should have from what feeds it
+///
+/// Returns None if nothing feeds it. Water with two or more sources beside
+/// it and something to stand on becomes a source itself.
+fn expected_level(world: &World, position: BlockVector, fluid: Fluid) -> Option<FluidLevel> {
+    let above = offset(position, 0, 1, 0);
+    if world.get_block_if_loaded(above).and_then(Fluid::of) == Some(fluid) {
+        return Some(FluidLevel::FALLING);
+    }
+
+    let mut sources = 0;
+    let mut nearest: Option<u8> = None;
+    for (dx, dz) in HORIZONTAL {
+        let Some(neighbour) = world.get_block_if_loaded(offset(position, dx, 0, dz)) else {
+            continue;
+        };
+        if Fluid::of(neighbour) != Some(fluid) {
+            continue;
+        }
+
+        let level = FluidLevel::of(neighbour);
+        if level.is_source() {
+            sources += 1;
+        }
+        let distance = level.spread_distance();
+        nearest = Some(nearest.map_or(distance, |nearest| nearest.min(distance)));
+    }
+
+    if fluid == Fluid::Water && sources >= 2 {
+        let below = world.get_block_if_loaded(offset(position, 0, -1, 0)).unwrap_or_default();
+        let below_is_source = Fluid::of(below) == Some(Fluid::Water) && FluidLevel::of(below).is_source();
+        if below.is_solid() || below_is_source {
+            return Some(FluidLevel::SOURCE);
+        }
+    }
+
+    nearest
+        .map(|distance| distance + fluid.falloff())
+        .filter(|distance| *distance <= MAX_DISTANCE)
+        .map(FluidLevel::flowing)
+}
+
+/// Spreads a fluid block downwards if it can, otherwise sideways towards
+/// the nearest drop
+async fn spread(server: &GameServer, position: BlockVector, fluid: Fluid, level: FluidLevel) -> Result<()> {
+    let world = &server.world;
+
+    let below = offset(position, 0, -1, 0);
+    if let Some(below_block) = world.get_block_if_loaded(below) {
+        if fluid == Fluid::Lava && Fluid::of(below_block) == Some(Fluid::Water) {
+            // Lava pouring onto water turns it to stone
+            return server.set_block(below, Block::new(BlockType::Stone)).await;
+        }
+        if can_fall_into(fluid, below_block) {
+            return server.set_block(below, fluid.block(FluidLevel::FALLING)?).await;
+        }
+        if is_flowing(fluid, below_block) {
+            // Already pouring into the flow below
+            return Ok(());
+        }
+    }
+
+    let distance = level.spread_distance() + fluid.falloff();
+    if distance > MAX_DISTANCE {
+        return Ok(());
+    }
+
+    for (dx, dz) in flow_directions(world, position, fluid, distance) {
+        server.set_block(offset(position, dx, 0, dz), fluid.block(FluidLevel::flowing(distance))?).await?;
+    }
+    Ok(())
+}
+
+/// Picks the horizontal directions fluid flows in
+///
+/// Only the neighbours on the shortest path to a drop are used, so fluid
+/// runs towards holes and off edges instead of spreading evenly. If there's
+/// no drop within reach, every neighbour is used. Neighbours the fluid
+/// already fills still count as paths, but aren't flowed into again unless
+/// this flow is closer to its source.
+fn flow_directions(world: &World, position: BlockVector, fluid: Fluid, distance: u8) -> Vec<(i32, i32)> {
+    let mut best = NO_DROP;
+    let mut directions = Vec::new();
+
+    for (dx, dz) in HORIZONTAL {
+        let neighbour = offset(position, dx, 0, dz);
+        let Some(block) = world.get_block_if_loaded(neighbour) else {
+            continue;
+        };
+        if !is_passable(fluid, block) {
+            continue;
+        }
+
+        let slope = if is_drop(world, neighbour, fluid) {
+            0
+        } else {
+            slope_distance(world, neighbour, fluid, (-dx, -dz), 1)
+        };
+        if slope < best {
+            best = slope;
+            directions.clear();
+        }
+        if slope == best {
+            directions.push((dx, dz, block));
+        }
+    }
+
+    directions
+        .into_iter()
+        .filter(|(_, _, block)| can_flow_into(fluid, *block, distance))
+        .map(|(dx, dz, _)| (dx, dz))
+        .collect()
+}
+
+/// Returns how many blocks of flat flow separate a block from the nearest drop
+///
+/// `from` is the direction back to the block the search came from, which
+/// isn't searched again.
+fn slope_distance(world: &World, position: BlockVector, fluid: Fluid, from: (i32, i32), depth: i32) -> i32 {
+    if depth > fluid.slope_reach() {
+        return NO_DROP;
+    }
+
+    let mut best = NO_DROP;
+    for (dx, dz) in HORIZONTAL {
+        if (dx, dz) == from {
+            continue;
+        }
+
+        let neighbour = offset(position, dx, 0, dz);
+        if !world.get_block_if_loaded(neighbour).is_some_and(|block| is_passable(fluid, block)) {
+            continue;
+        }
+
+        if is_drop(world, neighbour, fluid) {
+            return depth;
+        }
+        best = best.min(slope_distance(world, neighbour, fluid, (-dx, -dz), depth + 1));
+    }
+
+    best
+}
+
+/// Checks whether fluid at a position could fall into the block below it
+fn is_drop(world: &World, position: BlockVector, fluid: Fluid) -> bool {
+    world
+        .get_block_if_loaded(offset(position, 0, -1, 0))
+        .is_some_and(|below| is_passable(fluid, below))
+}
+
+/// Turns lava touching water into cobblestone, or stone for a lava source
+///
+/// Returns whether the lava hardened. Water below lava doesn't count; the
+/// lava pours onto it instead.
+async fn harden(server: &GameServer, position: BlockVector, block: Block) -> Result<bool> {
+    let touching_water = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, 0, 1), (0, 0, -1)]
+        .into_iter()
+        .any(|(dx, dy, dz)| {
+            server.world.get_block_if_loaded(offset(position, dx, dy, dz)).and_then(Fluid::of) == Some(Fluid::Water)
+        });
+    if !touching_water {
+        return Ok(false);
+    }
+
+    let hardened = if FluidLevel::of(block).is_source() {
+        BlockType::Stone
+    } else {
+        BlockType::Cobblestone
+    };
+    server.set_block(position, Block::new(hardened)).await?;
+    Ok(true)
+}
+
+/// Checks whether fluid can fall into a block: an empty or washable block,
+/// or the same fluid flowing sideways
+fn can_fall_into(fluid: Fluid, block: Block) -> bool {
+    is_replaceable(block) || (is_flowing(fluid, block) && !FluidLevel::of(block).falling)
+}
+
+/// Checks whether fluid flowing sideways at the specified distance can flow
+/// into a block: an empty or washable block, or the same fluid further from
+/// its source
+fn can_flow_into(fluid: Fluid, block: Block, distance: u8) -> bool {
+    if is_replaceable(block) {
+        return true;
+    }
+    if !is_flowing(fluid, block) {
+        return false;
+    }
+
+    let level = FluidLevel::of(block);
+    !level.falling && level.distance > distance
+}
+
+/// Checks whether fluid can make its way through a block: an empty or
+/// washable block, or the same fluid that isn't a source
+fn is_passable(fluid: Fluid, block: Block) -> bool {
+    is_replaceable(block) || is_flowing(fluid, block)
+}
+
+/// Checks whether a block is flowing (not source) fluid of the specified kind
+fn is_flowing(fluid: Fluid, block: Block) -> bool {
+    Fluid::of(block) == Some(fluid) && !FluidLevel::of(block).is_source()
+}
+
+/// Checks whether flowing fluid can wash a block away
+fn is_replaceable(block: Block) -> bool {
+    block.is_air()
+        || matches!(
+            block.block_type(),
+            Some(BlockType::TallGrass | BlockType::Flower | BlockType::Torch | BlockType::Fire)
+        )
+}
+
+/// Returns the position offset from another by the specified amounts
+fn offset(position: BlockVector, dx: i32, dy: i32, dz: i32) -> BlockVector {
+    BlockVector::new(position.x + dx, position.y + dy, position.z + dz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerSettings;
+    use crate::testing::{block_at, flat_world, run_ticks, server, GROUND};
+
+    /// Returns settings with fire damage off, so lava can't set the world alight
+    fn settings() -> ServerSettings {
+        let mut settings = ServerSettings::default();
+        settings.gameplay.fire_damage = false;
+        settings
+    }
+
+    /// Returns a water or lava block at the specified level
+    fn fluid(block_type: BlockType, level: FluidLevel) -> Block {
+        Block::from(level.state(block_type).unwrap())
+    }
+
+    /// Returns the fluid level at a position, or None if it isn't the expected fluid
+    fn level_at(server: &GameServer, block_type: BlockType, x: i32, z: i32) -> Option<FluidLevel> {
+        let block = block_at(server, x, GROUND, z);
+        (block.block_type() == Some(block_type)).then(|| FluidLevel::of(block))
+    }
+
+    #[tokio::test]
+    async fn water_spreads_seven_blocks_from_a_source() {
+        let server = server(flat_world(), settings()).await;
+        server.set_block(BlockVector::new(8, GROUND, 8), Block::new(BlockType::Water)).await.unwrap();
+        run_ticks(&server, 100).await;
+
+        assert_eq!(level_at(&server, BlockType::Water, 8, 8), Some(FluidLevel::SOURCE));
+        for distance in 1..=MAX_DISTANCE {
+            let x = 8 + distance as i32;
+            assert_eq!(level_at(&server, BlockType::Water, x, 8), Some(FluidLevel::flowing(distance)));
+        }
+        assert!(block_at(&server, 8 + MAX_DISTANCE as i32 + 1, GROUND, 8).is_air());
+    }
+
+    #[tokio::test]
+    async fn lava_spreads_three_blocks_from_a_source() {
+        let server = server(flat_world(), settings()).await;
+        server.set_block(BlockVector::new(8, GROUND, 8), Block::new(BlockType::Lava)).await.unwrap();
+        run_ticks(&server, 300).await;
+
+        for step in 1..=3 {
+            let distance = step as u8 * 2;
+            assert_eq!(level_at(&server, BlockType::Lava, 8, 8 + step), Some(FluidLevel::flowing(distance)));
+        }
+        assert!(block_at(&server, 8, GROUND, 12).is_air());
+    }
+
+    #[tokio::test]
+    async fn water_drains_when_its_source_is_removed() {
+        let server = server(flat_world(), settings()).await;
+        let source = BlockVector::new(8, GROUND, 8);
+        server.set_block(source, Block::new(BlockType::Water)).await.unwrap();
+        run_ticks(&server, 100).await;
+
+        server.set_block(source, Block::default()).await.unwrap();
+        run_ticks(&server, 100).await;
+
+        for x in 0..=16 {
+            for z in 0..=16 {
+                assert!(block_at(&server, x, GROUND, z).is_air(), "water left at ({}, {})", x, z);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn water_between_two_sources_becomes_a_source() {
+        let server = server(flat_world(), settings()).await;
+        server.set_block(BlockVector::new(8, GROUND, 8), Block::new(BlockType::Water)).await.unwrap();
+        server.set_block(BlockVector::new(10, GROUND, 8), Block::new(BlockType::Water)).await.unwrap();
+        run_ticks(&server, 20).await;
+
+        assert_eq!(level_at(&server, BlockType::Water, 9, 8), Some(FluidLevel::SOURCE));
+
+        // The new source keeps the water there after one of the others goes
+        server.set_block(BlockVector::new(8, GROUND, 8), Block::default()).await.unwrap();
+        run_ticks(&server, 100).await;
+        assert_eq!(level_at(&server, BlockType::Water, 9, 8), Some(FluidLevel::SOURCE));
+    }
+
+    #[tokio::test]
+    async fn lava_touching_water_hardens() {
+        let server = server(flat_world(), settings()).await;
+        server.set_block(BlockVector::new(8, GROUND, 8), Block::new(BlockType::Water)).await.unwrap();
+        run_ticks(&server, 100).await;
+
+        // The water reaches (15, 8) and (8, 15), but not a block further
+        server.set_block(BlockVector::new(16, GROUND, 8), Block::new(BlockType::Lava)).await.unwrap();
+        let flowing = fluid(BlockType::Lava, FluidLevel::flowing(2));
+        server.set_block(BlockVector::new(8, GROUND, 16), flowing).await.unwrap();
+        run_ticks(&server, 5).await;
+
+        assert_eq!(block_at(&server, 16, GROUND, 8).block_type(), Some(BlockType::Stone));
+        assert_eq!(block_at(&server, 8, GROUND, 16).block_type(), Some(BlockType::Cobblestone));
+    }
+
+    #[tokio::test]
+    async fn enclosed_sources_stay_still() {
+        let server = server(flat_world(), settings()).await;
+        let ground = GROUND - 1;
+        for x in 4..8 {
+            for z in 4..8 {
+                server.set_block(BlockVector::new(x, ground, z), Block::new(BlockType::Water)).await.unwrap();
+            }
+        }
+        run_ticks(&server, 100).await;
+
+        for x in 3..9 {
+            for z in 3..9 {
+                let block = block_at(&server, x, ground, z);
+                if (4..8).contains(&x) && (4..8).contains(&z) {
+                    assert_eq!(block, Block::new(BlockType::Water));
+                } else {
+                    assert_eq!(block.block_type(), Some(BlockType::Grass));
+                }
+                assert!(block_at(&server, x, GROUND, z).is_air());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_states_drain_instead_of_becoming_sources() {
+        let corrupt = Block::with_metadata(BlockType::Water, 0x100);
+        assert_eq!(FluidLevel::of(corrupt), FluidLevel::flowing(MAX_DISTANCE));
+
+        let server = server(flat_world(), settings()).await;
+        server.set_block(BlockVector::new(8, GROUND, 8), corrupt).await.unwrap();
+        run_ticks(&server, 20).await;
+
+        assert!(block_at(&server, 8, GROUND, 8).is_air());
+        assert!(block_at(&server, 9, GROUND, 8).is_air());
+    }
+
+    #[test]
+    fn levels_beyond_the_reach_of_fluid_have_no_block() {
+        assert!(Fluid::Water.block(FluidLevel::flowing(MAX_DISTANCE + 1)).is_err());
+        assert_eq!(
+            FluidLevel::of(Fluid::Lava.block(FluidLevel::flowing(4)).unwrap()),
+            FluidLevel::flowing(4)
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::server::GameServer;
+use anyhow::Result;
+use minecraft_core::block::{Block, BlockType};
+use minecraft_core::math::BlockVector;
+use minecraft_world::world::World;
+use rand::Rng;
+
+/// The light a grass block needs on top of it to stay grass
+const MIN_LIGHT: u8 = 4;
+
+/// The light a grass block needs on top of it to spread
+const SPREAD_LIGHT: u8 = 9;
+
+/// How many nearby spots grass tries to spread to per random tick
+const SPREAD_ATTEMPTS: usize = 4;
+
+/// Spreads grass onto nearby dirt, or turns it back to dirt if it's too dark
+///
+/// Grass dies under fluids and in the dark, such as under an opaque block.
+/// Well-lit grass spreads to dirt up to one block away horizontally, three
+/// below and one above, as long as the dirt is lit enough to stay grass.
+/// Nothing happens until the light around the grass has been worked out.
+pub async fn random_tick(server: &GameServer, position: BlockVector) -> Result<()> {
+    let world = &server.world;
+    let Some(survives) = can_stay(world, position) else {
+        return Ok(());
+    };
+    if !survives {
+        return server.set_block(position, Block::new(BlockType::Dirt)).await;
+    }
+
+    let light = world.light_level(offset(position, 0, 1, 0)).unwrap_or(0);
+    if light < SPREAD_LIGHT {
+        return Ok(());
+    }
+
+    for target in spread_targets(world, position) {
+        server.set_block(target, Block::new(BlockType::Grass)).await?;
+    }
+    Ok(())
+}
+
+/// Picks the dirt blocks near some grass that it spreads to this time
+fn spread_targets(world: &World, position: BlockVector) -> Vec<BlockVector> {
+    let mut rng = rand::thread_rng();
+    let mut targets = Vec::new();
+
+    for _ in 0..SPREAD_ATTEMPTS {
+        let target = offset(
+            position,
+            rng.gen_range(-1..=1),
+            rng.gen_range(-3..=1),
+            rng.gen_range(-1..=1),
+        );
+        let is_dirt = world.get_block_if_loaded(target).and_then(|block| block.block_type()) == Some(BlockType::Dirt);
+        if is_dirt && can_stay(world, target) == Some(true) && !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+
+    targets
+}
+
+/// Checks whether grass at a position has the light and open top it needs
+///
+/// Returns None if the light above isn't known yet.
+fn can_stay(world: &World, position: BlockVector) -> Option<bool> {
+    let above = offset(position, 0, 1, 0);
+    let light = world.light_level(above)?;
+    let covered = world.get_block_if_loaded(above)?.is_fluid();
+    Some(!covered && light >= MIN_LIGHT)
+}
+
+/// Returns the position offset from another by the specified amounts
+fn offset(position: BlockVector, dx: i32, dy: i32, dz: i32) -> BlockVector {
+    BlockVector::new(position.x + dx, position.y + dy, position.z + dz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerSettings;
+    use crate::testing::{block_at, flat_world, server, GROUND};
+
+    /// The grass block the tests tick, on the surface of a flat world
+    const GRASS: BlockVector = BlockVector { x: 8, y: GROUND - 1, z: 8 };
+
+    /// Gives a block many random ticks
+    async fn tick_many(server: &GameServer, position: BlockVector) {
+        for _ in 0..200 {
+            random_tick(server, position).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn spreads_to_lit_dirt() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        let dirt = offset(GRASS, 1, 0, 0);
+        server.set_block(dirt, Block::new(BlockType::Dirt)).await.unwrap();
+
+        tick_many(&server, GRASS).await;
+        assert_eq!(block_at(&server, dirt.x, dirt.y, dirt.z).block_type(), Some(BlockType::Grass));
+    }
+
+    #[tokio::test]
+    async fn does_not_spread_to_covered_dirt() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        let dirt = offset(GRASS, 1, 0, 0);
+        server.set_block(dirt, Block::new(BlockType::Dirt)).await.unwrap();
+        server.set_block(offset(dirt, 0, 1, 0), Block::new(BlockType::Stone)).await.unwrap();
+
+        tick_many(&server, GRASS).await;
+        assert_eq!(block_at(&server, dirt.x, dirt.y, dirt.z).block_type(), Some(BlockType::Dirt));
+        // The dirt below the grass is dark too
+        assert_eq!(block_at(&server, GRASS.x, GRASS.y - 1, GRASS.z).block_type(), Some(BlockType::Dirt));
+    }
+
+    #[tokio::test]
+    async fn dies_in_the_dark_and_under_water() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        server.set_block(offset(GRASS, 0, 1, 0), Block::new(BlockType::Stone)).await.unwrap();
+        let flooded = offset(GRASS, 4, 0, 0);
+        server.set_block(offset(flooded, 0, 1, 0), Block::new(BlockType::Water)).await.unwrap();
+
+        random_tick(&server, GRASS).await.unwrap();
+        random_tick(&server, flooded).await.unwrap();
+        assert_eq!(block_at(&server, GRASS.x, GRASS.y, GRASS.z).block_type(), Some(BlockType::Dirt));
+        assert_eq!(block_at(&server, flooded.x, flooded.y, flooded.z).block_type(), Some(BlockType::Dirt));
+    }
+
+    #[tokio::test]
+    async fn dim_grass_survives_without_spreading() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+
+        // A stone room lit only through a gap in the far wall
+        for x in 7..=15 {
+            for z in 7..=11 {
+                let on_edge = x == 7 || x == 15 || z == 7 || z == 11;
+                for y in GROUND..=GROUND + 1 {
+                    if on_edge && (x, y, z) != (15, GROUND, 9) {
+                        server.set_block(BlockVector::new(x, y, z), Block::new(BlockType::Stone)).await.unwrap();
+                    }
+                }
+                server.set_block(BlockVector::new(x, GROUND + 2, z), Block::new(BlockType::Stone)).await.unwrap();
+            }
+        }
+        let dirt = offset(GRASS, 0, 0, 1);
+        server.set_block(dirt, Block::new(BlockType::Dirt)).await.unwrap();
+
+        let light = server.world.light_level(offset(GRASS, 0, 1, 0)).unwrap();
+        assert!((MIN_LIGHT..SPREAD_LIGHT).contains(&light), "light {}", light);
+        tick_many(&server, GRASS).await;
+        assert_eq!(block_at(&server, GRASS.x, GRASS.y, GRASS.z).block_type(), Some(BlockType::Grass));
+        assert_eq!(block_at(&server, dirt.x, dirt.y, dirt.z).block_type(), Some(BlockType::Dirt));
+    }
+}
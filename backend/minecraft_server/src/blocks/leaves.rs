@@ -0,0 +1,148 @@
+use crate::server::GameServer;
+use anyhow::Result;
+use minecraft_core::block::{Block, BlockType};
+use minecraft_core::math::BlockVector;
+use minecraft_world::world::World;
+use std::collections::{HashSet, VecDeque};
+
+/// The furthest leaves can be from a log, counting steps through other leaves, and not decay
+pub const MAX_LOG_DISTANCE: u32 = 6;
+
+/// The six blocks touching a block
+const NEIGHBOURS: [(i32, i32, i32); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+/// Checks whether a block is leaves
+pub fn is_affected(block: Block) -> bool {
+    matches!(
+        block.block_type(),
+        Some(BlockType::OakLeaves | BlockType::BirchLeaves | BlockType::SpruceLeaves)
+    )
+}
+
+/// Removes leaves that are no longer held up by a log
+///
+/// Leaves with the `persistent` state never decay; that's the state leaves
+/// placed by players should get. Leaves whose metadata isn't a valid state
+/// decay like any others.
+pub async fn random_tick(server: &GameServer, position: BlockVector, block: Block) -> Result<()> {
+    let persistent = block.state().ok().and_then(|state| state.persistent()).unwrap_or(false);
+    if persistent || is_attached(&server.world, position) {
+        return Ok(());
+    }
+    server.set_block(position, Block::default()).await
+}
+
+/// Checks whether leaves connect to a log within `MAX_LOG_DISTANCE` steps
+///
+/// The search only passes through other leaves. Leaves next to a chunk that
+/// isn't loaded count as attached, so trees on the edge of the loaded world
+/// don't fall apart.
+fn is_attached(world: &World, position: BlockVector) -> bool {
+    let mut visited = HashSet::from([position]);
+    let mut queue = VecDeque::from([(position, 0)]);
+
+    while let Some((current, distance)) = queue.pop_front() {
+        for (dx, dy, dz) in NEIGHBOURS {
+            let neighbour = BlockVector::new(current.x + dx, current.y + dy, current.z + dz);
+            let Some(block) = world.get_block_if_loaded(neighbour) else {
+                return true;
+            };
+            if is_log(block) {
+                return true;
+            }
+            if distance + 1 < MAX_LOG_DISTANCE && is_affected(block) && visited.insert(neighbour) {
+                queue.push_back((neighbour, distance + 1));
+            }
+        }
+    }
+
+    false
+}
+
+/// Checks whether a block is a log
+fn is_log(block: Block) -> bool {
+    matches!(
+        block.block_type(),
+        Some(BlockType::OakLog | BlockType::BirchLog | BlockType::SpruceLog)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerSettings;
+    use crate::testing::{block_at, flat_world, server, GROUND};
+    use minecraft_core::block::{BlockState, StateValue};
+
+    /// The log the leaves in these tests hang from
+    const LOG: BlockVector = BlockVector { x: 8, y: GROUND, z: 8 };
+
+    /// Builds a log with a line of leaves running away from it along x
+    async fn build_branch(server: &GameServer, length: i32) {
+        server.set_block(LOG, Block::new(BlockType::OakLog)).await.unwrap();
+        for step in 1..=length {
+            server.set_block(leaf(step), Block::new(BlockType::OakLeaves)).await.unwrap();
+        }
+    }
+
+    /// Returns the position of the leaves a number of steps from the log
+    fn leaf(step: i32) -> BlockVector {
+        BlockVector::new(LOG.x + step, LOG.y, LOG.z)
+    }
+
+    /// Gives the leaves a number of steps from the log a random tick
+    async fn tick(server: &GameServer, step: i32) {
+        let position = leaf(step);
+        random_tick(server, position, block_at(server, position.x, position.y, position.z)).await.unwrap();
+    }
+
+    /// Checks whether there are still leaves a number of steps from the log
+    fn has_leaves(server: &GameServer, step: i32) -> bool {
+        let position = leaf(step);
+        is_affected(block_at(server, position.x, position.y, position.z))
+    }
+
+    #[tokio::test]
+    async fn leaves_further_than_six_blocks_from_a_log_decay() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        build_branch(&server, 7).await;
+
+        tick(&server, 7).await;
+        assert!(!has_leaves(&server, 7));
+        for step in 1..=6 {
+            tick(&server, step).await;
+            assert!(has_leaves(&server, step));
+        }
+    }
+
+    #[tokio::test]
+    async fn leaves_decay_once_the_log_is_gone() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        build_branch(&server, 3).await;
+        server.set_block(LOG, Block::default()).await.unwrap();
+
+        for step in (1..=3).rev() {
+            tick(&server, step).await;
+            assert!(!has_leaves(&server, step));
+        }
+    }
+
+    #[tokio::test]
+    async fn persistent_leaves_never_decay() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        let persistent = BlockState::new(BlockType::OakLeaves)
+            .with("persistent", StateValue::Bool(true))
+            .unwrap();
+        server.set_block(leaf(1), Block::from(persistent)).await.unwrap();
+
+        tick(&server, 1).await;
+        assert!(has_leaves(&server, 1));
+    }
+}
@@ -0,0 +1,154 @@
+pub mod falling;
+pub mod fire;
+pub mod fluid;
+pub mod grass;
+pub mod leaves;
+pub mod plants;
+
+use crate::server::GameServer;
+use anyhow::Result;
+use log::warn;
+use minecraft_core::block::{Block, BlockType};
+use minecraft_core::constants::CHUNK_SIZE;
+use minecraft_core::math::{BlockVector, ChunkVector};
+use rand::Rng;
+
+/// Processes the block updates due this tick
+///
+/// Each update is handed to the behaviour of the block now at its position.
+/// Updates for blocks in chunks that aren't loaded are dropped rather than
+/// loading the chunk.
+pub async fn process_updates(server: &GameServer) {
+    let updates = server.world.take_block_updates();
+
+    for position in updates.neighbour_updates {
+        let Some(block) = server.world.get_block_if_loaded(position) else {
+            continue;
+        };
+        if let Err(err) = neighbour_update(server, position, block).await {
+            warn!("Failed to update the block at {:?}: {}", position, err);
+        }
+    }
+
+    for position in updates.scheduled_ticks {
+        let Some(block) = server.world.get_block_if_loaded(position) else {
+            continue;
+        };
+        if let Err(err) = scheduled_tick(server, position, block).await {
+            warn!("Failed to tick the block at {:?}: {}", position, err);
+        }
+    }
+}
+
+/// Gives blocks near players their random ticks for this tick
+///
+/// Each loaded chunk within the simulation distance of a player, at any
+/// height, has `random_tick_speed` blocks picked at random, and each is handed
+/// to the random tick behaviour of its block. Chunks are one section tall, so
+/// this is the same as picking per section; empty sections are skipped.
+pub async fn process_random_ticks(server: &GameServer) {
+    for (position, block) in pick_random_ticks(server) {
+        if let Err(err) = random_tick(server, position, block).await {
+            warn!("Failed to random tick the block at {:?}: {}", position, err);
+        }
+    }
+}
+
+/// Picks the blocks that get a random tick this tick
+///
+/// Only blocks with a random tick behaviour are returned.
+fn pick_random_ticks(server: &GameServer) -> Vec<(BlockVector, Block)> {
+    let count = server.settings.gameplay.random_tick_speed;
+    let player_chunks: Vec<ChunkVector> = server
+        .sessions
+        .all()
+        .iter()
+        .map(|session| session.position.read().unwrap().to_chunk())
+        .collect();
+    if count == 0 || player_chunks.is_empty() {
+        return Vec::new();
+    }
+
+    let simulation_distance = server.settings.server.simulation_distance as i32;
+    let radius_squared = simulation_distance * simulation_distance;
+    let mut rng = rand::thread_rng();
+    let mut picked = Vec::new();
+
+    for chunk in server.world.chunk_provider.loaded_chunks() {
+        let near_player = player_chunks.iter().any(|player| {
+            let dx = chunk.position.x - player.x;
+            let dz = chunk.position.z - player.z;
+            dx * dx + dz * dz <= radius_squared
+        });
+        if !near_player {
+            continue;
+        }
+
+        let Some(section) = chunk.get_section(0) else {
+            continue;
+        };
+        let section = section.read().unwrap();
+        if section.is_empty() {
+            continue;
+        }
+
+        let origin = chunk.position.to_block();
+        for _ in 0..count {
+            let (x, y, z) = (
+                rng.gen_range(0..CHUNK_SIZE.0),
+                rng.gen_range(0..CHUNK_SIZE.1),
+                rng.gen_range(0..CHUNK_SIZE.2),
+            );
+            let block = section.get_block(x, y, z);
+            if has_random_tick(block) {
+                let position = BlockVector::new(origin.x + x as i32, origin.y + y as i32, origin.z + z as i32);
+                picked.push((position, block));
+            }
+        }
+    }
+
+    picked
+}
+
+/// Checks whether a block does anything on a random tick
+fn has_random_tick(block: Block) -> bool {
+    matches!(
+        block.block_type(),
+        Some(BlockType::Grass | BlockType::Lava | BlockType::TallGrass | BlockType::Flower)
+    ) || leaves::is_affected(block)
+}
+
+/// Lets a block picked for a random tick act
+async fn random_tick(server: &GameServer, position: BlockVector, block: Block) -> Result<()> {
+    match block.block_type() {
+        Some(BlockType::Grass) => grass::random_tick(server, position).await,
+        Some(BlockType::Lava) => fire::lava_tick(server, position).await,
+        Some(BlockType::TallGrass | BlockType::Flower) => plants::random_tick(server, position, block).await,
+        _ if leaves::is_affected(block) => leaves::random_tick(server, position, block).await,
+        _ => Ok(()),
+    }
+}
+
+/// Lets a block react to itself or a neighbour changing
+async fn neighbour_update(server: &GameServer, position: BlockVector, block: Block) -> Result<()> {
+    if falling::is_affected(block) {
+        falling::neighbour_update(server, position);
+    } else if fluid::is_affected(block) {
+        fluid::neighbour_update(server, position, block).await?;
+    } else if fire::is_affected(block) {
+        fire::neighbour_update(server, position).await?;
+    }
+    Ok(())
+}
+
+/// Runs a tick a block scheduled earlier
+async fn scheduled_tick(server: &GameServer, position: BlockVector, block: Block) -> Result<()> {
+    if falling::is_affected(block) {
+        falling::scheduled_tick(server, position, block).await?;
+    } else if fluid::is_affected(block) {
+        fluid::scheduled_tick(server, position, block).await?;
+    } else if fire::is_affected(block) {
+        fire::scheduled_tick(server, position, block).await?;
+    }
+    Ok(())
+}
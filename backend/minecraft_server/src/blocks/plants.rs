@@ -0,0 +1,140 @@
+use crate::server::GameServer;
+use anyhow::Result;
+use minecraft_core::block::{Block, BlockType};
+use minecraft_core::math::BlockVector;
+use minecraft_world::world::World;
+use rand::Rng;
+
+/// The chance a plant spreads when it gets a random tick
+const SPREAD_CHANCE: f64 = 0.2;
+
+/// How far (blocks) horizontally a plant spreads
+const SPREAD_RADIUS: i32 = 2;
+
+/// The light a spot needs for a plant to spread to it
+const MIN_LIGHT: u8 = 9;
+
+/// How far (blocks) horizontally to look for plants of the same kind when checking for crowding
+const CROWDING_RADIUS: i32 = 4;
+
+/// The most plants of the same kind there can be around a spot for another to grow there
+const MAX_CROWDING: usize = 5;
+
+/// Lets tall grass and flowers spread to a nearby lit spot on grass
+///
+/// A plant only spreads to an empty spot on top of a grass block, and not
+/// where plants of its kind are already crowded. The new plant copies the
+/// old one, metadata and all.
+pub async fn random_tick(server: &GameServer, position: BlockVector, block: Block) -> Result<()> {
+    if let Some(target) = spread_target(&server.world, position, block) {
+        server.set_block(target, block).await?;
+    }
+    Ok(())
+}
+
+/// Picks where a plant spreads to this time, if anywhere
+fn spread_target(world: &World, position: BlockVector, plant: Block) -> Option<BlockVector> {
+    let mut rng = rand::thread_rng();
+    if !rng.gen_bool(SPREAD_CHANCE) {
+        return None;
+    }
+
+    let target = offset(
+        position,
+        rng.gen_range(-SPREAD_RADIUS..=SPREAD_RADIUS),
+        rng.gen_range(-1..=1),
+        rng.gen_range(-SPREAD_RADIUS..=SPREAD_RADIUS),
+    );
+    if !world.get_block_if_loaded(target)?.is_air() {
+        return None;
+    }
+    if world.get_block_if_loaded(offset(target, 0, -1, 0))?.block_type() != Some(BlockType::Grass) {
+        return None;
+    }
+    if world.light_level(target)? < MIN_LIGHT || is_crowded(world, target, plant) {
+        return None;
+    }
+
+    Some(target)
+}
+
+/// Checks whether there are too many plants of the same kind around a spot
+fn is_crowded(world: &World, position: BlockVector, plant: Block) -> bool {
+    let mut nearby = 0;
+    for dx in -CROWDING_RADIUS..=CROWDING_RADIUS {
+        for dy in -1..=1 {
+            for dz in -CROWDING_RADIUS..=CROWDING_RADIUS {
+                let block = world.get_block_if_loaded(offset(position, dx, dy, dz));
+                if block.is_some_and(|block| block.block_type == plant.block_type) {
+                    nearby += 1;
+                    if nearby >= MAX_CROWDING {
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Returns the position offset from another by the specified amounts
+fn offset(position: BlockVector, dx: i32, dy: i32, dz: i32) -> BlockVector {
+    BlockVector::new(position.x + dx, position.y + dy, position.z + dz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerSettings;
+    use crate::testing::{block_at, flat_world, server, GROUND};
+
+    /// The spot the tests check for crowding, on the ground of a flat world
+    const SPOT: BlockVector = BlockVector { x: 8, y: GROUND, z: 8 };
+
+    /// Plants a number of blocks around the spot, within the crowding radius
+    async fn plant_around(server: &GameServer, block_type: BlockType, count: usize) {
+        let around = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (-1, -1)];
+        for (dx, dz) in around.into_iter().take(count) {
+            server.set_block(offset(SPOT, dx, 0, dz), Block::new(block_type)).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn crowding_caps_plants_of_the_same_kind() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        let flower = Block::new(BlockType::Flower);
+
+        plant_around(&server, BlockType::Flower, MAX_CROWDING - 1).await;
+        assert!(!is_crowded(&server.world, SPOT, flower));
+
+        plant_around(&server, BlockType::Flower, MAX_CROWDING).await;
+        assert!(is_crowded(&server.world, SPOT, flower));
+        // Other kinds of plant don't crowd each other
+        assert!(!is_crowded(&server.world, SPOT, Block::new(BlockType::TallGrass)));
+    }
+
+    #[tokio::test]
+    async fn plants_spread_onto_lit_grass() {
+        let server = server(flat_world(), ServerSettings::default()).await;
+        let grass = Block::new(BlockType::TallGrass);
+        server.set_block(SPOT, grass).await.unwrap();
+
+        for _ in 0..200 {
+            random_tick(&server, SPOT, grass).await.unwrap();
+        }
+
+        let mut planted = 0;
+        for x in SPOT.x - SPREAD_RADIUS..=SPOT.x + SPREAD_RADIUS {
+            for z in SPOT.z - SPREAD_RADIUS..=SPOT.z + SPREAD_RADIUS {
+                if block_at(&server, x, GROUND, z) == grass {
+                    planted += 1;
+                }
+                // Nothing grows in the air or underground
+                assert_ne!(block_at(&server, x, GROUND + 1, z), grass);
+                assert_ne!(block_at(&server, x, GROUND - 1, z), grass);
+            }
+        }
+        assert!(planted > 1);
+        assert!(planted <= MAX_CROWDING);
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+/// The complete server configuration, as stored in `server.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+    /// General server settings
+    pub server: ServerSection,
+    /// Default world settings
+    pub world: WorldSection,
+    /// Gameplay rules
+    pub gameplay: GameplaySection,
+    /// Performance tuning
+    pub performance: PerformanceSection,
+    /// Logging settings
+    pub logging: LoggingSection,
+}
+
+/// The `server` section of the configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerSection {
+    /// The server name
+    pub name: String,
+    /// The server description
+    pub description: String,
+    /// The game port
+    pub port: u16,
+    /// The HTTP API port
+    pub api_port: u16,
+    /// The maximum number of players
+    pub max_players: usize,
+    /// Whether to enable authentication
+    pub authentication: bool,
+    /// Whether to enable PvP
+    pub pvp_enabled: bool,
+    /// The view distance (chunks)
+    pub view_distance: u32,
+    /// The simulation distance (chunks)
+    pub simulation_distance: u32,
+    /// The directory containing the worlds, relative to the data path
+    pub worlds_path: String,
+    /// The block definitions file, relative to the directory of the config file
+    pub blocks_path: String,
+    /// The item definitions file, relative to the directory of the config file
+    pub items_path: String,
+    /// The message of the day sent to joining players
+    pub motd: String,
+}
+
+impl Default for ServerSection {
+    fn default() -> Self {
+        Self {
+            name: "Minecraft Clone Server".to_string(),
+            description: "A Minecraft clone server".to_string(),
+            port: 8080,
+            api_port: 8081,
+            max_players: minecraft_core::constants::DEFAULT_MAX_PLAYERS,
+            authentication: true,
+            pvp_enabled: true,
+            view_distance: minecraft_core::constants::DEFAULT_RENDER_DISTANCE,
+            simulation_distance: minecraft_core::constants::DEFAULT_SIMULATION_DISTANCE,
+            worlds_path: "worlds".to_string(),
+            blocks_path: "blocks.json".to_string(),
+            items_path: "items.json".to_string(),
+            motd: "Welcome to the Minecraft Clone Server!".to_string(),
+        }
+    }
+}
+
+/// A position in the configuration file
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PositionSetting {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// The `world` section of the configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldSection {
+    /// The name of the world to load or create on startup
+    pub default_world: String,
+    /// The generator used when creating the default world
+    pub default_generator: String,
+    /// Where the default world stores its chunks ("region", "sqlite" or "memory")
+    pub storage_type: String,
+    /// The seed used when creating the default world
+    pub seed: i64,
+    /// The spawn position used when creating the default world
+    pub spawn_position: PositionSetting,
+    /// Whether structures should be generated
+    pub generate_structures: bool,
+    /// Multiplier for the speed of the day/night cycle
+    pub day_night_cycle_speed: f32,
+    /// Seconds between autosaves of modified chunks (0 disables autosave)
+    pub autosave_interval: u64,
+}
+
+impl Default for WorldSection {
+    fn default() -> Self {
+        Self {
+            default_world: "main".to_string(),
+            default_generator: "overworld".to_string(),
+            storage_type: "region".to_string(),
+            seed: minecraft_core::constants::DEFAULT_SEED as i64,
+            spawn_position: PositionSetting { x: 0, y: 70, z: 0 },
+            generate_structures: true,
+            day_night_cycle_speed: 1.0,
+            autosave_interval: 300,
+        }
+    }
+}
+
+/// The `gameplay` section of the configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameplaySection {
+    /// The game mode for new players
+    pub default_gamemode: String,
+    /// The difficulty level
+    pub difficulty: String,
+    /// Whether players may fly
+    pub allow_flight: bool,
+    /// Whether fire spreads and damages
+    pub fire_damage: bool,
+    /// Whether fall damage is applied
+    pub fall_damage: bool,
+    /// Whether mobs spawn naturally
+    pub mob_spawning: bool,
+    /// Whether mobs and environmental effects may destroy blocks
+    pub mob_griefing: bool,
+    /// The number of blocks picked in each chunk section every tick for a random tick
+    pub random_tick_speed: u32,
+}
+
+impl Default for GameplaySection {
+    fn default() -> Self {
+        Self {
+            default_gamemode: "survival".to_string(),
+            difficulty: "normal".to_string(),
+            allow_flight: false,
+            fire_damage: true,
+            fall_damage: true,
+            mob_spawning: true,
+            mob_griefing: true,
+            random_tick_speed: 3,
+        }
+    }
+}
+
+/// The `performance` section of the configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PerformanceSection {
+    /// The target number of ticks per second
+    pub max_tps: u32,
+    /// The maximum number of entities per chunk
+    pub max_entities_per_chunk: usize,
+    /// The range (blocks) in which entities are simulated
+    pub entity_activation_range: u32,
+    /// The maximum number of chunk bytes sent to each player per tick
+    pub chunk_bytes_per_tick: usize,
+    /// The most chunks kept loaded before unused ones are unloaded (0 for no limit)
+    pub max_loaded_chunks: usize,
+    /// The most memory (MiB) loaded chunks may use before unused ones are unloaded (0 for no limit)
+    pub chunk_memory_budget_mb: usize,
+}
+
+impl Default for PerformanceSection {
+    fn default() -> Self {
+        Self {
+            max_tps: minecraft_core::constants::TICK_RATE,
+            max_entities_per_chunk: 100,
+            entity_activation_range: 32,
+            chunk_bytes_per_tick: 256 * 1024,
+            max_loaded_chunks: 16384,
+            chunk_memory_budget_mb: 512,
+        }
+    }
+}
+
+/// The `logging` section of the configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingSection {
+    /// The log level
+    pub level: String,
+    /// Whether connections and disconnections are logged
+    pub log_connections: bool,
+}
+
+impl Default for LoggingSection {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            log_connections: true,
+        }
+    }
+}
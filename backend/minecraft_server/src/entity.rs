@@ -0,0 +1,83 @@
+use minecraft_core::block::Block;
+use minecraft_core::math::WorldVector;
+use minecraft_core::physics::PhysicsState;
+use minecraft_net::protocol::{EntityKind, ServerMessage};
+use std::collections::HashMap;
+use tokio::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+/// The size of a falling block entity, just under a block so it fits down a one-block hole
+const FALLING_BLOCK_SIZE: f32 = 0.98;
+
+/// An entity other than a player, simulated by the server
+#[derive(Debug, Clone)]
+pub struct Entity {
+    /// The entity's unique ID
+    pub id: Uuid,
+    /// What the entity is
+    pub kind: EntityKind,
+    /// The entity's physics state
+    pub physics: PhysicsState,
+}
+
+impl Entity {
+    /// Creates a falling block entity centred on the specified position
+    pub fn falling_block(block: Block, position: WorldVector) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind: EntityKind::FallingBlock { block },
+            physics: PhysicsState::new(position, (FALLING_BLOCK_SIZE, FALLING_BLOCK_SIZE, FALLING_BLOCK_SIZE)),
+        }
+    }
+
+    /// Returns the message telling players the entity appeared
+    pub fn spawn_message(&self) -> ServerMessage {
+        ServerMessage::EntitySpawned {
+            entity_id: self.id,
+            entity: self.kind,
+            position: self.physics.position,
+            velocity: self.physics.velocity,
+        }
+    }
+
+    /// Returns the message telling players where the entity is now
+    pub fn move_message(&self) -> ServerMessage {
+        ServerMessage::EntityMoved {
+            entity_id: self.id,
+            position: self.physics.position,
+            velocity: self.physics.velocity,
+        }
+    }
+}
+
+/// The non-player entities in the world
+///
+/// The map stays locked while entities are stepped, which waits on the
+/// world, so it sits behind an async mutex.
+#[derive(Debug, Default)]
+pub struct EntityManager {
+    /// The entities, keyed by ID
+    entities: Mutex<HashMap<Uuid, Entity>>,
+}
+
+impl EntityManager {
+    /// Creates an empty entity manager
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an entity
+    pub async fn add(&self, entity: Entity) {
+        self.entities.lock().await.insert(entity.id, entity);
+    }
+
+    /// Returns a copy of every entity
+    pub async fn all(&self) -> Vec<Entity> {
+        self.entities.lock().await.values().cloned().collect()
+    }
+
+    /// Locks the entities for stepping
+    pub async fn lock(&self) -> MutexGuard<'_, HashMap<Uuid, Entity>> {
+        self.entities.lock().await
+    }
+}
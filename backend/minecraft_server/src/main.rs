@@ -1,9 +1,93 @@
+mod autosave;
+mod blocks;
+mod config;
+mod entity;
+mod movement;
+mod network;
+mod server;
+#[cfg(test)]
+mod testing;
+mod tick;
+mod unloader;
+
+use std::path::Path;
 use std::sync::Arc;
-use std::path::PathBuf;
-use log::{info, error};
-use tokio::sync::Mutex;
+use log::{info, warn, error};
 use warp::Filter;
 use anyhow::Result;
+use minecraft_core::block::BlockRegistry;
+use minecraft_core::item::ItemRegistry;
+use minecraft_util::config::load_config;
+use minecraft_world::world::World;
+use minecraft_world::world::loader::{WorldConfig, WorldLoader};
+use autosave::Autosave;
+use config::ServerSettings;
+use server::GameServer;
+use tick::TickLoop;
+use unloader::ChunkUnloader;
+
+/// Loads the server configuration, falling back to defaults if the file is missing
+fn load_settings(path: &Path) -> Result<ServerSettings> {
+    if !path.exists() {
+        warn!("Config file {} not found, using defaults", path.display());
+        return Ok(ServerSettings::default());
+    }
+    
+    load_config(path)
+}
+
+/// Loads the block definitions, falling back to the built-in blocks if the file is missing
+fn load_blocks(path: &Path) -> Result<BlockRegistry> {
+    if !path.exists() {
+        warn!("Block definitions {} not found, using built-in blocks", path.display());
+        return BlockRegistry::builtin();
+    }
+    
+    BlockRegistry::load(path)
+}
+
+/// Loads the item definitions, falling back to the built-in items if the file is missing
+fn load_items(path: &Path) -> Result<ItemRegistry> {
+    if !path.exists() {
+        warn!("Item definitions {} not found, using built-in items", path.display());
+        return ItemRegistry::builtin();
+    }
+    
+    ItemRegistry::load(BlockRegistry::global(), path)
+}
+
+/// Loads the default world, creating it if it doesn't exist yet
+async fn load_world(settings: &ServerSettings, loader: &WorldLoader) -> Result<World> {
+    let world_settings = &settings.world;
+    
+    let existing = loader
+        .list_worlds()
+        .await?
+        .into_iter()
+        .find(|config| config.name == world_settings.default_world);
+    
+    if let Some(config) = existing {
+        info!("Loading world '{}' ({})", config.name, config.id);
+        return loader.load_world(config.id).await;
+    }
+    
+    info!("Creating world '{}'", world_settings.default_world);
+    let config = WorldConfig {
+        name: world_settings.default_world.clone(),
+        seed: world_settings.seed,
+        generator_type: world_settings.default_generator.clone(),
+        storage_type: world_settings.storage_type.clone(),
+        spawn_position: (
+            world_settings.spawn_position.x,
+            world_settings.spawn_position.y,
+            world_settings.spawn_position.z,
+        ),
+        generate_structures: world_settings.generate_structures,
+        ..WorldConfig::default()
+    };
+    
+    loader.create_world(config).await
+}
 
 async fn start_server() -> Result<()> {
     info!("Starting Minecraft Clone Server...");
@@ -30,11 +114,36 @@ async fn start_server() -> Result<()> {
     info!("Config path: {}", config_path);
     info!("Data path: {}", data_path);
     
-    // TODO: Load configuration
+    // Load configuration
+    let settings = load_settings(Path::new(&config_path))?;
+    
+    // Load block and item definitions before anything looks them up
+    let config_dir = Path::new(&config_path).parent().unwrap_or(Path::new("."));
+    let blocks = load_blocks(&config_dir.join(&settings.server.blocks_path))?;
+    BlockRegistry::install(blocks)?;
+    let items = load_items(&config_dir.join(&settings.server.items_path))?;
+    ItemRegistry::install(items)?;
     
-    // TODO: Initialize world
+    // Initialize world
+    let loader = WorldLoader::new(Path::new(&data_path).join(&settings.server.worlds_path));
+    let world = Arc::new(load_world(&settings, &loader).await?);
+    let server = Arc::new(GameServer::new(settings, world));
     
-    // TODO: Setup WebSocket server for game connections
+    // Start the game loop
+    tokio::spawn(TickLoop::new(server.clone()).run());
+    
+    // Start saving modified chunks in the background
+    if let Some(autosave) = Autosave::new(server.clone()) {
+        tokio::spawn(autosave.run());
+    }
+    
+    // Start unloading chunks no player needs
+    tokio::spawn(ChunkUnloader::new(server.clone()).run());
+    
+    // Setup WebSocket server for game connections
+    let game_server = warp::serve(network::routes(server.clone()))
+        .run(([0, 0, 0, 0], server_port));
+    tokio::spawn(game_server);
     
     // Setup HTTP API server
     let routes = warp::path("health")
@@ -53,6 +162,11 @@ async fn start_server() -> Result<()> {
     tokio::signal::ctrl_c().await?;
     info!("Shutting down server...");
     
+    // Save the world and the players still online so nothing is lost on restart
+    server.save_players(&server.sessions.all()).await?;
+    loader.save_world(&server.world).await?;
+    info!("World saved");
+    
     Ok(())
 }
 
@@ -63,7 +177,7 @@ async fn main() -> Result<()> {
     
     // Start the server
     if let Err(e) = start_server().await {
-        error!("Server error: {}", e);
+        error!("Server error: {:#}", e);
         std::process::exit(1);
     }
     
@@ -0,0 +1,253 @@
+use anyhow::Result;
+use minecraft_core::block::Block;
+use minecraft_core::constants::{PLAYER_HEIGHT, PLAYER_WIDTH};
+use minecraft_core::math::{block_to_local, BlockVector, ChunkVector, WorldVector};
+use minecraft_core::physics::collision::get_blocks_to_check;
+use minecraft_core::physics::movement::{calculate_movement_input, update_physics};
+use minecraft_core::physics::PhysicsState;
+use minecraft_net::protocol::{MovementFlags, MovementKeys};
+use minecraft_world::world::World;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// The most inputs kept waiting for a player
+///
+/// Older inputs are dropped once the queue is full, so a client can't build
+/// up a backlog and then replay it all at once.
+const MAX_QUEUED_INPUTS: usize = 10;
+
+/// The most inputs applied for a player in one tick
+///
+/// Allows a little catch-up after network jitter. The inputs applied in a
+/// tick share that tick's time, so sending more of them doesn't move a
+/// player any faster.
+const MAX_INPUTS_PER_TICK: usize = 3;
+
+/// How far (in blocks) around the player to look for colliding blocks
+const COLLISION_RADIUS: i32 = 2;
+
+/// One tick of player input
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlayerInput {
+    /// The client's sequence number for this input
+    pub sequence: u32,
+    /// The movement keys held
+    pub keys: MovementKeys,
+    /// Pitch and yaw in radians
+    pub orientation: [f32; 2],
+    /// Whether the player wants to jump
+    pub jump: bool,
+    /// Sprinting, sneaking and flying
+    pub flags: MovementFlags,
+}
+
+/// The result of stepping a player's movement for one tick
+#[derive(Debug, Clone, Copy)]
+pub struct MovementUpdate {
+    /// The sequence of the last input applied
+    pub sequence: u32,
+    /// Whether any new input was applied
+    pub applied_input: bool,
+    /// Whether the player's position changed
+    pub moved: bool,
+}
+
+/// The server-side movement state of a player
+///
+/// Clients send their input every tick; the server steps the player's
+/// physics with those inputs against the real world blocks, so the
+/// resulting position is authoritative.
+pub struct PlayerMovement {
+    /// The player's physics state
+    pub physics: PhysicsState,
+    /// Inputs received but not yet applied
+    inputs: VecDeque<PlayerInput>,
+    /// The last input applied, repeated when no new input arrives
+    last_input: PlayerInput,
+}
+
+impl PlayerMovement {
+    /// Creates the movement state for a player at the specified position
+    pub fn new(position: WorldVector) -> Self {
+        Self {
+            physics: PhysicsState::new(position, (PLAYER_WIDTH, PLAYER_HEIGHT, PLAYER_WIDTH)),
+            inputs: VecDeque::new(),
+            last_input: PlayerInput::default(),
+        }
+    }
+
+    /// Returns the orientation from the player's last input
+    pub fn orientation(&self) -> [f32; 2] {
+        self.last_input.orientation
+    }
+
+    /// Queues an input to be applied on a later tick
+    ///
+    /// Inputs with a non-finite orientation are dropped, since they would
+    /// turn the player's position into NaN. Returns whether the input was
+    /// queued.
+    pub fn queue_input(&mut self, input: PlayerInput) -> bool {
+        if !input.orientation.iter().all(|angle| angle.is_finite()) {
+            return false;
+        }
+
+        if self.inputs.len() >= MAX_QUEUED_INPUTS {
+            self.inputs.pop_front();
+        }
+        self.inputs.push_back(input);
+        true
+    }
+
+    /// Applies the queued inputs for one tick
+    ///
+    /// The tick's time is split evenly between the inputs applied, so the
+    /// player is never simulated for longer than the tick. When no input
+    /// arrived the last one is repeated without the jump, so gravity and held
+    /// keys keep acting between packets.
+    pub async fn step(
+        &mut self,
+        world: &World,
+        delta_time: Duration,
+        allow_flight: bool,
+    ) -> Result<MovementUpdate> {
+        let start = self.physics.position;
+        let mut applied_input = false;
+
+        let count = self.inputs.len().min(MAX_INPUTS_PER_TICK);
+        for input in self.inputs.drain(..count).collect::<Vec<_>>() {
+            self.apply(world, input, delta_time / count as u32, allow_flight).await?;
+            self.last_input = input;
+            applied_input = true;
+        }
+
+        if !applied_input {
+            let idle = PlayerInput {
+                jump: false,
+                ..self.last_input
+            };
+            self.apply(world, idle, delta_time, allow_flight).await?;
+        }
+
+        Ok(MovementUpdate {
+            sequence: self.last_input.sequence,
+            applied_input,
+            moved: self.physics.position != start,
+        })
+    }
+
+    /// Steps the physics state with a single input
+    async fn apply(
+        &mut self,
+        world: &World,
+        input: PlayerInput,
+        delta_time: Duration,
+        allow_flight: bool,
+    ) -> Result<()> {
+        let state = &mut self.physics;
+        let keys = input.keys;
+
+        state.is_sprinting = input.flags.sprinting && !input.flags.sneaking;
+        state.is_sneaking = input.flags.sneaking;
+        state.is_flying = input.flags.flying && allow_flight;
+
+        // The fluid flags are only ever set by the physics step, so clear them first
+        state.in_water = false;
+        state.in_lava = false;
+
+        let move_input = calculate_movement_input(
+            keys.forward,
+            keys.backward,
+            keys.left,
+            keys.right,
+            keys.up,
+            keys.down,
+            input.orientation[1],
+        );
+        let blocks = nearby_blocks(world, &state.position).await?;
+
+        update_physics(state, delta_time, move_input, input.jump, &blocks);
+
+        Ok(())
+    }
+}
+
+/// Collects the blocks around a position that an entity could collide with
+pub(crate) async fn nearby_blocks(
+    world: &World,
+    position: &WorldVector,
+) -> Result<Vec<(i32, i32, i32, Block)>> {
+    let mut chunks = HashMap::new();
+    let mut blocks = Vec::new();
+
+    for (x, y, z) in get_blocks_to_check(position, COLLISION_RADIUS) {
+        let block_position = BlockVector::new(x, y, z);
+        let chunk_position: ChunkVector = block_position.to_chunk();
+
+        // Fetch each chunk once rather than once per block
+        let chunk = match chunks.get(&chunk_position) {
+            Some(chunk) => chunk,
+            None => {
+                let chunk = world.get_chunk(chunk_position).await?;
+                chunks.entry(chunk_position).or_insert(chunk)
+            }
+        };
+
+        let (local_x, local_y, local_z) = block_to_local(x, y, z);
+        let block = chunk
+            .get_block(local_x, local_y, local_z)
+            .unwrap_or_default();
+        if !block.is_air() {
+            blocks.push((x, y, z, block));
+        }
+    }
+
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::flat_world;
+
+    /// The length of a tick in the tests
+    const TICK: Duration = Duration::from_millis(50);
+
+    /// Returns how far a player walking forward moves when the client sends
+    /// the specified number of inputs every tick
+    async fn distance_walked(world: &World, inputs_per_tick: u32, ticks: u32) -> f32 {
+        let start = WorldVector::new(8.5, 6.0, 8.5);
+        let mut movement = PlayerMovement::new(start);
+        let mut sequence = 0;
+
+        for _ in 0..ticks {
+            for _ in 0..inputs_per_tick {
+                sequence += 1;
+                let mut input = PlayerInput { sequence, ..PlayerInput::default() };
+                input.keys.forward = true;
+                assert!(movement.queue_input(input));
+            }
+            movement.step(world, TICK, false).await.unwrap();
+        }
+
+        let offset = movement.physics.position - start;
+        (offset.x * offset.x + offset.z * offset.z).sqrt()
+    }
+
+    #[tokio::test]
+    async fn extra_inputs_do_not_speed_players_up() {
+        let world = flat_world();
+        let normal = distance_walked(&world, 1, 40).await;
+        let flooding = distance_walked(&world, MAX_INPUTS_PER_TICK as u32, 40).await;
+
+        assert!(normal > 1.0, "the player didn't walk ({})", normal);
+        assert!(flooding <= normal * 1.05, "{} inputs per tick walked {} against {}", MAX_INPUTS_PER_TICK, flooding, normal);
+    }
+
+    #[tokio::test]
+    async fn rejects_non_finite_orientation() {
+        let mut movement = PlayerMovement::new(WorldVector::new(0.0, 0.0, 0.0));
+        let input = PlayerInput { orientation: [f32::NAN, 0.0], ..PlayerInput::default() };
+        assert!(!movement.queue_input(input));
+        assert!(movement.queue_input(PlayerInput::default()));
+    }
+}
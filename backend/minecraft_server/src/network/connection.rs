@@ -0,0 +1,306 @@
+use crate::movement::PlayerInput;
+use crate::network::session::PlayerSession;
+use crate::network::streaming::ChunkStreamer;
+use crate::server::GameServer;
+use futures::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use minecraft_core::constants::TICK_LENGTH_MS;
+use minecraft_net::protocol::{ClientMessage, Encoding, ServerMessage, PROTOCOL_VERSION};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use warp::ws::{Message, WebSocket};
+
+/// How long a new connection has to send its handshake
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the server sends a keepalive
+pub const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long a connection may stay silent before it is dropped
+pub const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runs a game connection from handshake to disconnect
+pub async fn handle_connection(socket: WebSocket, server: Arc<GameServer>) {
+    let (mut ws_tx, mut ws_rx) = socket.split();
+
+    // Wait for the handshake, which also decides the encoding for the connection
+    let handshake = tokio::time::timeout(HANDSHAKE_TIMEOUT, read_message(&mut ws_rx)).await;
+    let (name, encoding) = match handshake {
+        Ok(Some((ClientMessage::Join { protocol_version, name }, encoding))) => {
+            if protocol_version != PROTOCOL_VERSION {
+                let reason = format!(
+                    "Unsupported protocol version {} (server speaks {})",
+                    protocol_version, PROTOCOL_VERSION
+                );
+                send_direct(&mut ws_tx, encoding, disconnect(&reason)).await;
+                return;
+            }
+            (name, encoding)
+        }
+        Ok(Some((_, encoding))) => {
+            send_direct(&mut ws_tx, encoding, disconnect("Expected a join message")).await;
+            return;
+        }
+        Ok(None) => return,
+        Err(_) => {
+            send_direct(&mut ws_tx, Encoding::Json, disconnect("Handshake timed out")).await;
+            return;
+        }
+    };
+
+    // Create the session
+    let (tx, mut rx) = mpsc::unbounded_channel::<ServerMessage>();
+    let streamer = ChunkStreamer::new(
+        server.settings.server.view_distance,
+        server.settings.performance.chunk_bytes_per_tick,
+    );
+    let mut session = PlayerSession::new(&name, server.world.clone(), encoding, streamer, tx);
+    if let Some(store) = &server.world.player_store {
+        match store.load_player(&name).await {
+            Ok(Some(profile)) => session.restore(&profile),
+            Ok(None) => {}
+            Err(err) => warn!("Failed to load the profile of {}: {}", name, err),
+        }
+    }
+    let session = match server.sessions.join(session) {
+        Ok(session) => session,
+        Err(err) => {
+            send_direct(&mut ws_tx, encoding, disconnect(&err.to_string())).await;
+            return;
+        }
+    };
+
+    if server.settings.logging.log_connections {
+        info!(
+            "Player {} ({}) joined, {} online",
+            session.name,
+            session.id,
+            server.sessions.count()
+        );
+    }
+
+    session.send(ServerMessage::Welcome {
+        protocol_version: PROTOCOL_VERSION,
+        player_id: session.id,
+        world_name: session.world.name.clone(),
+        spawn_position: session.world.spawn_position,
+        time: session.world.get_time(),
+        motd: server.settings.server.motd.clone(),
+    });
+    session.send(ServerMessage::Players {
+        players: server.sessions.player_list(),
+    });
+    for entity in server.entities.all().await {
+        session.send(entity.spawn_message());
+    }
+
+    // Forward queued messages to the socket until every sender is gone
+    let encoding = session.encoding;
+    let writer = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            let is_disconnect = matches!(message, ServerMessage::Disconnect { .. });
+            if !send_direct(&mut ws_tx, encoding, message).await || is_disconnect {
+                break;
+            }
+        }
+        let _ = ws_tx.close().await;
+    });
+
+    let stream_task = tokio::spawn(stream_chunks(session.clone()));
+
+    // Read messages and keep the connection alive
+    let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+    let mut last_seen = Instant::now();
+    let mut keepalive_id = 0u64;
+    let mut pending_keepalive = None;
+
+    loop {
+        tokio::select! {
+            message = read_message(&mut ws_rx) => {
+                let Some((message, _)) = message else {
+                    break;
+                };
+                last_seen = Instant::now();
+
+                match message {
+                    ClientMessage::KeepAlive { id } => {
+                        if !acknowledge_keepalive(&mut pending_keepalive, id) {
+                            warn!("Player {} answered keepalive {} that wasn't sent", session.name, id);
+                            session.send(disconnect("Invalid keepalive"));
+                            break;
+                        }
+                        debug!("Keepalive {} from {}", id, session.name);
+                    }
+                    ClientMessage::Disconnect => break,
+                    ClientMessage::Join { .. } => {
+                        warn!("Player {} sent a second join message", session.name);
+                    }
+                    ClientMessage::Input { sequence, keys, orientation, jump, flags } => {
+                        let queued = session.movement.lock().await.queue_input(PlayerInput {
+                            sequence,
+                            keys,
+                            orientation,
+                            jump,
+                            flags,
+                        });
+                        if !queued {
+                            warn!("Dropped input {} from {} with invalid orientation {:?}", sequence, session.name, orientation);
+                        }
+                    }
+                    ClientMessage::ChunkRequest { positions, .. } => {
+                        session.streamer.lock().await.request(&positions);
+                    }
+                    other => {
+                        debug!("Unhandled message from {}: {:?}", session.name, other);
+                    }
+                }
+            }
+            _ = keepalive.tick() => {
+                if last_seen.elapsed() > CONNECTION_TIMEOUT {
+                    session.send(disconnect("Timed out"));
+                    break;
+                }
+                keepalive_id += 1;
+                session.send(ServerMessage::KeepAlive { id: keepalive_id });
+                pending_keepalive = Some(keepalive_id);
+            }
+        }
+    }
+
+    // Clean up the session
+    server.sessions.leave(session.id);
+    if let Err(err) = server.save_players(std::slice::from_ref(&session)).await {
+        warn!("Failed to save the profile of {}: {}", session.name, err);
+    }
+    if server.settings.logging.log_connections {
+        info!(
+            "Player {} ({}) left after {:?}",
+            session.name,
+            session.id,
+            session.connected_at.elapsed()
+        );
+    }
+
+    // Dropping the last sender lets the writer flush and close the socket
+    stream_task.abort();
+    let _ = stream_task.await;
+    drop(session);
+    let _ = writer.await;
+}
+
+/// Accepts a keepalive reply if it answers the last keepalive sent
+///
+/// Each keepalive can only be answered once, and sending a new one replaces
+/// any still unanswered.
+fn acknowledge_keepalive(pending: &mut Option<u64>, id: u64) -> bool {
+    if *pending == Some(id) {
+        *pending = None;
+        true
+    } else {
+        false
+    }
+}
+
+/// Streams chunks to a player once per tick until the connection closes
+async fn stream_chunks(session: Arc<PlayerSession>) {
+    let mut interval = tokio::time::interval(Duration::from_millis(TICK_LENGTH_MS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+
+        let center = session.position.read().unwrap().to_chunk();
+        let messages = ChunkStreamer::tick(&session.streamer, &session.world, center).await;
+        for message in messages {
+            if !session.send(message) {
+                return;
+            }
+        }
+    }
+}
+
+/// Reads the next client message, skipping frames that are not game messages
+///
+/// Text frames are decoded as JSON and binary frames as MessagePack; the
+/// encoding of the frame is returned with the message. Returns None when the
+/// connection is closed or broken.
+async fn read_message<S>(ws_rx: &mut S) -> Option<(ClientMessage, Encoding)>
+where
+    S: futures::Stream<Item = Result<Message, warp::Error>> + Unpin,
+{
+    while let Some(result) = ws_rx.next().await {
+        let message = match result {
+            Ok(message) => message,
+            Err(err) => {
+                debug!("WebSocket error: {}", err);
+                return None;
+            }
+        };
+
+        if message.is_close() {
+            return None;
+        }
+
+        let encoding = if message.is_text() {
+            Encoding::Json
+        } else if message.is_binary() {
+            Encoding::MessagePack
+        } else {
+            continue;
+        };
+
+        match encoding.decode::<ClientMessage>(message.as_bytes()) {
+            Ok(message) => return Some((message, encoding)),
+            Err(err) => debug!("Ignoring malformed message: {}", err),
+        }
+    }
+
+    None
+}
+
+/// Sends a message straight to the socket, returning false on failure
+async fn send_direct<S>(ws_tx: &mut S, encoding: Encoding, message: ServerMessage) -> bool
+where
+    S: futures::Sink<Message, Error = warp::Error> + Unpin,
+{
+    match encoding.encode(&message) {
+        Ok(data) if encoding.is_binary() => ws_tx.send(Message::binary(data)).await.is_ok(),
+        Ok(data) => {
+            // JSON output is always valid UTF-8
+            let text = String::from_utf8(data).unwrap_or_default();
+            ws_tx.send(Message::text(text)).await.is_ok()
+        }
+        Err(err) => {
+            warn!("Failed to serialize message: {}", err);
+            true
+        }
+    }
+}
+
+/// Builds a disconnect message with the specified reason
+fn disconnect(reason: &str) -> ServerMessage {
+    ServerMessage::Disconnect {
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keepalive_replies_must_answer_the_last_one_sent() {
+        // Nothing sent yet
+        let mut pending = None;
+        assert!(!acknowledge_keepalive(&mut pending, 1));
+
+        pending = Some(2);
+        assert!(!acknowledge_keepalive(&mut pending, 1));
+        assert!(!acknowledge_keepalive(&mut pending, 3));
+        assert!(acknowledge_keepalive(&mut pending, 2));
+
+        // A reply can't be repeated
+        assert!(!acknowledge_keepalive(&mut pending, 2));
+    }
+}
@@ -0,0 +1,26 @@
+pub mod connection;
+pub mod session;
+pub mod streaming;
+
+use crate::server::GameServer;
+use std::sync::Arc;
+use warp::Filter;
+
+/// Builds the WebSocket route for game connections
+///
+/// Clients may connect either at the root or at `/ws`, so the endpoint works
+/// both directly and behind the nginx `/ws/` proxy.
+pub fn routes(
+    server: Arc<GameServer>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let server = warp::any().map(move || server.clone());
+
+    warp::path::end()
+        .or(warp::path("ws").and(warp::path::end()))
+        .unify()
+        .and(warp::ws())
+        .and(server)
+        .map(|ws: warp::ws::Ws, server: Arc<GameServer>| {
+            ws.on_upgrade(move |socket| connection::handle_connection(socket, server))
+        })
+}
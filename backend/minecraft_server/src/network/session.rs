@@ -0,0 +1,299 @@
+use crate::movement::PlayerMovement;
+use crate::network::streaming::ChunkStreamer;
+use dashmap::DashMap;
+use minecraft_core::math::WorldVector;
+use minecraft_net::protocol::{Encoding, PlayerInfo, ServerMessage};
+use minecraft_world::storage::PlayerData;
+use minecraft_world::world::World;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+
+/// A connected player
+pub struct PlayerSession {
+    /// The player's unique ID
+    pub id: Uuid,
+    /// The player's name
+    pub name: String,
+    /// The world the player is in
+    pub world: Arc<World>,
+    /// The player's current position
+    pub position: RwLock<WorldVector>,
+    /// The player's physics state and pending inputs
+    pub movement: tokio::sync::Mutex<PlayerMovement>,
+    /// When the session was created
+    pub connected_at: Instant,
+    /// The encoding the client chose in its handshake
+    pub encoding: Encoding,
+    /// The chunks sent to the player and the chunks still to send
+    pub streamer: tokio::sync::Mutex<ChunkStreamer>,
+    /// The channel used to send messages to the player's connection
+    sender: UnboundedSender<ServerMessage>,
+}
+
+impl PlayerSession {
+    /// Creates a new session for a player in the specified world
+    pub fn new(
+        name: &str,
+        world: Arc<World>,
+        encoding: Encoding,
+        streamer: ChunkStreamer,
+        sender: UnboundedSender<ServerMessage>,
+    ) -> Self {
+        let spawn = world.spawn_position;
+        let position = WorldVector::new(spawn.x as f32 + 0.5, spawn.y as f32, spawn.z as f32 + 0.5);
+
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            world,
+            position: RwLock::new(position),
+            movement: tokio::sync::Mutex::new(PlayerMovement::new(position)),
+            connected_at: Instant::now(),
+            encoding,
+            streamer: tokio::sync::Mutex::new(streamer),
+            sender,
+        }
+    }
+
+    /// Restores a returning player's ID and position from their saved profile
+    pub fn restore(&mut self, profile: &PlayerData) {
+        self.id = profile.id;
+        *self.position.get_mut().unwrap() = profile.position;
+        *self.movement.get_mut() = PlayerMovement::new(profile.position);
+    }
+
+    /// Returns the profile to save for this player
+    pub fn profile(&self) -> PlayerData {
+        PlayerData {
+            id: self.id,
+            name: self.name.clone(),
+            position: *self.position.read().unwrap(),
+        }
+    }
+
+    /// Queues a message for the player, returning false if the connection is gone
+    pub fn send(&self, message: ServerMessage) -> bool {
+        self.sender.send(message).is_ok()
+    }
+
+    /// Returns the public information about this player
+    pub fn info(&self) -> PlayerInfo {
+        PlayerInfo {
+            id: self.id,
+            name: self.name.clone(),
+            position: *self.position.read().unwrap(),
+        }
+    }
+}
+
+/// The reasons a join can be refused
+#[derive(Debug, thiserror::Error)]
+pub enum JoinError {
+    #[error("The server is full")]
+    ServerFull,
+    #[error("A player named '{0}' is already connected")]
+    NameTaken(String),
+    #[error("Invalid player name")]
+    InvalidName,
+}
+
+/// Tracks every connected player
+pub struct SessionManager {
+    /// The sessions, keyed by player ID
+    sessions: DashMap<Uuid, Arc<PlayerSession>>,
+    /// The maximum number of concurrent players
+    max_players: usize,
+    /// Serializes joins so the capacity and name checks can't race
+    join_lock: Mutex<()>,
+}
+
+impl SessionManager {
+    /// Creates a new session manager
+    pub fn new(max_players: usize) -> Self {
+        Self {
+            sessions: DashMap::new(),
+            max_players,
+            join_lock: Mutex::new(()),
+        }
+    }
+
+    /// Registers a new session and notifies the other players
+    pub fn join(&self, session: PlayerSession) -> Result<Arc<PlayerSession>, JoinError> {
+        if !is_valid_name(&session.name) {
+            return Err(JoinError::InvalidName);
+        }
+
+        let _guard = self.join_lock.lock().unwrap();
+        if self.sessions.len() >= self.max_players {
+            return Err(JoinError::ServerFull);
+        }
+        if self.find_by_name(&session.name).is_some() {
+            return Err(JoinError::NameTaken(session.name.clone()));
+        }
+
+        let session = Arc::new(session);
+        self.sessions.insert(session.id, session.clone());
+
+        self.broadcast_except(session.id, ServerMessage::PlayerJoined { player: session.info() });
+
+        Ok(session)
+    }
+
+    /// Removes a session and notifies the remaining players
+    pub fn leave(&self, id: Uuid) -> Option<Arc<PlayerSession>> {
+        let (_, session) = self.sessions.remove(&id)?;
+
+        self.broadcast(ServerMessage::PlayerLeft {
+            player_id: session.id,
+            name: session.name.clone(),
+        });
+
+        Some(session)
+    }
+
+    /// Finds a session by player name (case-insensitive)
+    pub fn find_by_name(&self, name: &str) -> Option<Arc<PlayerSession>> {
+        self.sessions
+            .iter()
+            .find(|entry| entry.value().name.eq_ignore_ascii_case(name))
+            .map(|entry| entry.value().clone())
+    }
+
+    /// Returns every connected session
+    pub fn all(&self) -> Vec<Arc<PlayerSession>> {
+        self.sessions.iter().map(|entry| entry.value().clone()).collect()
+    }
+
+    /// Returns the public information for every connected player
+    pub fn player_list(&self) -> Vec<PlayerInfo> {
+        self.sessions.iter().map(|entry| entry.value().info()).collect()
+    }
+
+    /// Returns the number of connected players
+    pub fn count(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Sends a message to every connected player
+    pub fn broadcast(&self, message: ServerMessage) {
+        for entry in self.sessions.iter() {
+            entry.value().send(message.clone());
+        }
+    }
+
+    /// Sends a message to every connected player except one
+    pub fn broadcast_except(&self, excluded: Uuid, message: ServerMessage) {
+        for entry in self.sessions.iter() {
+            if *entry.key() != excluded {
+                entry.value().send(message.clone());
+            }
+        }
+    }
+}
+
+/// Checks that a player name is 1-16 characters of letters, digits or underscores
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 16
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::flat_world;
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+    /// Creates a session for a player, with the receiving end of its connection
+    fn session(world: &Arc<World>, name: &str) -> (PlayerSession, UnboundedReceiver<ServerMessage>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let streamer = ChunkStreamer::new(2, usize::MAX);
+        (PlayerSession::new(name, world.clone(), Encoding::Json, streamer, sender), receiver)
+    }
+
+    /// Returns the messages waiting on a connection
+    fn received(receiver: &mut UnboundedReceiver<ServerMessage>) -> Vec<ServerMessage> {
+        std::iter::from_fn(|| receiver.try_recv().ok()).collect()
+    }
+
+    #[test]
+    fn players_are_told_about_joins_and_leaves() {
+        let world = Arc::new(flat_world());
+        let sessions = SessionManager::new(4);
+        let (alice, mut alice_rx) = session(&world, "Alice");
+        let alice = sessions.join(alice).unwrap();
+        let (bob, mut bob_rx) = session(&world, "Bob");
+        let bob = sessions.join(bob).unwrap();
+        assert_eq!(sessions.count(), 2);
+
+        // Only the players already there hear about a join
+        match received(&mut alice_rx).as_slice() {
+            [ServerMessage::PlayerJoined { player }] => assert_eq!(player.id, bob.id),
+            other => panic!("unexpected messages {:?}", other),
+        }
+        assert!(received(&mut bob_rx).is_empty());
+
+        assert!(sessions.leave(alice.id).is_some());
+        assert_eq!(sessions.count(), 1);
+        match received(&mut bob_rx).as_slice() {
+            [ServerMessage::PlayerLeft { player_id, name }] => {
+                assert_eq!(*player_id, alice.id);
+                assert_eq!(name, "Alice");
+            }
+            other => panic!("unexpected messages {:?}", other),
+        }
+        assert!(received(&mut alice_rx).is_empty());
+
+        // Leaving twice does nothing
+        assert!(sessions.leave(alice.id).is_none());
+        assert!(received(&mut bob_rx).is_empty());
+    }
+
+    #[test]
+    fn joins_are_refused_when_full_or_the_name_is_taken() {
+        let world = Arc::new(flat_world());
+        let sessions = SessionManager::new(2);
+        sessions.join(session(&world, "Alice").0).unwrap();
+
+        assert!(matches!(sessions.join(session(&world, "alice").0), Err(JoinError::NameTaken(_))));
+        for name in ["", "has space", "a_name_far_too_long"] {
+            assert!(matches!(sessions.join(session(&world, name).0), Err(JoinError::InvalidName)));
+        }
+
+        sessions.join(session(&world, "Bob").0).unwrap();
+        assert!(matches!(sessions.join(session(&world, "Carol").0), Err(JoinError::ServerFull)));
+        assert_eq!(sessions.count(), 2);
+
+        // A leave frees the slot and the name
+        let bob = sessions.find_by_name("BOB").unwrap();
+        sessions.leave(bob.id);
+        sessions.join(session(&world, "Bob").0).unwrap();
+    }
+
+    #[test]
+    fn sends_fail_once_the_connection_is_gone() {
+        let world = Arc::new(flat_world());
+        let (player, receiver) = session(&world, "Alice");
+        assert!(player.send(ServerMessage::KeepAlive { id: 1 }));
+        drop(receiver);
+        assert!(!player.send(ServerMessage::KeepAlive { id: 2 }));
+    }
+
+    #[test]
+    fn returning_players_keep_their_id_and_position() {
+        let world = Arc::new(flat_world());
+        let profile = PlayerData {
+            id: Uuid::new_v4(),
+            name: "Alice".to_string(),
+            position: WorldVector::new(10.0, 20.0, 30.0),
+        };
+
+        let (mut player, _receiver) = session(&world, "Alice");
+        player.restore(&profile);
+        assert_eq!(player.id, profile.id);
+        assert_eq!(player.info().position, profile.position);
+        assert_eq!(player.profile().position, profile.position);
+    }
+}
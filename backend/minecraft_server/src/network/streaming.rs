@@ -0,0 +1,360 @@
+use anyhow::Result;
+use log::warn;
+use minecraft_core::constants::{CHUNK_SIZE, MAX_BUILD_HEIGHT, MIN_BUILD_HEIGHT};
+use minecraft_core::math::ChunkVector;
+use minecraft_net::protocol::{checksum, ChunkFormat, ServerMessage};
+use minecraft_world::world::World;
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+/// Streams the chunks around a player to their client
+///
+/// The streamer remembers which chunks the client already has. When the
+/// player's chunk changes it works out which chunks entered and left the
+/// view, and then sends the missing ones nearest-first, a few per tick.
+pub struct ChunkStreamer {
+    /// The view distance in chunks
+    view_distance: i32,
+    /// The maximum number of bytes to send per tick
+    budget_bytes: usize,
+    /// The chunk the view is centred on
+    center: Option<ChunkVector>,
+    /// The chunks the client has
+    loaded: HashSet<ChunkVector>,
+    /// The chunks still to send, farthest first so the nearest can be popped
+    pending: Vec<ChunkVector>,
+    /// The same chunks as `pending`, for quick lookups
+    queued: HashSet<ChunkVector>,
+    /// The chunks taken off the queue that are still being loaded
+    in_flight: HashSet<ChunkVector>,
+}
+
+impl ChunkStreamer {
+    /// Creates a new chunk streamer
+    pub fn new(view_distance: u32, budget_bytes: usize) -> Self {
+        Self {
+            view_distance: view_distance as i32,
+            budget_bytes,
+            center: None,
+            loaded: HashSet::new(),
+            pending: Vec::new(),
+            queued: HashSet::new(),
+            in_flight: HashSet::new(),
+        }
+    }
+
+    /// Queues chunks the client asked for again, if they are in view
+    ///
+    /// Chunks already queued or on their way aren't queued a second time.
+    pub fn request(&mut self, positions: &[ChunkVector]) {
+        let Some(center) = self.center else {
+            return;
+        };
+
+        for &position in positions {
+            if self.in_view(center, position) && !self.in_flight.contains(&position) && self.queued.insert(position) {
+                self.loaded.remove(&position);
+                self.pending.push(position);
+            }
+        }
+        sort_farthest_first(&mut self.pending, center);
+    }
+
+    /// Advances the stream by one tick
+    ///
+    /// Returns unload messages for chunks that left the view, followed by
+    /// chunk data for as many pending chunks as the budget allows. At least
+    /// one chunk is sent per tick so a large chunk can't stall the stream.
+    ///
+    /// The lock is only held while picking chunks and recording them as
+    /// sent, never while a chunk is generated. A picked chunk is marked in
+    /// flight under the same lock, so it can't be queued again until it's
+    /// done. A chunk that fails to load goes back to the front of the queue to
+    /// be retried next tick, and the messages already built are still
+    /// returned. Chunks that left the view while loading are dropped.
+    pub async fn tick(streamer: &Mutex<ChunkStreamer>, world: &World, center: ChunkVector) -> Vec<ServerMessage> {
+        let mut messages = Vec::new();
+        let budget_bytes = {
+            let mut streamer = streamer.lock().await;
+            if streamer.center != Some(center) {
+                for position in streamer.recenter(center) {
+                    messages.push(ServerMessage::ChunkUnload { position });
+                }
+            }
+            streamer.budget_bytes
+        };
+
+        let mut sent_bytes = 0;
+        loop {
+            let Some(position) = streamer.lock().await.take_next() else {
+                break;
+            };
+
+            let data = match chunk_data(world, position).await {
+                Ok(data) => data,
+                Err(err) => {
+                    warn!("Failed to stream chunk {:?}: {}", position, err);
+                    streamer.lock().await.finish(position, false);
+                    break;
+                }
+            };
+
+            if streamer.lock().await.finish(position, true) {
+                sent_bytes += data.len();
+                messages.push(ServerMessage::ChunkData {
+                    timestamp: now_millis(),
+                    position,
+                    format: ChunkFormat::Plain,
+                    checksum: checksum(&data),
+                    data,
+                });
+            }
+            if sent_bytes >= budget_bytes {
+                break;
+            }
+        }
+
+        messages
+    }
+
+    /// Takes the nearest pending chunk off the queue and marks it in flight
+    fn take_next(&mut self) -> Option<ChunkVector> {
+        let position = self.pending.pop()?;
+        self.queued.remove(&position);
+        self.in_flight.insert(position);
+        Some(position)
+    }
+
+    /// Records that an in-flight chunk finished loading, or failed to
+    ///
+    /// A loaded chunk counts as sent if it's still in view; a failed one is
+    /// queued again to be sent next. Returns whether the chunk should be sent.
+    fn finish(&mut self, position: ChunkVector, loaded: bool) -> bool {
+        self.in_flight.remove(&position);
+        let Some(center) = self.center else {
+            return false;
+        };
+        if !self.in_view(center, position) {
+            return false;
+        }
+
+        if loaded {
+            self.loaded.insert(position);
+        } else if self.queued.insert(position) {
+            self.pending.push(position);
+        }
+        loaded
+    }
+
+    /// Moves the view to a new centre, returning the chunks that left it
+    fn recenter(&mut self, center: ChunkVector) -> Vec<ChunkVector> {
+        self.center = Some(center);
+
+        let in_view = self.chunks_in_view(center);
+        let in_view_set: HashSet<ChunkVector> = in_view.iter().copied().collect();
+
+        let removed: Vec<ChunkVector> = self
+            .loaded
+            .iter()
+            .filter(|position| !in_view_set.contains(position))
+            .copied()
+            .collect();
+        for position in &removed {
+            self.loaded.remove(position);
+        }
+
+        self.pending = in_view
+            .into_iter()
+            .filter(|position| !self.loaded.contains(position) && !self.in_flight.contains(position))
+            .collect();
+        self.queued = self.pending.iter().copied().collect();
+        sort_farthest_first(&mut self.pending, center);
+
+        removed
+    }
+
+    /// Returns every chunk within view distance of the centre
+    ///
+    /// The view is a cylinder: a circle of radius `view_distance` around the
+    /// centre column, limited vertically to the build height.
+    fn chunks_in_view(&self, center: ChunkVector) -> Vec<ChunkVector> {
+        let radius = self.view_distance;
+        let (min_y, max_y) = vertical_range(center, radius);
+        let mut result = Vec::new();
+
+        for x in (center.x - radius)..=(center.x + radius) {
+            for z in (center.z - radius)..=(center.z + radius) {
+                let dx = x - center.x;
+                let dz = z - center.z;
+                if dx * dx + dz * dz > radius * radius {
+                    continue;
+                }
+
+                for y in min_y..=max_y {
+                    result.push(ChunkVector::new(x, y, z));
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Checks if a chunk is within view distance of the centre
+    fn in_view(&self, center: ChunkVector, position: ChunkVector) -> bool {
+        let radius = self.view_distance;
+        let (min_y, max_y) = vertical_range(center, radius);
+        let dx = position.x - center.x;
+        let dz = position.z - center.z;
+
+        dx * dx + dz * dz <= radius * radius && position.y >= min_y && position.y <= max_y
+    }
+}
+
+/// Returns the range of chunk Y coordinates in view, clamped to the build height
+fn vertical_range(center: ChunkVector, radius: i32) -> (i32, i32) {
+    let chunk_height = CHUNK_SIZE.1 as i32;
+    let world_min = MIN_BUILD_HEIGHT.div_euclid(chunk_height);
+    let world_max = (MAX_BUILD_HEIGHT - 1).div_euclid(chunk_height);
+
+    (
+        (center.y - radius).max(world_min),
+        (center.y + radius).min(world_max),
+    )
+}
+
+/// Loads a chunk and serializes it for sending
+async fn chunk_data(world: &World, position: ChunkVector) -> Result<Vec<u8>> {
+    // Chunks are generated lazily on first access
+    let chunk = world.get_chunk(position).await?;
+    chunk.serialize()
+}
+
+/// Sorts chunks so the one nearest the centre is last
+fn sort_farthest_first(positions: &mut [ChunkVector], center: ChunkVector) {
+    positions.sort_by_key(|position| std::cmp::Reverse(position.distance_squared(&center)));
+}
+
+/// Returns the current time in milliseconds
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::flat_world;
+
+    /// Returns the positions of the chunks sent in a batch of messages
+    fn sent(messages: &[ServerMessage]) -> Vec<ChunkVector> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                ServerMessage::ChunkData { position, .. } => Some(*position),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the positions of the chunks unloaded in a batch of messages
+    fn unloaded(messages: &[ServerMessage]) -> HashSet<ChunkVector> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                ServerMessage::ChunkUnload { position } => Some(*position),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recenter_queues_the_view_nearest_first() {
+        let center = ChunkVector::new(0, 4, 0);
+        let mut streamer = ChunkStreamer::new(2, usize::MAX);
+        assert!(streamer.recenter(center).is_empty());
+
+        assert_eq!(streamer.pending.len(), streamer.chunks_in_view(center).len());
+        assert_eq!(streamer.pending.len(), streamer.queued.len());
+        assert_eq!(streamer.pending.last(), Some(&center));
+        assert!(streamer.pending.iter().all(|position| streamer.in_view(center, *position)));
+
+        let distances: Vec<i32> = streamer.pending.iter().map(|position| position.distance_squared(&center)).collect();
+        assert!(distances.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+
+    #[tokio::test]
+    async fn sends_nearest_first_and_at_least_one_per_tick() {
+        let world = flat_world();
+        let center = ChunkVector::new(0, 4, 0);
+        let streamer = Mutex::new(ChunkStreamer::new(2, 0));
+
+        let first = ChunkStreamer::tick(&streamer, &world, center).await;
+        assert_eq!(sent(&first), vec![center]);
+
+        let second = sent(&ChunkStreamer::tick(&streamer, &world, center).await);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].distance_squared(&center), 1);
+    }
+
+    #[tokio::test]
+    async fn moving_unloads_chunks_that_left_the_view() {
+        let world = flat_world();
+        let streamer = Mutex::new(ChunkStreamer::new(2, usize::MAX));
+
+        let before = ChunkVector::new(0, 4, 0);
+        let first = sent(&ChunkStreamer::tick(&streamer, &world, before).await);
+        let first_set: HashSet<ChunkVector> = first.iter().copied().collect();
+        assert_eq!(first.len(), first_set.len());
+        assert_eq!(first_set.len(), streamer.lock().await.chunks_in_view(before).len());
+
+        let after = ChunkVector::new(3, 4, 0);
+        let messages = ChunkStreamer::tick(&streamer, &world, after).await;
+        let streamer = streamer.lock().await;
+
+        let expected_unloads: HashSet<ChunkVector> = first_set
+            .iter()
+            .filter(|position| !streamer.in_view(after, **position))
+            .copied()
+            .collect();
+        assert!(!expected_unloads.is_empty());
+        assert_eq!(unloaded(&messages), expected_unloads);
+
+        // Only the chunks the client doesn't have yet are sent
+        let second = sent(&messages);
+        assert!(second.iter().all(|position| !first_set.contains(position)));
+        assert_eq!(first_set.len() - expected_unloads.len() + second.len(), streamer.loaded.len());
+    }
+
+    #[test]
+    fn requests_skip_queued_and_in_flight_chunks() {
+        let center = ChunkVector::new(0, 4, 0);
+        let mut streamer = ChunkStreamer::new(2, usize::MAX);
+        streamer.recenter(center);
+        let queued = streamer.pending.len();
+
+        let position = streamer.take_next().unwrap();
+        assert_eq!(position, center);
+        streamer.request(&[position, ChunkVector::new(1, 4, 0)]);
+        assert_eq!(streamer.pending.len(), queued - 1);
+
+        // Once sent, asking again sends it again, but chunks out of view never are
+        assert!(streamer.finish(position, true));
+        streamer.request(&[position, ChunkVector::new(10, 4, 0)]);
+        assert_eq!(streamer.pending.len(), queued);
+        assert_eq!(streamer.pending.last(), Some(&center));
+    }
+
+    #[test]
+    fn chunks_leaving_the_view_in_flight_are_dropped() {
+        let mut streamer = ChunkStreamer::new(2, usize::MAX);
+        streamer.recenter(ChunkVector::new(0, 4, 0));
+        let position = streamer.take_next().unwrap();
+
+        streamer.recenter(ChunkVector::new(10, 4, 0));
+        assert!(!streamer.finish(position, true));
+        assert!(!streamer.loaded.contains(&position));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::config::ServerSettings;
+use crate::entity::{Entity, EntityManager};
+use crate::network::session::{PlayerSession, SessionManager};
+use crate::tick::TickStats;
+use minecraft_core::block::Block;
+use minecraft_core::math::{BlockVector, WorldVector};
+use minecraft_net::protocol::ServerMessage;
+use minecraft_util::profiler::Profiler;
+use minecraft_world::world::World;
+use minecraft_world::world::explosion::Explosion;
+use anyhow::Result;
+use std::sync::Arc;
+
+/// The shared state of a running game server
+pub struct GameServer {
+    /// The server configuration
+    pub settings: ServerSettings,
+    /// The world players join
+    pub world: Arc<World>,
+    /// The connected players
+    pub sessions: SessionManager,
+    /// The entities other than players
+    pub entities: EntityManager,
+    /// Timings for the tick loop
+    pub profiler: Profiler,
+    /// Counters for the tick loop
+    pub ticks: TickStats,
+}
+
+impl GameServer {
+    /// Creates a new game server for the specified world
+    pub fn new(settings: ServerSettings, world: Arc<World>) -> Self {
+        let sessions = SessionManager::new(settings.server.max_players);
+
+        Self {
+            settings,
+            world,
+            sessions,
+            entities: EntityManager::new(),
+            profiler: Profiler::new(),
+            ticks: TickStats::default(),
+        }
+    }
+
+    /// Sets a block in the world and tells every player about it
+    pub async fn set_block(&self, position: BlockVector, block: Block) -> Result<()> {
+        self.world.set_block(position, block).await?;
+        self.sessions.broadcast(ServerMessage::BlockUpdate { position, block });
+        Ok(())
+    }
+
+    /// Sets off an explosion, tells every player about the blocks it destroyed,
+    /// and pushes players and entities away from it
+    ///
+    /// Players and entities that were knocked back send their new movement
+    /// on the next tick. Nothing in the base game sets off explosions; this
+    /// is for game modes and admin tools.
+    #[allow(dead_code)]
+    pub async fn explode(&self, center: WorldVector, power: f32) -> Result<Explosion> {
+        let explosion = self.world.explode(center, power).await?;
+        for (position, _) in &explosion.destroyed {
+            self.sessions.broadcast(ServerMessage::BlockUpdate {
+                position: *position,
+                block: Block::default(),
+            });
+        }
+
+        for session in self.sessions.all() {
+            let mut movement = session.movement.lock().await;
+            explosion.knockback(&self.world, &mut movement.physics);
+        }
+        for entity in self.entities.lock().await.values_mut() {
+            explosion.knockback(&self.world, &mut entity.physics);
+        }
+
+        Ok(explosion)
+    }
+
+    /// Adds an entity to the world and tells every player about it
+    pub async fn spawn_entity(&self, entity: Entity) {
+        self.sessions.broadcast(entity.spawn_message());
+        self.entities.add(entity).await;
+    }
+
+    /// Saves the profiles of the specified players, if the world stores profiles
+    pub async fn save_players(&self, sessions: &[Arc<PlayerSession>]) -> Result<()> {
+        let Some(store) = &self.world.player_store else {
+            return Ok(());
+        };
+
+        let profiles: Vec<_> = sessions.iter().map(|session| session.profile()).collect();
+        store.save_players(&profiles).await
+    }
+}
@@ -0,0 +1,46 @@
+//! Helpers shared by the server's unit tests
+
+use crate::blocks;
+use crate::config::ServerSettings;
+use crate::server::GameServer;
+use minecraft_core::block::Block;
+use minecraft_core::math::{BlockVector, ChunkVector};
+use minecraft_world::chunk::provider::MemoryChunkProvider;
+use minecraft_world::generator::flat::FlatWorldGenerator;
+use minecraft_world::world::World;
+use std::sync::Arc;
+
+/// The height of the first air block above the ground of a flat world
+pub const GROUND: i32 = 6;
+
+/// Creates an in-memory world of flat chunks, with the ground's top at y = 5
+pub fn flat_world() -> World {
+    let generator = Arc::new(FlatWorldGenerator::new("flat", 1));
+    World::new("test", 1, Box::new(MemoryChunkProvider::new(generator.clone()).unwrap()), generator)
+}
+
+/// Creates a server for a world, with the chunks around the origin loaded and lit
+///
+/// The loaded area covers x and z from -16 to 31, and y from 0 to 31.
+pub async fn server(world: World, settings: ServerSettings) -> GameServer {
+    for x in -1..=1 {
+        for z in -1..=1 {
+            for y in 0..=1 {
+                world.get_chunk(ChunkVector::new(x, y, z)).await.unwrap();
+            }
+        }
+    }
+    GameServer::new(settings, Arc::new(world))
+}
+
+/// Processes the block updates of a number of ticks
+pub async fn run_ticks(server: &GameServer, ticks: u32) {
+    for _ in 0..ticks {
+        blocks::process_updates(server).await;
+    }
+}
+
+/// Returns the block at a position, which must be loaded
+pub fn block_at(server: &GameServer, x: i32, y: i32, z: i32) -> Block {
+    server.world.get_block_if_loaded(BlockVector::new(x, y, z)).unwrap()
+}
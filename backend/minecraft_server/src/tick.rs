@@ -0,0 +1,234 @@
+use crate::blocks;
+use crate::server::GameServer;
+use log::{debug, warn};
+use minecraft_core::constants::TICK_RATE;
+use minecraft_net::protocol::ServerMessage;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The most ticks the loop will run back-to-back to catch up
+///
+/// If the loop falls further behind than this, the missed ticks are skipped
+/// instead, so a long stall doesn't turn into a burst of fast-forwarding.
+pub const MAX_CATCH_UP_TICKS: u64 = 10;
+
+/// How often (in ticks) the time of day is sent to players
+const TIME_UPDATE_INTERVAL: u64 = TICK_RATE as u64;
+
+/// How often (in ticks) the tick profile is logged and reset
+const PROFILE_INTERVAL: u64 = 60 * TICK_RATE as u64;
+
+/// Counters describing how the tick loop is keeping up
+#[derive(Debug, Default)]
+pub struct TickStats {
+    /// The number of ticks run so far
+    pub ticks: AtomicU64,
+    /// The number of ticks that took longer than the tick length
+    pub overruns: AtomicU64,
+    /// The number of ticks skipped because the loop fell too far behind
+    pub skipped: AtomicU64,
+}
+
+impl TickStats {
+    /// Returns the number of ticks run so far
+    pub fn current_tick(&self) -> u64 {
+        self.ticks.load(Ordering::Relaxed)
+    }
+}
+
+/// The fixed-rate game loop
+///
+/// Each tick advances the world time, then runs the physics phase, the
+/// entity phase, the random tick phase and the block update phase. Tick
+/// durations are recorded in the server profiler under `tick` and one entry
+/// per phase.
+pub struct TickLoop {
+    /// The server being ticked
+    server: Arc<GameServer>,
+    /// The target duration of one tick
+    tick_length: Duration,
+    /// Fractional world time carried over between ticks
+    time_remainder: f32,
+}
+
+impl TickLoop {
+    /// Creates a tick loop running at the configured `max_tps`
+    pub fn new(server: Arc<GameServer>) -> Self {
+        let tps = match server.settings.performance.max_tps {
+            0 => TICK_RATE,
+            tps => tps,
+        };
+
+        Self {
+            tick_length: Duration::from_secs(1) / tps,
+            server,
+            time_remainder: 0.0,
+        }
+    }
+
+    /// Runs the loop forever
+    pub async fn run(mut self) {
+        let mut next_tick = Instant::now();
+
+        loop {
+            let now = Instant::now();
+            if now < next_tick {
+                tokio::time::sleep_until(next_tick.into()).await;
+            } else {
+                // Run late ticks back-to-back, unless we're too far behind
+                let behind = ((now - next_tick).as_nanos() / self.tick_length.as_nanos()) as u64;
+                if behind > MAX_CATCH_UP_TICKS {
+                    warn!("Can't keep up! Skipping {} ticks", behind);
+                    self.server
+                        .ticks
+                        .skipped
+                        .fetch_add(behind, Ordering::Relaxed);
+                    next_tick = now;
+                }
+            }
+
+            self.tick().await;
+            next_tick += self.tick_length;
+        }
+    }
+
+    /// Runs a single tick
+    async fn tick(&mut self) {
+        let started = Instant::now();
+        let profiler = self.server.profiler.clone();
+
+        {
+            let _tick = profiler.measure("tick");
+
+            {
+                let _phase = profiler.measure("tick.time");
+                self.advance_time();
+            }
+            {
+                let _phase = profiler.measure("tick.physics");
+                self.physics_phase().await;
+            }
+            {
+                let _phase = profiler.measure("tick.entities");
+                self.entity_phase().await;
+            }
+            {
+                let _phase = profiler.measure("tick.random_ticks");
+                self.random_tick_phase().await;
+            }
+            {
+                let _phase = profiler.measure("tick.block_updates");
+                self.block_update_phase().await;
+            }
+        }
+
+        let stats = &self.server.ticks;
+        if started.elapsed() > self.tick_length {
+            stats.overruns.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let tick = stats.ticks.fetch_add(1, Ordering::Relaxed) + 1;
+        if tick.is_multiple_of(PROFILE_INTERVAL) {
+            debug!(
+                "{}Overruns: {}, skipped: {}",
+                profiler.summary_string(),
+                stats.overruns.load(Ordering::Relaxed),
+                stats.skipped.load(Ordering::Relaxed)
+            );
+            if let Some(generation) = self.server.world.generation_metrics() {
+                debug!(
+                    "Chunk generation: {} queued, {} running, {} done ({:.1}/s, {:.2} ms avg), {} deduplicated",
+                    generation.queued,
+                    generation.running,
+                    generation.completed,
+                    generation.chunks_per_second(),
+                    generation.average_time().as_secs_f64() * 1000.0,
+                    generation.deduplicated
+                );
+            }
+            profiler.clear();
+        }
+    }
+
+    /// Advances the world time, scaled by the day/night cycle speed
+    fn advance_time(&mut self) {
+        let world = &self.server.world;
+
+        self.time_remainder += self.server.settings.world.day_night_cycle_speed.max(0.0);
+        let whole = self.time_remainder.floor();
+        self.time_remainder -= whole;
+        world.increment_time(whole as u64);
+
+        if self
+            .server
+            .ticks
+            .current_tick()
+            .is_multiple_of(TIME_UPDATE_INTERVAL)
+        {
+            self.server.sessions.broadcast(ServerMessage::TimeUpdate {
+                time: world.get_time(),
+            });
+        }
+    }
+
+    /// Steps every player's movement and sends out the results
+    ///
+    /// The player gets its authoritative state back with the sequence of the
+    /// last input applied; everyone else sees the new position.
+    async fn physics_phase(&mut self) {
+        let allow_flight = self.server.settings.gameplay.allow_flight;
+
+        for session in self.server.sessions.all() {
+            let mut movement = session.movement.lock().await;
+            let update = match movement
+                .step(&session.world, self.tick_length, allow_flight)
+                .await
+            {
+                Ok(update) => update,
+                Err(err) => {
+                    warn!("Failed to move player {}: {}", session.name, err);
+                    continue;
+                }
+            };
+
+            let physics = &movement.physics;
+            *session.position.write().unwrap() = physics.position;
+
+            if update.applied_input || update.moved {
+                session.send(ServerMessage::PlayerState {
+                    sequence: update.sequence,
+                    position: physics.position,
+                    velocity: physics.velocity,
+                    on_ground: physics.on_ground,
+                });
+            }
+            if update.moved {
+                self.server.sessions.broadcast_except(
+                    session.id,
+                    ServerMessage::PlayerMoved {
+                        player_id: session.id,
+                        position: physics.position,
+                        velocity: physics.velocity,
+                        orientation: movement.orientation(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Steps every entity other than the players
+    async fn entity_phase(&mut self) {
+        blocks::falling::step(&self.server, self.tick_length).await;
+    }
+
+    /// Gives random blocks around the players a random tick
+    async fn random_tick_phase(&mut self) {
+        blocks::process_random_ticks(&self.server).await;
+    }
+
+    /// Processes the neighbour updates and scheduled block ticks due this tick
+    async fn block_update_phase(&mut self) {
+        blocks::process_updates(&self.server).await;
+    }
+}
@@ -0,0 +1,72 @@
+use crate::server::GameServer;
+use log::{debug, error};
+use minecraft_core::math::ChunkVector;
+use minecraft_world::world::ChunkBudget;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+
+/// How often loaded chunks are checked against the budget
+const UNLOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Periodically unloads chunks no player needs once the chunk budget is exceeded
+///
+/// Chunks within any player's simulation distance are kept; the rest are
+/// evicted least recently used first, and modified chunks are saved before
+/// they are dropped.
+pub struct ChunkUnloader {
+    /// The server whose world is unloaded
+    server: Arc<GameServer>,
+    /// The limits on loaded chunks
+    budget: ChunkBudget,
+}
+
+impl ChunkUnloader {
+    /// Creates an unloader using the configured chunk budget
+    pub fn new(server: Arc<GameServer>) -> Self {
+        let performance = &server.settings.performance;
+        let budget = ChunkBudget {
+            max_chunks: performance.max_loaded_chunks,
+            max_memory: performance.chunk_memory_budget_mb * 1024 * 1024,
+        };
+
+        Self { server, budget }
+    }
+
+    /// Runs the unloader forever
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(UNLOAD_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            self.unload().await;
+        }
+    }
+
+    /// Unloads chunks once if the world is over budget
+    async fn unload(&self) {
+        let player_chunks: Vec<ChunkVector> = self
+            .server
+            .sessions
+            .all()
+            .iter()
+            .map(|session| session.position.read().unwrap().to_chunk())
+            .collect();
+        let simulation_distance = self.server.settings.server.simulation_distance as i32;
+
+        let result = {
+            let _profile = self.server.profiler.measure("unload");
+            self.server
+                .world
+                .unload_chunks(&player_chunks, simulation_distance, self.budget)
+                .await
+        };
+
+        match result {
+            Ok(0) => {}
+            Ok(count) => debug!("Unloaded {} chunks", count),
+            Err(err) => error!("Failed to unload chunks: {}", err),
+        }
+    }
+}
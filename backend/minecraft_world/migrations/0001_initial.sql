@@ -0,0 +1,23 @@
+-- World metadata, such as the world configuration, as JSON values
+CREATE TABLE IF NOT EXISTS world_meta (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL
+);
+
+-- Chunks, stored as zstd-compressed `Chunk::serialize` output
+CREATE TABLE IF NOT EXISTS chunks (
+    x INTEGER NOT NULL,
+    y INTEGER NOT NULL,
+    z INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    last_modified INTEGER NOT NULL,
+    PRIMARY KEY (x, y, z)
+) WITHOUT ROWID;
+
+-- Player profiles, keyed by name, with the profile as JSON
+CREATE TABLE IF NOT EXISTS players (
+    name TEXT PRIMARY KEY NOT NULL COLLATE NOCASE,
+    id TEXT NOT NULL,
+    data TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
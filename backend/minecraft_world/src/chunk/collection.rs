@@ -1,5 +1,5 @@
 use super::Chunk;
-use minecraft_core::math::{BlockVector, ChunkVector, block_to_chunk};
+use minecraft_core::math::{BlockVector, ChunkVector};
 use minecraft_core::block::Block;
 use dashmap::DashMap;
 use std::sync::Arc;
@@ -97,7 +97,7 @@ impl ChunkCollection {
     pub fn get_dirty_chunks(&self) -> HashSet<ChunkVector> {
         self.chunks
             .iter()
-            .filter(|entry| entry.value().is_dirty)
+            .filter(|entry| entry.value().is_dirty())
             .map(|entry| entry.value().position)
             .collect()
     }
@@ -0,0 +1,105 @@
+use minecraft_core::constants::{CHUNK_SIZE, CHUNK_VOLUME};
+use serde::{Deserialize, Serialize};
+
+/// The two kinds of light tracked for every block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LightKind {
+    /// Light given off by blocks such as torches and lava
+    Block,
+    /// Light coming down from the sky
+    Sky,
+}
+
+impl LightKind {
+    /// Both kinds of light
+    pub const ALL: [LightKind; 2] = [LightKind::Block, LightKind::Sky];
+}
+
+/// The block light and sky light of every block in a chunk, four bits each
+#[derive(Debug, Clone)]
+pub struct ChunkLight {
+    /// The block light levels
+    block: NibbleArray,
+    /// The sky light levels
+    sky: NibbleArray,
+}
+
+impl ChunkLight {
+    /// Creates light storage with every block dark
+    pub fn new() -> Self {
+        Self {
+            block: NibbleArray::new(),
+            sky: NibbleArray::new(),
+        }
+    }
+
+    /// Gets the light level (0-15) at the specified local position
+    pub fn get(&self, kind: LightKind, x: usize, y: usize, z: usize) -> u8 {
+        self.levels(kind).get(index(x, y, z))
+    }
+
+    /// Sets the light level (0-15) at the specified local position
+    pub fn set(&mut self, kind: LightKind, x: usize, y: usize, z: usize, level: u8) {
+        let index = index(x, y, z);
+        match kind {
+            LightKind::Block => self.block.set(index, level),
+            LightKind::Sky => self.sky.set(index, level),
+        }
+    }
+
+    /// Returns the number of bytes used by the light levels
+    pub fn memory_usage(&self) -> usize {
+        self.block.data.len() + self.sky.data.len()
+    }
+
+    /// Returns the levels of one kind of light
+    fn levels(&self, kind: LightKind) -> &NibbleArray {
+        match kind {
+            LightKind::Block => &self.block,
+            LightKind::Sky => &self.sky,
+        }
+    }
+}
+
+impl Default for ChunkLight {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts a local position to an index into the light arrays
+fn index(x: usize, y: usize, z: usize) -> usize {
+    x + z * CHUNK_SIZE.0 + y * CHUNK_SIZE.0 * CHUNK_SIZE.2
+}
+
+/// An array of 4-bit values, two to a byte
+#[derive(Debug, Clone)]
+struct NibbleArray {
+    /// The packed values
+    data: Box<[u8]>,
+}
+
+impl NibbleArray {
+    /// Creates an array of zeros covering a chunk
+    fn new() -> Self {
+        Self {
+            data: vec![0; CHUNK_VOLUME / 2].into_boxed_slice(),
+        }
+    }
+
+    /// Gets the value at an index
+    fn get(&self, index: usize) -> u8 {
+        let byte = self.data[index / 2];
+        if index.is_multiple_of(2) { byte & 0x0F } else { byte >> 4 }
+    }
+
+    /// Sets the value at an index
+    fn set(&mut self, index: usize, value: u8) {
+        let byte = &mut self.data[index / 2];
+        if index.is_multiple_of(2) {
+            *byte = (*byte & 0xF0) | (value & 0x0F);
+        } else {
+            *byte = (*byte & 0x0F) | ((value & 0x0F) << 4);
+        }
+    }
+}
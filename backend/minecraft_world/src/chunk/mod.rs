@@ -1,46 +1,71 @@
 pub mod collection;
+pub mod light;
 pub mod section;
 pub mod provider;
+pub mod region;
 
 use minecraft_core::block::Block;
-use minecraft_core::math::{ChunkVector, BlockVector, block_to_local, chunk_index};
-use minecraft_core::constants::{CHUNK_SIZE, CHUNK_VOLUME};
+use minecraft_core::math::{ChunkVector, BlockVector};
+use minecraft_core::constants::CHUNK_SIZE;
+use crate::generator::biome::Biome;
+use light::{ChunkLight, LightKind};
 use section::ChunkSection;
 use serde::{Serialize, Deserialize};
 use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::collections::HashMap;
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The number of columns in a chunk, each with its own biome
+const BIOME_COLUMNS: usize = CHUNK_SIZE.0 * CHUNK_SIZE.2;
 
 /// Represents a chunk in the world
-#[derive(Clone, Debug)]
+///
+/// Chunks are shared between tasks as `Arc<Chunk>`, so every mutation goes
+/// through `&self`: blocks are written under the lock of their section, the
+/// section map has its own lock for adding sections, and the dirty flag and
+/// modification time are atomics.
+#[derive(Debug)]
 pub struct Chunk {
     /// The position of the chunk
     pub position: ChunkVector,
     /// The sections of the chunk, divided by height
     /// Each section represents a 16x16x16 cube of blocks
-    pub sections: HashMap<i32, Arc<RwLock<ChunkSection>>>,
+    sections: RwLock<HashMap<i32, Arc<RwLock<ChunkSection>>>>,
     /// The timestamp when this chunk was last modified
-    pub last_modified: u64,
+    last_modified: AtomicU64,
     /// Whether the chunk has been modified since it was last saved
-    pub is_dirty: bool,
+    is_dirty: AtomicBool,
     /// The timestamp when this chunk was generated or loaded
     pub created_at: u64,
+    /// The time (Unix milliseconds) this chunk was last accessed through the world
+    last_accessed: AtomicU64,
+    /// The biome ID of each column, indexed by `x + z * 16`
+    biomes: [u8; BIOME_COLUMNS],
+    /// The block light and sky light of every block
+    ///
+    /// Light isn't saved; it is worked out by the world's light engine when
+    /// the chunk is loaded.
+    light: RwLock<ChunkLight>,
+    /// Whether the light engine has lit the chunk since it was loaded
+    is_lit: AtomicBool,
 }
 
 impl Chunk {
     /// Creates a new empty chunk at the specified position
     pub fn new(position: ChunkVector) -> Self {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
+        let now = now_secs();
             
         Self {
             position,
-            sections: HashMap::new(),
-            last_modified: now,
-            is_dirty: true,
+            sections: RwLock::new(HashMap::new()),
+            last_modified: AtomicU64::new(now),
+            is_dirty: AtomicBool::new(true),
             created_at: now,
+            last_accessed: AtomicU64::new(now_millis()),
+            biomes: [Biome::Plains.id(); BIOME_COLUMNS],
+            light: RwLock::new(ChunkLight::new()),
+            is_lit: AtomicBool::new(false),
         }
     }
     
@@ -49,11 +74,10 @@ impl Chunk {
         let section_y = (y / CHUNK_SIZE.1) as i32;
         let local_y = y % CHUNK_SIZE.1;
         
-        self.sections
-            .get(&section_y)
-            .and_then(|section| {
+        self.get_section(section_y)
+            .map(|section| {
                 let section = section.read().unwrap();
-                Some(section.get_block(x, local_y, z))
+                section.get_block(x, local_y, z)
             })
     }
     
@@ -69,35 +93,36 @@ impl Chunk {
     }
     
     /// Sets the block at the specified position in the chunk
-    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: Block) -> bool {
+    ///
+    /// Only the affected section is locked, so writers to different sections
+    /// and readers of other sections don't wait for each other.
+    pub fn set_block(&self, x: usize, y: usize, z: usize, block: Block) -> bool {
         let section_y = (y / CHUNK_SIZE.1) as i32;
         let local_y = y % CHUNK_SIZE.1;
         
         // Create the section if it doesn't exist
-        if !self.sections.contains_key(&section_y) {
-            self.sections.insert(section_y, Arc::new(RwLock::new(ChunkSection::new())));
-        }
+        let section = match self.get_section(section_y) {
+            Some(section) => section,
+            None => self.sections
+                .write()
+                .unwrap()
+                .entry(section_y)
+                .or_insert_with(|| Arc::new(RwLock::new(ChunkSection::new())))
+                .clone(),
+        };
         
         // Update the block
-        if let Some(section) = self.sections.get(&section_y) {
-            let mut section = section.write().unwrap();
-            section.set_block(x, local_y, z, block);
-            
-            // Update chunk metadata
-            self.is_dirty = true;
-            self.last_modified = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs();
-                
-            return true;
-        }
+        section.write().unwrap().set_block(x, local_y, z, block);
+        
+        // Update chunk metadata after the write, so a save that marks the
+        // chunk clean can't hide it
+        self.mark_dirty();
         
-        false
+        true
     }
     
     /// Sets the block at the specified world position
-    pub fn set_block_at(&mut self, position: &BlockVector, block: Block) -> bool {
+    pub fn set_block_at(&self, position: &BlockVector, block: Block) -> bool {
         let (local_x, local_y, local_z) = self.world_to_local(position);
         
         if self.is_position_valid(local_x, local_y, local_z) {
@@ -107,37 +132,105 @@ impl Chunk {
         }
     }
     
+    /// Gets the biome of the column at the specified local position
+    pub fn biome(&self, x: usize, z: usize) -> Biome {
+        Biome::from_id(self.biomes[x + z * CHUNK_SIZE.0]).unwrap_or(Biome::Plains)
+    }
+    
+    /// Sets the biome of the column at the specified local position
+    pub fn set_biome(&mut self, x: usize, z: usize, biome: Biome) {
+        self.biomes[x + z * CHUNK_SIZE.0] = biome.id();
+    }
+    
+    /// Returns the biome ID of every column, indexed by `x + z * 16`
+    pub fn biome_ids(&self) -> &[u8] {
+        &self.biomes
+    }
+    
+    /// Gets the light level (0-15) of one kind at the specified local position
+    pub fn get_light(&self, kind: LightKind, x: usize, y: usize, z: usize) -> u8 {
+        self.light.read().unwrap().get(kind, x, y, z)
+    }
+    
+    /// Sets the light level (0-15) of one kind at the specified local position
+    ///
+    /// Light is derived from the blocks, so this doesn't mark the chunk as modified.
+    pub fn set_light(&self, kind: LightKind, x: usize, y: usize, z: usize, level: u8) {
+        self.light.write().unwrap().set(kind, x, y, z, level);
+    }
+    
+    /// Returns whether the light engine has lit the chunk since it was loaded
+    pub fn is_lit(&self) -> bool {
+        self.is_lit.load(Ordering::Acquire)
+    }
+    
+    /// Marks the chunk as lit by the light engine
+    pub fn mark_lit(&self) {
+        self.is_lit.store(true, Ordering::Release);
+    }
+    
     /// Checks if the chunk contains the specified section
     pub fn has_section(&self, section_y: i32) -> bool {
-        self.sections.contains_key(&section_y)
+        self.sections.read().unwrap().contains_key(&section_y)
     }
     
     /// Gets a section of the chunk
     pub fn get_section(&self, section_y: i32) -> Option<Arc<RwLock<ChunkSection>>> {
-        self.sections.get(&section_y).cloned()
+        self.sections.read().unwrap().get(&section_y).cloned()
     }
     
     /// Sets a section of the chunk
-    pub fn set_section(&mut self, section_y: i32, section: ChunkSection) {
-        self.sections.insert(section_y, Arc::new(RwLock::new(section)));
-        self.is_dirty = true;
-        self.last_modified = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
+    pub fn set_section(&self, section_y: i32, section: ChunkSection) {
+        self.sections.write().unwrap().insert(section_y, Arc::new(RwLock::new(section)));
+        self.mark_dirty();
     }
     
     /// Counts the number of non-air blocks in the chunk
     pub fn count_non_air_blocks(&self) -> usize {
-        self.sections.values().map(|section| {
+        self.sections.read().unwrap().values().map(|section| {
             let section = section.read().unwrap();
             section.count_non_air_blocks()
         }).sum()
     }
     
+    /// Returns the timestamp when this chunk was last modified
+    pub fn last_modified(&self) -> u64 {
+        self.last_modified.load(Ordering::Acquire)
+    }
+    
+    /// Returns whether the chunk has been modified since it was last saved
+    pub fn is_dirty(&self) -> bool {
+        self.is_dirty.load(Ordering::Acquire)
+    }
+    
+    /// Marks the chunk as modified
+    pub fn mark_dirty(&self) {
+        self.last_modified.store(now_secs(), Ordering::Release);
+        self.is_dirty.store(true, Ordering::Release);
+    }
+    
     /// Marks the chunk as clean (saved)
-    pub fn mark_clean(&mut self) {
-        self.is_dirty = false;
+    pub fn mark_clean(&self) {
+        self.is_dirty.store(false, Ordering::Release);
+    }
+    
+    /// Records that the chunk was just accessed
+    pub fn touch(&self) {
+        self.last_accessed.store(now_millis(), Ordering::Relaxed);
+    }
+    
+    /// Returns the time (Unix milliseconds) this chunk was last accessed
+    pub fn last_accessed(&self) -> u64 {
+        self.last_accessed.load(Ordering::Relaxed)
+    }
+    
+    /// Returns the approximate number of bytes used by this chunk
+    pub fn memory_usage(&self) -> usize {
+        let sections: usize = self.sections.read().unwrap().values().map(|section| {
+            section.read().unwrap().memory_usage()
+        }).sum();
+        
+        std::mem::size_of::<Self>() + sections + self.light.read().unwrap().memory_usage()
     }
     
     /// Converts a world position to local chunk coordinates
@@ -162,8 +255,13 @@ impl Chunk {
     
     /// Deserializes a chunk from bytes
     pub fn deserialize(data: &[u8]) -> anyhow::Result<Self> {
-        let serializable: SerializableChunk = bincode::deserialize(data)
-            .map_err(|e| anyhow::anyhow!("Failed to deserialize chunk: {}", e))?;
+        let serializable: SerializableChunk = match bincode::deserialize(data) {
+            Ok(serializable) => serializable,
+            // Chunks saved before biomes were stored have no biome data
+            Err(e) => bincode::deserialize::<LegacySerializableChunk>(data)
+                .map(SerializableChunk::from)
+                .map_err(|_| anyhow::anyhow!("Failed to deserialize chunk: {}", e))?,
+        };
             
         Ok(serializable.into())
     }
@@ -176,47 +274,190 @@ struct SerializableChunk {
     sections: HashMap<i32, SerializableChunkSection>,
     last_modified: u64,
     created_at: u64,
+    biomes: Vec<u8>,
+}
+
+/// A chunk serialized before biomes were stored
+#[derive(Deserialize)]
+struct LegacySerializableChunk {
+    position: ChunkVector,
+    sections: HashMap<i32, SerializableChunkSection>,
+    last_modified: u64,
+    created_at: u64,
+}
+
+impl From<LegacySerializableChunk> for SerializableChunk {
+    fn from(legacy: LegacySerializableChunk) -> Self {
+        Self {
+            position: legacy.position,
+            sections: legacy.sections,
+            last_modified: legacy.last_modified,
+            created_at: legacy.created_at,
+            biomes: Vec::new(),
+        }
+    }
 }
 
 /// A serializable representation of a chunk section
 #[derive(Serialize, Deserialize)]
 struct SerializableChunkSection {
-    blocks: Vec<u16>,
+    blocks: Vec<u32>,
 }
 
 impl From<&Chunk> for SerializableChunk {
     fn from(chunk: &Chunk) -> Self {
         let mut sections = HashMap::new();
         
-        for (section_y, section) in &chunk.sections {
+        for (section_y, section) in chunk.sections.read().unwrap().iter() {
             let section = section.read().unwrap();
             sections.insert(*section_y, SerializableChunkSection {
-                blocks: section.blocks.clone(),
+                blocks: section.to_packed(),
             });
         }
         
         Self {
             position: chunk.position,
             sections,
-            last_modified: chunk.last_modified,
+            last_modified: chunk.last_modified(),
             created_at: chunk.created_at,
+            biomes: chunk.biomes.to_vec(),
         }
     }
 }
 
 impl From<SerializableChunk> for Chunk {
     fn from(serializable: SerializableChunk) -> Self {
-        let mut chunk = Chunk::new(serializable.position);
-        chunk.last_modified = serializable.last_modified;
-        chunk.created_at = serializable.created_at;
+        let sections = serializable.sections
+            .into_iter()
+            .map(|(section_y, serializable_section)| {
+                let section = ChunkSection::from_packed(serializable_section.blocks);
+                (section_y, Arc::new(RwLock::new(section)))
+            })
+            .collect();
         
-        for (section_y, serializable_section) in serializable.sections {
-            let mut section = ChunkSection::new();
-            section.blocks = serializable_section.blocks;
-            chunk.sections.insert(section_y, Arc::new(RwLock::new(section)));
+        let mut biomes = [Biome::Plains.id(); BIOME_COLUMNS];
+        if serializable.biomes.len() == BIOME_COLUMNS {
+            biomes.copy_from_slice(&serializable.biomes);
         }
         
-        chunk.is_dirty = false;
-        chunk
+        Chunk {
+            position: serializable.position,
+            sections: RwLock::new(sections),
+            last_modified: AtomicU64::new(serializable.last_modified),
+            is_dirty: AtomicBool::new(false),
+            created_at: serializable.created_at,
+            last_accessed: AtomicU64::new(now_millis()),
+            biomes,
+            light: RwLock::new(ChunkLight::new()),
+            is_lit: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Clone for Chunk {
+    /// Clones the chunk, copying its blocks and light
+    ///
+    /// The clone is a snapshot: changes to either chunk afterwards don't show
+    /// up in the other.
+    fn clone(&self) -> Self {
+        let sections = self
+            .sections
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(section_y, section)| (*section_y, Arc::new(RwLock::new(section.read().unwrap().clone()))))
+            .collect();
+
+        Self {
+            position: self.position,
+            sections: RwLock::new(sections),
+            last_modified: AtomicU64::new(self.last_modified()),
+            is_dirty: AtomicBool::new(self.is_dirty()),
+            created_at: self.created_at,
+            last_accessed: AtomicU64::new(self.last_accessed()),
+            biomes: self.biomes,
+            light: RwLock::new(self.light.read().unwrap().clone()),
+            is_lit: AtomicBool::new(self.is_lit()),
+        }
+    }
+}
+
+/// Returns the current Unix time in seconds
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Returns the current Unix time in milliseconds
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minecraft_core::block::BlockType;
+    use std::thread;
+
+    #[test]
+    fn clones_are_snapshots() {
+        let chunk = Chunk::new(ChunkVector::new(0, 0, 0));
+        chunk.set_block(1, 1, 1, Block::new(BlockType::Stone));
+
+        let copy = chunk.clone();
+        chunk.set_block(1, 1, 1, Block::new(BlockType::Dirt));
+        copy.set_block(2, 2, 2, Block::new(BlockType::Sand));
+
+        assert_eq!(copy.get_block(1, 1, 1), Some(Block::new(BlockType::Stone)));
+        assert_eq!(chunk.get_block(1, 1, 1), Some(Block::new(BlockType::Dirt)));
+        assert_eq!(chunk.get_block(2, 2, 2), Some(Block::default()));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn blocks_can_be_set_and_read_from_many_threads() {
+        let chunk = Arc::new(Chunk::new(ChunkVector::new(0, 0, 0)));
+        let block_types = [BlockType::Stone, BlockType::Dirt, BlockType::Sand, BlockType::Gravel];
+
+        // Each thread fills its own layers of the same section, checking its
+        // writes as it goes while the others write around it
+        let threads: Vec<_> = block_types
+            .iter()
+            .enumerate()
+            .map(|(index, block_type)| {
+                let chunk = chunk.clone();
+                let block = Block::new(*block_type);
+                thread::spawn(move || {
+                    for y in (index..CHUNK_SIZE.1).step_by(block_types.len()) {
+                        for x in 0..CHUNK_SIZE.0 {
+                            for z in 0..CHUNK_SIZE.2 {
+                                assert!(chunk.set_block(x, y, z, block));
+                                assert_eq!(chunk.get_block(x, y, z), Some(block));
+                            }
+                        }
+                        // Snapshots taken mid-write are still whole chunks
+                        assert!(chunk.clone().count_non_air_blocks() > 0);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        for y in 0..CHUNK_SIZE.1 {
+            let expected = Block::new(block_types[y % block_types.len()]);
+            for x in 0..CHUNK_SIZE.0 {
+                for z in 0..CHUNK_SIZE.2 {
+                    assert_eq!(chunk.get_block(x, y, z), Some(expected));
+                }
+            }
+        }
+        assert_eq!(chunk.count_non_air_blocks(), CHUNK_SIZE.0 * CHUNK_SIZE.1 * CHUNK_SIZE.2);
+        assert!(chunk.is_dirty());
+    }
+}
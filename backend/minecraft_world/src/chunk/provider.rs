@@ -1,30 +1,47 @@
 use super::Chunk;
 use minecraft_core::math::ChunkVector;
 use crate::generator::TerrainGenerator;
+use crate::generator::scheduler::{GenerationMetrics, GenerationScheduler};
 use std::sync::Arc;
-use std::future::Future;
 use anyhow::Result;
+use async_trait::async_trait;
 
 /// A trait for providing chunks, whether from generation or loading
+#[async_trait]
 pub trait ChunkProvider: Send + Sync {
     /// Checks if a chunk exists at the specified position
-    fn chunk_exists(&self, position: ChunkVector) -> bool;
+    async fn chunk_exists(&self, position: ChunkVector) -> bool;
     
     /// Gets a chunk at the specified position, loading or generating it if necessary
-    fn get_chunk(&self, position: ChunkVector) -> impl Future<Output = Result<Arc<Chunk>>> + Send;
+    async fn get_chunk(&self, position: ChunkVector) -> Result<Arc<Chunk>>;
     
     /// Gets a chunk if it's already loaded, without generating or loading from storage
     fn get_chunk_if_loaded(&self, position: ChunkVector) -> Option<Arc<Chunk>>;
     
+    /// Returns every chunk currently loaded in memory
+    fn loaded_chunks(&self) -> Vec<Arc<Chunk>>;
+    
     /// Unloads a chunk from memory
     fn unload_chunk(&self, position: ChunkVector) -> bool;
     
     /// Saves a chunk to storage
-    fn save_chunk(&self, chunk: &Chunk) -> impl Future<Output = Result<()>> + Send;
+    async fn save_chunk(&self, chunk: &Chunk) -> Result<()>;
+    
+    /// Saves several chunks to storage, as a single batch where the storage supports it
+    async fn save_chunks(&self, chunks: &[Arc<Chunk>]) -> Result<()> {
+        for chunk in chunks {
+            self.save_chunk(chunk).await?;
+        }
+        Ok(())
+    }
+    
+    /// Returns the counters of the provider's generation scheduler, if it has one
+    fn generation_metrics(&self) -> Option<GenerationMetrics> {
+        None
+    }
     
     /// Forces a chunk to be generated, even if it already exists
-    fn force_generate_chunk(&self, position: ChunkVector, generator: &dyn TerrainGenerator) 
-        -> impl Future<Output = Result<Arc<Chunk>>> + Send;
+    async fn force_generate_chunk(&self, position: ChunkVector, generator: &dyn TerrainGenerator) -> Result<Arc<Chunk>>;
 }
 
 /// A trait for a chunk provider that can be cloned
@@ -37,21 +54,22 @@ impl<T> CloneableChunkProvider for T where T: ChunkProvider + Clone {}
 #[derive(Clone)]
 pub struct MemoryChunkProvider {
     chunks: Arc<dashmap::DashMap<i64, Arc<Chunk>>>,
-    generator: Arc<dyn TerrainGenerator + Send + Sync>,
+    scheduler: Arc<GenerationScheduler>,
 }
 
 impl MemoryChunkProvider {
     /// Creates a new memory chunk provider with the specified generator
-    pub fn new(generator: impl TerrainGenerator + Send + Sync + 'static) -> Self {
-        Self {
+    pub fn new(generator: Arc<dyn TerrainGenerator>) -> Result<Self> {
+        Ok(Self {
             chunks: Arc::new(dashmap::DashMap::new()),
-            generator: Arc::new(generator),
-        }
+            scheduler: Arc::new(GenerationScheduler::new(generator)?),
+        })
     }
 }
 
+#[async_trait]
 impl ChunkProvider for MemoryChunkProvider {
-    fn chunk_exists(&self, position: ChunkVector) -> bool {
+    async fn chunk_exists(&self, position: ChunkVector) -> bool {
         self.chunks.contains_key(&position.to_key())
     }
     
@@ -63,14 +81,11 @@ impl ChunkProvider for MemoryChunkProvider {
             return Ok(chunk.clone());
         }
         
-        // Generate a new chunk
-        let chunk = self.generator.generate_chunk(position).await?;
-        let chunk_arc = Arc::new(chunk);
-        
-        // Store it in the map
-        self.chunks.insert(key, chunk_arc.clone());
-        
-        Ok(chunk_arc)
+        // Generate a new chunk, sharing the job with concurrent requests, and
+        // store it in the map
+        self.scheduler
+            .generate(position, |chunk| self.chunks.entry(key).or_insert_with(|| Arc::new(chunk)).clone())
+            .await
     }
     
     fn get_chunk_if_loaded(&self, position: ChunkVector) -> Option<Arc<Chunk>> {
@@ -78,11 +93,19 @@ impl ChunkProvider for MemoryChunkProvider {
         self.chunks.get(&key).map(|chunk| chunk.clone())
     }
     
+    fn loaded_chunks(&self) -> Vec<Arc<Chunk>> {
+        self.chunks.iter().map(|entry| entry.value().clone()).collect()
+    }
+    
     fn unload_chunk(&self, position: ChunkVector) -> bool {
         let key = position.to_key();
         self.chunks.remove(&key).is_some()
     }
     
+    fn generation_metrics(&self) -> Option<GenerationMetrics> {
+        Some(self.scheduler.metrics())
+    }
+    
     async fn save_chunk(&self, _chunk: &Chunk) -> Result<()> {
         // Memory provider doesn't actually save chunks
         Ok(())
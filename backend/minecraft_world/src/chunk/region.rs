@@ -0,0 +1,535 @@
+use super::Chunk;
+use super::provider::ChunkProvider;
+use crate::generator::TerrainGenerator;
+use crate::generator::scheduler::{GenerationMetrics, GenerationScheduler};
+use minecraft_core::constants::{CHUNK_SIZE, MAX_BUILD_HEIGHT, MIN_BUILD_HEIGHT};
+use minecraft_core::math::ChunkVector;
+use dashmap::DashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+/// The number of chunk columns along each side of a region
+pub const REGION_SIZE: i32 = 32;
+
+/// The size of a sector in a region file, in bytes
+const SECTOR_SIZE: usize = 4096;
+
+/// The size of one offset table entry (sector offset and byte length)
+const ENTRY_SIZE: usize = 8;
+
+/// The zstd compression level used for chunk data
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The lowest chunk Y stored in a region
+const MIN_SECTION: i32 = MIN_BUILD_HEIGHT / CHUNK_SIZE.1 as i32;
+
+/// The number of chunks stored per column
+const SECTIONS_PER_COLUMN: usize = ((MAX_BUILD_HEIGHT - MIN_BUILD_HEIGHT) / CHUNK_SIZE.1 as i32) as usize;
+
+/// The number of chunk slots in a region
+const SLOTS: usize = (REGION_SIZE * REGION_SIZE) as usize * SECTIONS_PER_COLUMN;
+
+/// The number of sectors taken up by the offset table
+const HEADER_SECTORS: usize = (SLOTS * ENTRY_SIZE).div_ceil(SECTOR_SIZE);
+
+/// The position of a region, in region coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegionPosition {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl RegionPosition {
+    /// Returns the region containing a chunk
+    pub fn of(chunk: ChunkVector) -> Self {
+        Self {
+            x: chunk.x.div_euclid(REGION_SIZE),
+            z: chunk.z.div_euclid(REGION_SIZE),
+        }
+    }
+
+    /// Returns the file name of this region
+    pub fn file_name(&self) -> String {
+        format!("r.{}.{}.region", self.x, self.z)
+    }
+}
+
+/// Where a chunk is stored within a region file
+#[derive(Debug, Clone, Copy, Default)]
+struct SlotEntry {
+    /// The first sector of the data, or 0 if the chunk isn't stored
+    sector: u32,
+    /// The length of the compressed data in bytes
+    length: u32,
+}
+
+impl SlotEntry {
+    /// Returns the number of sectors the data occupies
+    fn sector_count(&self) -> usize {
+        (self.length as usize).div_ceil(SECTOR_SIZE)
+    }
+}
+
+/// A region file holding the chunks of 32x32 columns
+///
+/// The file starts with an offset table with one entry per chunk slot,
+/// followed by the chunk data in 4 KiB sectors. Each entry holds the first
+/// sector and the byte length of a zstd-compressed `Chunk::serialize` blob.
+/// Rewritten chunks always go to free sectors before the old ones are
+/// released, so a crash mid-write leaves the previous copy readable.
+pub struct RegionFile {
+    /// The open file
+    file: File,
+    /// The offset table
+    entries: Vec<SlotEntry>,
+    /// Which sectors are in use
+    used_sectors: Vec<bool>,
+}
+
+impl RegionFile {
+    /// Opens a region file, creating it if it doesn't exist
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("Failed to open region file {}", path.display()))?;
+
+        let header_size = (HEADER_SECTORS * SECTOR_SIZE) as u64;
+        if file.metadata()?.len() < header_size {
+            file.set_len(header_size)?;
+        }
+
+        // Read the offset table
+        let mut header = vec![0u8; SLOTS * ENTRY_SIZE];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+
+        let entries: Vec<SlotEntry> = header
+            .chunks_exact(ENTRY_SIZE)
+            .map(|entry| SlotEntry {
+                sector: u32::from_le_bytes(entry[0..4].try_into().unwrap()),
+                length: u32::from_le_bytes(entry[4..8].try_into().unwrap()),
+            })
+            .collect();
+
+        // Rebuild the sector allocation from the table
+        let total_sectors = (file.metadata()?.len() as usize).div_ceil(SECTOR_SIZE);
+        let mut used_sectors = vec![false; total_sectors];
+        used_sectors[..HEADER_SECTORS].fill(true);
+
+        for entry in entries.iter().filter(|entry| entry.sector != 0) {
+            let start = entry.sector as usize;
+            let end = start + entry.sector_count();
+            if end > used_sectors.len() {
+                used_sectors.resize(end, false);
+            }
+            used_sectors[start..end].fill(true);
+        }
+
+        Ok(Self {
+            file,
+            entries,
+            used_sectors,
+        })
+    }
+
+    /// Checks if a chunk is stored in this region
+    pub fn contains(&self, position: ChunkVector) -> bool {
+        slot_index(position).is_some_and(|slot| self.entries[slot].sector != 0)
+    }
+
+    /// Reads and decompresses a chunk, or returns None if it isn't stored
+    pub fn read_chunk(&mut self, position: ChunkVector) -> Result<Option<Chunk>> {
+        let Some(slot) = slot_index(position) else {
+            return Ok(None);
+        };
+
+        let entry = self.entries[slot];
+        if entry.sector == 0 {
+            return Ok(None);
+        }
+
+        let mut compressed = vec![0u8; entry.length as usize];
+        self.file.seek(SeekFrom::Start(entry.sector as u64 * SECTOR_SIZE as u64))?;
+        self.file.read_exact(&mut compressed)?;
+
+        let data = zstd::decode_all(compressed.as_slice())?;
+        Ok(Some(Chunk::deserialize(&data)?))
+    }
+
+    /// Compresses and writes a chunk
+    ///
+    /// Returns false if the chunk is outside the build height and can't be stored.
+    pub fn write_chunk(&mut self, chunk: &Chunk) -> Result<bool> {
+        let Some(slot) = slot_index(chunk.position) else {
+            return Ok(false);
+        };
+
+        let compressed = zstd::encode_all(chunk.serialize()?.as_slice(), COMPRESSION_LEVEL)?;
+        let new_entry = SlotEntry {
+            sector: 0,
+            length: compressed.len() as u32,
+        };
+        let sector = self.allocate(new_entry.sector_count());
+
+        // Write the data, padded to whole sectors, before pointing the table at it
+        let mut padded = compressed;
+        padded.resize(new_entry.sector_count() * SECTOR_SIZE, 0);
+        self.file.seek(SeekFrom::Start((sector * SECTOR_SIZE) as u64))?;
+        self.file.write_all(&padded)?;
+
+        let old_entry = self.entries[slot];
+        self.set_entry(slot, SlotEntry { sector: sector as u32, ..new_entry })?;
+
+        // Release the old copy only once the new one is in place
+        if old_entry.sector != 0 {
+            let start = old_entry.sector as usize;
+            self.used_sectors[start..start + old_entry.sector_count()].fill(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Finds a run of free sectors, growing the file if there is none
+    fn allocate(&mut self, count: usize) -> usize {
+        let mut run_start = HEADER_SECTORS;
+        let mut run_length = 0;
+
+        for sector in HEADER_SECTORS..self.used_sectors.len() {
+            if self.used_sectors[sector] {
+                run_start = sector + 1;
+                run_length = 0;
+            } else {
+                run_length += 1;
+                if run_length == count {
+                    break;
+                }
+            }
+        }
+
+        if run_length < count {
+            // Append at the end, reusing any free sectors at the tail
+            self.used_sectors.resize(run_start + count, false);
+        }
+
+        self.used_sectors[run_start..run_start + count].fill(true);
+        run_start
+    }
+
+    /// Updates an entry in the offset table, both in memory and on disk
+    fn set_entry(&mut self, slot: usize, entry: SlotEntry) -> Result<()> {
+        let mut bytes = [0u8; ENTRY_SIZE];
+        bytes[0..4].copy_from_slice(&entry.sector.to_le_bytes());
+        bytes[4..8].copy_from_slice(&entry.length.to_le_bytes());
+
+        self.file.seek(SeekFrom::Start((slot * ENTRY_SIZE) as u64))?;
+        self.file.write_all(&bytes)?;
+        self.entries[slot] = entry;
+
+        Ok(())
+    }
+}
+
+/// Returns the slot of a chunk within its region, or None if it is outside the build height
+fn slot_index(position: ChunkVector) -> Option<usize> {
+    let local_y = position.y - MIN_SECTION;
+    if local_y < 0 || local_y as usize >= SECTIONS_PER_COLUMN {
+        return None;
+    }
+
+    let local_x = position.x.rem_euclid(REGION_SIZE) as usize;
+    let local_z = position.z.rem_euclid(REGION_SIZE) as usize;
+
+    Some((local_x * REGION_SIZE as usize + local_z) * SECTIONS_PER_COLUMN + local_y as usize)
+}
+
+/// A chunk provider that persists chunks to region files
+///
+/// Chunks are loaded from disk when first requested and generated if they
+/// have never been saved. Chunks outside the build height are generated but
+/// never stored.
+#[derive(Clone)]
+pub struct RegionChunkProvider {
+    /// The directory containing the region files
+    directory: PathBuf,
+    /// The loaded chunks
+    chunks: Arc<DashMap<i64, Arc<Chunk>>>,
+    /// The open region files
+    regions: Arc<DashMap<RegionPosition, Arc<Mutex<RegionFile>>>>,
+    /// Generates chunks that haven't been saved
+    scheduler: Arc<GenerationScheduler>,
+}
+
+impl RegionChunkProvider {
+    /// Creates a region chunk provider storing its files in the specified directory
+    pub fn new(directory: impl AsRef<Path>, generator: Arc<dyn TerrainGenerator>) -> Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        std::fs::create_dir_all(&directory)?;
+
+        Ok(Self {
+            directory,
+            chunks: Arc::new(DashMap::new()),
+            regions: Arc::new(DashMap::new()),
+            scheduler: Arc::new(GenerationScheduler::new(generator)?),
+        })
+    }
+
+    /// Returns the region file for a chunk, opening it if necessary
+    ///
+    /// When `create` is false, returns None if the file doesn't exist yet.
+    fn region(&self, position: ChunkVector, create: bool) -> Result<Option<Arc<Mutex<RegionFile>>>> {
+        let region_position = RegionPosition::of(position);
+        if let Some(region) = self.regions.get(&region_position) {
+            return Ok(Some(region.clone()));
+        }
+
+        let path = self.directory.join(region_position.file_name());
+        if !create && !path.exists() {
+            return Ok(None);
+        }
+
+        let region = self
+            .regions
+            .entry(region_position)
+            .or_try_insert_with(|| RegionFile::open(&path).map(|file| Arc::new(Mutex::new(file))))?
+            .clone();
+
+        Ok(Some(region))
+    }
+
+    /// Reads a chunk from disk on a blocking thread
+    async fn load_chunk(&self, position: ChunkVector) -> Result<Option<Chunk>> {
+        let provider = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let Some(region) = provider.region(position, false)? else {
+                return Ok(None);
+            };
+            let mut region = region.lock().unwrap();
+            region.read_chunk(position)
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl ChunkProvider for RegionChunkProvider {
+    async fn chunk_exists(&self, position: ChunkVector) -> bool {
+        if self.chunks.contains_key(&position.to_key()) {
+            return true;
+        }
+
+        match self.region(position, false) {
+            Ok(Some(region)) => region.lock().unwrap().contains(position),
+            _ => false,
+        }
+    }
+
+    async fn get_chunk(&self, position: ChunkVector) -> Result<Arc<Chunk>> {
+        let key = position.to_key();
+
+        // Try to get an already loaded chunk
+        if let Some(chunk) = self.chunks.get(&key) {
+            return Ok(chunk.clone());
+        }
+
+        // Load it from disk, or generate it if it was never saved. Another
+        // task may have loaded the same chunk in the meantime
+        let store = |chunk| self.chunks.entry(key).or_insert_with(|| Arc::new(chunk)).clone();
+        match self.load_chunk(position).await? {
+            Some(chunk) => Ok(store(chunk)),
+            None => self.scheduler.generate(position, store).await,
+        }
+    }
+
+    fn get_chunk_if_loaded(&self, position: ChunkVector) -> Option<Arc<Chunk>> {
+        self.chunks.get(&position.to_key()).map(|chunk| chunk.clone())
+    }
+
+    fn loaded_chunks(&self) -> Vec<Arc<Chunk>> {
+        self.chunks.iter().map(|entry| entry.value().clone()).collect()
+    }
+
+    fn unload_chunk(&self, position: ChunkVector) -> bool {
+        self.chunks.remove(&position.to_key()).is_some()
+    }
+
+    fn generation_metrics(&self) -> Option<GenerationMetrics> {
+        Some(self.scheduler.metrics())
+    }
+
+    async fn save_chunk(&self, chunk: &Chunk) -> Result<()> {
+        let provider = self.clone();
+        let chunk = chunk.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let Some(region) = provider.region(chunk.position, true)? else {
+                return Ok(());
+            };
+            let mut region = region.lock().unwrap();
+            if !region.write_chunk(&chunk)? {
+                log::debug!("Not saving chunk {:?} outside the build height", chunk.position);
+            }
+            Ok(())
+        })
+        .await?
+    }
+
+    async fn force_generate_chunk(&self, position: ChunkVector, generator: &dyn TerrainGenerator) -> Result<Arc<Chunk>> {
+        let chunk = Arc::new(generator.generate_chunk(position).await?);
+        self.chunks.insert(position.to_key(), chunk.clone());
+
+        Ok(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minecraft_core::block::{Block, BlockType};
+    use rand::Rng;
+
+    /// A region file in a fresh temporary directory, removed when dropped
+    struct TempRegion {
+        directory: PathBuf,
+    }
+
+    impl TempRegion {
+        /// Creates the directory
+        fn new() -> Self {
+            let directory = std::env::temp_dir().join(format!("region-test-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&directory).unwrap();
+            Self { directory }
+        }
+
+        /// Returns the path of the region file
+        fn path(&self) -> PathBuf {
+            self.directory.join(RegionPosition { x: 0, z: 0 }.file_name())
+        }
+
+        /// Opens the region file, creating it the first time
+        fn open(&self) -> RegionFile {
+            RegionFile::open(&self.path()).unwrap()
+        }
+    }
+
+    impl Drop for TempRegion {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.directory);
+        }
+    }
+
+    /// Creates a chunk with a few blocks set
+    fn small_chunk(position: ChunkVector) -> Chunk {
+        let chunk = Chunk::new(position);
+        chunk.set_block(0, 0, 0, Block::new(BlockType::Stone));
+        chunk.set_block(5, 6, 7, Block::new(BlockType::OakLog));
+        chunk
+    }
+
+    /// Creates a chunk full of random blocks, which compresses to several sectors
+    fn noisy_chunk(position: ChunkVector) -> Chunk {
+        let mut rng = rand::thread_rng();
+        let chunk = Chunk::new(position);
+        for x in 0..CHUNK_SIZE.0 {
+            for y in 0..CHUNK_SIZE.1 {
+                for z in 0..CHUNK_SIZE.2 {
+                    chunk.set_block(x, y, z, Block::with_metadata(BlockType::Stone, rng.gen()));
+                }
+            }
+        }
+        chunk
+    }
+
+    /// Checks that two chunks hold the same blocks
+    fn assert_same_blocks(actual: &Chunk, expected: &Chunk) {
+        assert_eq!(actual.position, expected.position);
+        for x in 0..CHUNK_SIZE.0 {
+            for y in 0..CHUNK_SIZE.1 {
+                for z in 0..CHUNK_SIZE.2 {
+                    assert_eq!(actual.get_block(x, y, z), expected.get_block(x, y, z), "at {} {} {}", x, y, z);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_round_trip() {
+        let temp = TempRegion::new();
+        let mut region = temp.open();
+        let chunk = small_chunk(ChunkVector::new(3, 2, 5));
+
+        assert!(region.write_chunk(&chunk).unwrap());
+        assert!(region.contains(chunk.position));
+        assert_same_blocks(&region.read_chunk(chunk.position).unwrap().unwrap(), &chunk);
+    }
+
+    #[test]
+    fn reopened_regions_keep_their_chunks() {
+        let temp = TempRegion::new();
+        let chunks = [small_chunk(ChunkVector::new(0, 0, 0)), noisy_chunk(ChunkVector::new(31, 15, 31))];
+        {
+            let mut region = temp.open();
+            for chunk in &chunks {
+                region.write_chunk(chunk).unwrap();
+            }
+        }
+
+        let mut region = temp.open();
+        for chunk in &chunks {
+            assert_same_blocks(&region.read_chunk(chunk.position).unwrap().unwrap(), chunk);
+        }
+
+        // New chunks don't overwrite the ones already stored
+        let extra = small_chunk(ChunkVector::new(1, 0, 0));
+        region.write_chunk(&extra).unwrap();
+        for chunk in chunks.iter().chain([&extra]) {
+            assert_same_blocks(&region.read_chunk(chunk.position).unwrap().unwrap(), chunk);
+        }
+    }
+
+    #[test]
+    fn rewritten_chunks_can_grow_past_their_sectors() {
+        let temp = TempRegion::new();
+        let mut region = temp.open();
+        let position = ChunkVector::new(4, 4, 4);
+        let neighbour = small_chunk(ChunkVector::new(4, 5, 4));
+
+        // The neighbour is written right after the small chunk, so growing in place would overwrite it
+        region.write_chunk(&small_chunk(position)).unwrap();
+        region.write_chunk(&neighbour).unwrap();
+        let grown = noisy_chunk(position);
+        region.write_chunk(&grown).unwrap();
+        assert!(region.entries[slot_index(position).unwrap()].sector_count() > 1);
+
+        assert_same_blocks(&region.read_chunk(position).unwrap().unwrap(), &grown);
+        assert_same_blocks(&region.read_chunk(neighbour.position).unwrap().unwrap(), &neighbour);
+
+        drop(region);
+        let mut region = temp.open();
+        assert_same_blocks(&region.read_chunk(position).unwrap().unwrap(), &grown);
+        assert_same_blocks(&region.read_chunk(neighbour.position).unwrap().unwrap(), &neighbour);
+    }
+
+    #[test]
+    fn missing_chunks_read_as_none() {
+        let temp = TempRegion::new();
+        let mut region = temp.open();
+        region.write_chunk(&small_chunk(ChunkVector::new(0, 0, 0))).unwrap();
+
+        let missing = ChunkVector::new(0, 1, 0);
+        assert!(!region.contains(missing));
+        assert!(region.read_chunk(missing).unwrap().is_none());
+
+        // Chunks outside the build height are never stored
+        let too_high = ChunkVector::new(0, SECTIONS_PER_COLUMN as i32, 0);
+        assert!(!region.write_chunk(&small_chunk(too_high)).unwrap());
+        assert!(region.read_chunk(too_high).unwrap().is_none());
+    }
+}
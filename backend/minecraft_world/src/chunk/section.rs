@@ -1,94 +1,384 @@
 use minecraft_core::block::Block;
 use minecraft_core::block::types::BlockType;
-use minecraft_core::constants::{CHUNK_SIZE, CHUNK_VOLUME};
+use minecraft_core::constants::CHUNK_VOLUME;
 use minecraft_core::math::chunk_index;
+use std::collections::HashMap;
+
+/// The index widths a palette can use, smallest first
+const PALETTE_BITS: [u32; 3] = [4, 8, 12];
+
+/// The largest palette before a section switches to direct storage
+///
+/// With more distinct blocks than this, 12-bit indices plus the palette
+/// take more memory than storing every block directly.
+const MAX_PALETTE_SIZE: usize = 2048;
+
+/// How the blocks of a section are stored
+#[derive(Clone, Debug)]
+enum Storage {
+    /// Every block is the same
+    Uniform(u32),
+    /// Indices into a palette of packed blocks
+    Palette {
+        palette: Vec<u32>,
+        indices: PackedArray,
+    },
+    /// Every block stored as a packed u32 (type << 16 | metadata)
+    Direct(Vec<u32>),
+}
+
+/// A fixed-size array of small integers packed into u64 words
+///
+/// Entries never straddle two words, so 12-bit entries leave 4 bits of
+/// each word unused in exchange for simpler indexing.
+#[derive(Clone, Debug)]
+struct PackedArray {
+    /// The number of bits per entry
+    bits: u32,
+    /// The number of entries in each word
+    per_word: usize,
+    /// The packed entries
+    words: Vec<u64>,
+}
+
+impl PackedArray {
+    /// Creates an array of CHUNK_VOLUME zeroes with the specified entry width
+    fn new(bits: u32) -> Self {
+        let per_word = (64 / bits) as usize;
+
+        Self {
+            bits,
+            per_word,
+            words: vec![0; CHUNK_VOLUME.div_ceil(per_word)],
+        }
+    }
+
+    /// Gets the entry at an index
+    fn get(&self, index: usize) -> usize {
+        let word = self.words[index / self.per_word];
+        let shift = (index % self.per_word) as u32 * self.bits;
+        ((word >> shift) & self.mask()) as usize
+    }
+
+    /// Sets the entry at an index
+    fn set(&mut self, index: usize, value: usize) {
+        let mask = self.mask();
+        let shift = (index % self.per_word) as u32 * self.bits;
+        let word = &mut self.words[index / self.per_word];
+        *word = (*word & !(mask << shift)) | ((value as u64 & mask) << shift);
+    }
+
+    /// Returns the mask for a single entry
+    fn mask(&self) -> u64 {
+        (1 << self.bits) - 1
+    }
+
+    /// Returns the largest palette the array can index
+    fn capacity(&self) -> usize {
+        (1 << self.bits).min(MAX_PALETTE_SIZE)
+    }
+}
 
 /// Represents a section of a chunk (16x16x16 blocks)
+///
+/// Blocks are palette-compressed: a section of a single block stores just
+/// that block, a section with few distinct blocks stores 4, 8 or 12-bit
+/// indices into a palette, and a very diverse section stores every block
+/// directly. The storage is resized automatically as blocks are set.
 #[derive(Clone, Debug)]
 pub struct ChunkSection {
-    /// Blocks in this section, stored as packed u16 values
-    pub blocks: Vec<u16>,
+    /// The block storage
+    storage: Storage,
     /// Number of non-air blocks in this section
-    pub non_air_count: u16,
+    non_air_count: u16,
 }
 
 impl ChunkSection {
     /// Creates a new empty chunk section filled with air
     pub fn new() -> Self {
         Self {
-            blocks: vec![0; CHUNK_VOLUME], // 0 = Air block type id
+            storage: Storage::Uniform(Block::default().pack()),
             non_air_count: 0,
         }
     }
-    
+
+    /// Creates a section from packed blocks (type << 16 | metadata) in `chunk_index` order
+    pub fn from_packed(mut blocks: Vec<u32>) -> Self {
+        blocks.resize(CHUNK_VOLUME, 0);
+
+        let non_air_count = blocks
+            .iter()
+            .filter(|&&packed| !Block::unpack(packed).is_air())
+            .count() as u16;
+
+        Self {
+            storage: compact(blocks.iter().copied()),
+            non_air_count,
+        }
+    }
+
+    /// Returns every block, packed (type << 16 | metadata), in `chunk_index` order
+    pub fn to_packed(&self) -> Vec<u32> {
+        match &self.storage {
+            Storage::Uniform(packed) => vec![*packed; CHUNK_VOLUME],
+            Storage::Palette { palette, indices } => {
+                (0..CHUNK_VOLUME).map(|index| palette[indices.get(index)]).collect()
+            }
+            Storage::Direct(blocks) => blocks.clone(),
+        }
+    }
+
     /// Gets the block at the specified local position
     pub fn get_block(&self, x: usize, y: usize, z: usize) -> Block {
         let index = chunk_index(x, y, z);
-        
-        if index < self.blocks.len() {
-            let packed = self.blocks[index];
-            Block::unpack(packed)
+
+        if index < CHUNK_VOLUME {
+            Block::unpack(self.get_packed(index))
         } else {
             Block::default() // Return air if out of bounds
         }
     }
-    
+
     /// Sets the block at the specified local position
     pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: Block) {
         let index = chunk_index(x, y, z);
-        
-        if index < self.blocks.len() {
-            let old_block = Block::unpack(self.blocks[index]);
-            let packed = block.pack();
-            
+
+        if index < CHUNK_VOLUME {
+            let old_block = Block::unpack(self.get_packed(index));
+
             // Update non-air count
             if old_block.is_air() && !block.is_air() {
                 self.non_air_count += 1;
             } else if !old_block.is_air() && block.is_air() {
                 self.non_air_count = self.non_air_count.saturating_sub(1);
             }
-            
-            self.blocks[index] = packed;
+
+            self.set_packed(index, block.pack());
         }
     }
-    
+
     /// Fills the entire section with the specified block
     pub fn fill(&mut self, block: Block) {
-        let packed = block.pack();
-        self.blocks.fill(packed);
-        
+        self.storage = Storage::Uniform(block.pack());
+
         if block.is_air() {
             self.non_air_count = 0;
         } else {
             self.non_air_count = CHUNK_VOLUME as u16;
         }
     }
-    
+
     /// Fills the section with the specified block type, using default metadata
     pub fn fill_with(&mut self, block_type: BlockType) {
         self.fill(Block::new(block_type));
     }
-    
+
     /// Returns the number of non-air blocks in this section
     pub fn count_non_air_blocks(&self) -> usize {
         self.non_air_count as usize
     }
-    
+
     /// Returns whether this section is completely filled with air
     pub fn is_empty(&self) -> bool {
         self.non_air_count == 0
     }
-    
+
     /// Returns whether this section is completely filled with non-air blocks
     pub fn is_full(&self) -> bool {
         self.non_air_count as usize == CHUNK_VOLUME
     }
-    
+
+    /// Returns the approximate number of bytes used by the block storage
+    pub fn memory_usage(&self) -> usize {
+        let storage = match &self.storage {
+            Storage::Uniform(_) => 0,
+            Storage::Palette { palette, indices } => palette.len() * 4 + indices.words.len() * 8,
+            Storage::Direct(blocks) => blocks.len() * 4,
+        };
+
+        std::mem::size_of::<Self>() + storage
+    }
+
     /// Creates a deep copy of this section
     pub fn clone_section(&self) -> Self {
-        Self {
-            blocks: self.blocks.clone(),
-            non_air_count: self.non_air_count,
+        self.clone()
+    }
+
+    /// Gets the packed block at an index
+    fn get_packed(&self, index: usize) -> u32 {
+        match &self.storage {
+            Storage::Uniform(packed) => *packed,
+            Storage::Palette { palette, indices } => palette[indices.get(index)],
+            Storage::Direct(blocks) => blocks[index],
+        }
+    }
+
+    /// Sets the packed block at an index, growing the storage if needed
+    fn set_packed(&mut self, index: usize, packed: u32) {
+        match &mut self.storage {
+            Storage::Uniform(current) => {
+                if *current == packed {
+                    return;
+                }
+
+                let mut indices = PackedArray::new(PALETTE_BITS[0]);
+                indices.set(index, 1);
+                self.storage = Storage::Palette {
+                    palette: vec![*current, packed],
+                    indices,
+                };
+            }
+            Storage::Palette { palette, indices } => {
+                if let Some(palette_index) = palette.iter().position(|&entry| entry == packed) {
+                    indices.set(index, palette_index);
+                } else if palette.len() < indices.capacity() {
+                    palette.push(packed);
+                    indices.set(index, palette.len() - 1);
+                } else {
+                    // The palette is full: rebuild it with the new block, which
+                    // also drops entries that are no longer used
+                    let blocks = (0..CHUNK_VOLUME).map(|i| {
+                        if i == index {
+                            packed
+                        } else {
+                            palette[indices.get(i)]
+                        }
+                    });
+                    self.storage = compact(blocks);
+                }
+            }
+            Storage::Direct(blocks) => blocks[index] = packed,
+        }
+    }
+}
+
+impl Default for ChunkSection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds the smallest storage for a full section of packed blocks
+fn compact(blocks: impl Iterator<Item = u32> + Clone) -> Storage {
+    // Build the palette in order of first appearance
+    let mut palette = Vec::new();
+    let mut lookup = HashMap::new();
+    for packed in blocks.clone() {
+        if lookup.len() > MAX_PALETTE_SIZE {
+            break;
+        }
+        lookup.entry(packed).or_insert_with(|| {
+            palette.push(packed);
+            palette.len() - 1
+        });
+    }
+
+    if palette.len() == 1 {
+        return Storage::Uniform(palette[0]);
+    }
+
+    let bits = PALETTE_BITS.iter().copied().find(|&bits| palette.len() <= 1 << bits);
+    match bits {
+        Some(bits) if palette.len() <= MAX_PALETTE_SIZE => {
+            let mut indices = PackedArray::new(bits);
+            for (index, packed) in blocks.enumerate() {
+                indices.set(index, lookup[&packed]);
+            }
+            Storage::Palette { palette, indices }
+        }
+        _ => Storage::Direct(blocks.collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the bits each block takes in a section's storage
+    fn bits(section: &ChunkSection) -> u32 {
+        match &section.storage {
+            Storage::Uniform(_) => 0,
+            Storage::Palette { indices, .. } => indices.bits,
+            Storage::Direct(_) => 32,
+        }
+    }
+
+    /// Returns one of `distinct` different blocks for a position: air, then stone with
+    /// increasing metadata
+    ///
+    /// Air counts as one of them since a new section's palette starts with it.
+    fn block_at(x: usize, y: usize, z: usize, distinct: usize) -> Block {
+        match chunk_index(x, y, z) % distinct {
+            0 => Block::default(),
+            n => Block::with_metadata(BlockType::Stone, n as u16),
+        }
+    }
+
+    /// Calls a function with every local position in a section
+    fn for_each_position(mut f: impl FnMut(usize, usize, usize)) {
+        for y in 0..16 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    f(x, y, z);
+                }
+            }
+        }
+    }
+
+    /// Fills a section with `distinct` different blocks and reads them back
+    fn round_trip(distinct: usize) -> ChunkSection {
+        let mut section = ChunkSection::new();
+        for_each_position(|x, y, z| section.set_block(x, y, z, block_at(x, y, z, distinct)));
+        for_each_position(|x, y, z| assert_eq!(section.get_block(x, y, z), block_at(x, y, z, distinct)));
+        assert_eq!(section.count_non_air_blocks(), CHUNK_VOLUME - CHUNK_VOLUME.div_ceil(distinct));
+        section
+    }
+
+    #[test]
+    fn storage_grows_with_distinct_blocks() {
+        assert_eq!(bits(&round_trip(1)), 0);
+        assert_eq!(bits(&round_trip(2)), 4);
+        assert_eq!(bits(&round_trip(16)), 4);
+        assert_eq!(bits(&round_trip(17)), 8);
+        assert_eq!(bits(&round_trip(256)), 8);
+        assert_eq!(bits(&round_trip(257)), 12);
+        assert_eq!(bits(&round_trip(MAX_PALETTE_SIZE)), 12);
+        assert_eq!(bits(&round_trip(MAX_PALETTE_SIZE + 1)), 32);
+        assert_eq!(bits(&round_trip(CHUNK_VOLUME)), 32);
+    }
+
+    #[test]
+    fn packed_blocks_round_trip() {
+        for distinct in [1, 2, 16, 256, MAX_PALETTE_SIZE, CHUNK_VOLUME] {
+            let section = round_trip(distinct);
+            let copy = ChunkSection::from_packed(section.to_packed());
+            assert_eq!(bits(&copy), bits(&section));
+            for_each_position(|x, y, z| assert_eq!(copy.get_block(x, y, z), section.get_block(x, y, z)));
         }
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn full_palette_drops_unused_entries() {
+        let mut section = round_trip(256);
+        for_each_position(|x, y, z| section.set_block(x, y, z, block_at(x, y, z, 2)));
+        assert_eq!(bits(&section), 8);
+
+        // The palette is full, so a new block rebuilds it from the blocks still in use
+        section.set_block(0, 0, 0, Block::new(BlockType::Dirt));
+        assert_eq!(bits(&section), 4);
+        assert_eq!(section.get_block(0, 0, 0), Block::new(BlockType::Dirt));
+        assert_eq!(section.get_block(1, 0, 0), block_at(1, 0, 0, 2));
+    }
+
+    #[test]
+    fn compact_shrinks_direct_storage() {
+        let mut section = round_trip(CHUNK_VOLUME);
+        let memory = section.memory_usage();
+        for_each_position(|x, y, z| section.set_block(x, y, z, Block::default()));
+        assert!(section.is_empty());
+
+        let compacted = ChunkSection::from_packed(section.to_packed());
+        assert_eq!(bits(&compacted), 0);
+        assert!(compacted.memory_usage() < memory);
+    }
+}
@@ -0,0 +1,252 @@
+use minecraft_core::block::BlockType;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use serde::{Deserialize, Serialize};
+
+/// How many blocks one unit of climate noise spans (larger = bigger biomes)
+const CLIMATE_SCALE: f64 = 512.0;
+
+/// The biomes of the overworld
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum Biome {
+    Plains = 0,
+    Forest = 1,
+    Desert = 2,
+    Taiga = 3,
+    Ocean = 4,
+    Mountains = 5,
+}
+
+/// The kinds of tree a biome can grow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeSpecies {
+    Oak,
+    Birch,
+    Spruce,
+}
+
+impl TreeSpecies {
+    /// Returns the block used for the trunk
+    pub fn log(&self) -> BlockType {
+        match self {
+            TreeSpecies::Oak => BlockType::OakLog,
+            TreeSpecies::Birch => BlockType::BirchLog,
+            TreeSpecies::Spruce => BlockType::SpruceLog,
+        }
+    }
+
+    /// Returns the block used for the leaves
+    pub fn leaves(&self) -> BlockType {
+        match self {
+            TreeSpecies::Oak => BlockType::OakLeaves,
+            TreeSpecies::Birch => BlockType::BirchLeaves,
+            TreeSpecies::Spruce => BlockType::SpruceLeaves,
+        }
+    }
+}
+
+/// How a biome shapes and decorates its terrain
+#[derive(Debug, Clone)]
+pub struct BiomeProperties {
+    /// The name of the biome
+    pub name: &'static str,
+    /// The top block of each column
+    pub surface: BlockType,
+    /// The blocks just below the surface
+    pub filler: BlockType,
+    /// Blocks above sea level past which the surface is bare stone, if it ever is
+    pub treeline: Option<i32>,
+    /// The chance of a tree growing on each surface block
+    pub tree_density: f64,
+    /// The trees that grow in the biome, picked with equal chance
+    pub trees: &'static [TreeSpecies],
+    /// Blocks added to the sea level to get the average surface height
+    pub height_offset: f64,
+    /// Multiplier for how far the surface strays from its average height
+    pub height_scale: f64,
+}
+
+impl Biome {
+    /// Every biome, in ID order
+    pub const ALL: [Biome; 6] = [
+        Biome::Plains,
+        Biome::Forest,
+        Biome::Desert,
+        Biome::Taiga,
+        Biome::Ocean,
+        Biome::Mountains,
+    ];
+
+    /// Returns the ID stored in chunks for this biome
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Returns the biome with the specified ID, if there is one
+    pub fn from_id(id: u8) -> Option<Biome> {
+        Self::ALL.get(id as usize).copied()
+    }
+
+    /// Returns the properties of this biome
+    pub fn properties(&self) -> BiomeProperties {
+        match self {
+            Biome::Plains => BiomeProperties {
+                name: "Plains",
+                surface: BlockType::Grass,
+                filler: BlockType::Dirt,
+                treeline: None,
+                tree_density: 0.002,
+                trees: &[TreeSpecies::Oak],
+                height_offset: 6.0,
+                height_scale: 0.25,
+            },
+            Biome::Forest => BiomeProperties {
+                name: "Forest",
+                surface: BlockType::Grass,
+                filler: BlockType::Dirt,
+                treeline: None,
+                tree_density: 0.05,
+                trees: &[TreeSpecies::Oak, TreeSpecies::Oak, TreeSpecies::Birch],
+                height_offset: 8.0,
+                height_scale: 0.4,
+            },
+            Biome::Desert => BiomeProperties {
+                name: "Desert",
+                surface: BlockType::Sand,
+                filler: BlockType::Sand,
+                treeline: None,
+                tree_density: 0.0,
+                trees: &[],
+                height_offset: 5.0,
+                height_scale: 0.2,
+            },
+            Biome::Taiga => BiomeProperties {
+                name: "Taiga",
+                surface: BlockType::Grass,
+                filler: BlockType::Dirt,
+                treeline: None,
+                tree_density: 0.04,
+                trees: &[TreeSpecies::Spruce],
+                height_offset: 10.0,
+                height_scale: 0.5,
+            },
+            Biome::Ocean => BiomeProperties {
+                name: "Ocean",
+                surface: BlockType::Sand,
+                filler: BlockType::Gravel,
+                treeline: None,
+                tree_density: 0.0,
+                trees: &[],
+                height_offset: -18.0,
+                height_scale: 0.3,
+            },
+            Biome::Mountains => BiomeProperties {
+                name: "Mountains",
+                surface: BlockType::Grass,
+                filler: BlockType::Dirt,
+                treeline: Some(40),
+                tree_density: 0.008,
+                trees: &[TreeSpecies::Spruce],
+                height_offset: 30.0,
+                height_scale: 1.5,
+            },
+        }
+    }
+}
+
+/// Chooses biomes from temperature and humidity noise maps
+pub struct BiomeSource {
+    /// The temperature noise
+    temperature: Fbm<Perlin>,
+    /// The humidity noise
+    humidity: Fbm<Perlin>,
+}
+
+impl BiomeSource {
+    /// Creates a biome source for the specified world seed
+    pub fn new(seed: i64) -> Self {
+        // Offset the seeds so the climate maps don't match the terrain noise
+        let climate_noise = |offset: u32| {
+            Fbm::<Perlin>::new((seed as u32).wrapping_add(offset))
+                .set_octaves(3)
+                .set_frequency(1.0)
+                .set_persistence(0.5)
+        };
+
+        Self {
+            temperature: climate_noise(1),
+            humidity: climate_noise(2),
+        }
+    }
+
+    /// Returns the temperature and humidity (roughly -1 to 1) at a world column
+    pub fn climate(&self, x: i32, z: i32) -> (f64, f64) {
+        let point = [x as f64 / CLIMATE_SCALE, z as f64 / CLIMATE_SCALE];
+        (self.temperature.get(point), self.humidity.get(point))
+    }
+
+    /// Returns the biome at a world column
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        let (temperature, humidity) = self.climate(x, z);
+        Self::classify(temperature, humidity)
+    }
+
+    /// Picks the biome for a climate
+    fn classify(temperature: f64, humidity: f64) -> Biome {
+        if humidity > 0.4 {
+            Biome::Ocean
+        } else if temperature > 0.3 && humidity < 0.0 {
+            Biome::Desert
+        } else if temperature < -0.3 {
+            Biome::Taiga
+        } else if temperature < 0.0 && humidity < -0.2 {
+            Biome::Mountains
+        } else if humidity > 0.1 {
+            Biome::Forest
+        } else {
+            Biome::Plains
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Returns the biomes of a coarse grid of columns around the origin
+    fn sample(source: &BiomeSource) -> Vec<Biome> {
+        (-32..32)
+            .flat_map(|x| (-32..32).map(move |z| (x * 128, z * 128)))
+            .map(|(x, z)| source.biome_at(x, z))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_same_biomes() {
+        assert_eq!(sample(&BiomeSource::new(42)), sample(&BiomeSource::new(42)));
+        assert_ne!(sample(&BiomeSource::new(42)), sample(&BiomeSource::new(43)));
+    }
+
+    #[test]
+    fn every_biome_is_reachable() {
+        let steps = 40;
+        let climate = |step: i32| step as f64 / steps as f64 * 2.0 - 1.0;
+        let reached: HashSet<Biome> = (0..=steps)
+            .flat_map(|t| (0..=steps).map(move |h| BiomeSource::classify(climate(t), climate(h))))
+            .collect();
+        assert_eq!(reached, Biome::ALL.into_iter().collect());
+
+        // And the climate noise reaches every biome in a world
+        let sampled: HashSet<Biome> = sample(&BiomeSource::new(42)).into_iter().collect();
+        assert_eq!(sampled, Biome::ALL.into_iter().collect());
+    }
+
+    #[test]
+    fn ids_round_trip() {
+        for biome in Biome::ALL {
+            assert_eq!(Biome::from_id(biome.id()), Some(biome));
+        }
+        assert_eq!(Biome::from_id(Biome::ALL.len() as u8), None);
+    }
+}
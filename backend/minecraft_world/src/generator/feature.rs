@@ -0,0 +1,203 @@
+use crate::chunk::Chunk;
+use crate::generator::biome::TreeSpecies;
+use minecraft_core::block::{Block, BlockType};
+use minecraft_core::math::BlockVector;
+use rand::Rng;
+use rand::rngs::StdRng;
+
+/// How far (blocks) a tree's leaves reach from its trunk
+pub const TREE_RADIUS: i32 = 2;
+
+/// How far (blocks) the top of a tree's leaves can be above the ground it grows on
+pub const MAX_TREE_HEIGHT: i32 = 7;
+
+/// A decoration such as a tree, described in world coordinates
+///
+/// A feature may cross chunk borders. It is built the same way whichever chunk
+/// asks for it, and each chunk places only the blocks that fall inside it, so
+/// the parts line up no matter which chunk is generated first.
+#[derive(Debug, Clone, Default)]
+pub struct Feature {
+    /// The blocks of the feature, in placement order
+    blocks: Vec<(BlockVector, BlockType)>,
+}
+
+impl Feature {
+    /// Builds a tree whose trunk starts at the specified position
+    pub fn tree(species: TreeSpecies, base: BlockVector, rng: &mut StdRng) -> Self {
+        let mut feature = Self::default();
+
+        // Tree trunk (4-6 blocks tall)
+        let tree_height = rng.gen_range(4..=6);
+        for i in 0..tree_height {
+            feature.push(BlockVector::new(base.x, base.y + i, base.z), species.log());
+        }
+
+        // Tree leaves
+        let leaf_start = base.y + tree_height - 3;
+        let leaf_end = base.y + tree_height + 1;
+        for leaf_y in leaf_start..leaf_end {
+            let radius = if leaf_y == leaf_end - 1 { 1 } else { TREE_RADIUS };
+
+            for dx in -radius..=radius {
+                for dz in -radius..=radius {
+                    // Skip corners for a more natural shape
+                    if dx.abs() == radius && dz.abs() == radius {
+                        continue;
+                    }
+
+                    // Random chance to skip leaf placement for irregular shape
+                    if rng.gen_bool(0.9) {
+                        feature.push(BlockVector::new(base.x + dx, leaf_y, base.z + dz), species.leaves());
+                    }
+                }
+            }
+        }
+
+        feature
+    }
+
+    /// Adds a block to the feature
+    fn push(&mut self, position: BlockVector, block_type: BlockType) {
+        self.blocks.push((position, block_type));
+    }
+
+    /// Places the blocks of the feature that fall inside a chunk
+    ///
+    /// Features only grow into air, except that solid blocks such as logs may
+    /// also replace the leaves of another feature.
+    pub fn place(&self, chunk: &Chunk) {
+        for &(position, block_type) in &self.blocks {
+            if position.y < 0 || position.to_chunk() != chunk.position {
+                continue;
+            }
+
+            let (x, y, z) = (
+                position.x.rem_euclid(16) as usize,
+                position.y.rem_euclid(16) as usize,
+                position.z.rem_euclid(16) as usize,
+            );
+            let existing = chunk.get_block(x, y, z).and_then(|block| block.block_type()).unwrap_or(BlockType::Air);
+            if existing == BlockType::Air || (is_leaves(existing) && !is_leaves(block_type)) {
+                chunk.set_block(x, y, z, Block::new(block_type));
+            }
+        }
+    }
+}
+
+/// Checks whether a block is a kind of leaves
+fn is_leaves(block_type: BlockType) -> bool {
+    matches!(block_type, BlockType::OakLeaves | BlockType::BirchLeaves | BlockType::SpruceLeaves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::provider::{ChunkProvider, MemoryChunkProvider};
+    use crate::generator::biome::{Biome, BiomeSource};
+    use crate::generator::overworld::OverworldGenerator;
+    use crate::generator::TerrainGenerator;
+    use minecraft_core::math::ChunkVector;
+    use rand::SeedableRng;
+    use std::sync::Arc;
+
+    /// The seed of the generated worlds in these tests
+    const SEED: i64 = 42;
+
+    /// Returns every block of a chunk
+    fn blocks(chunk: &Chunk) -> Vec<Option<Block>> {
+        (0..16)
+            .flat_map(|x| (0..16).flat_map(move |y| (0..16).map(move |z| (x, y, z))))
+            .map(|(x, y, z)| chunk.get_block(x, y, z))
+            .collect()
+    }
+
+    /// Generates chunks in order with a fresh provider and returns their blocks
+    async fn generate_in_order(positions: &[ChunkVector]) -> Vec<(ChunkVector, Vec<Option<Block>>)> {
+        let generator = Arc::new(OverworldGenerator::new("test", SEED));
+        let provider = MemoryChunkProvider::new(generator).unwrap();
+        let mut generated = Vec::new();
+        for position in positions {
+            generated.push((*position, blocks(&provider.get_chunk(*position).await.unwrap())));
+        }
+        generated.sort_by_key(|(position, _)| position.to_key());
+        generated
+    }
+
+    /// Checks whether a chunk has a log at a local position
+    fn is_log(chunk: &Chunk, x: usize, y: usize, z: usize) -> bool {
+        let block_type = chunk.get_block(x, y, z).and_then(|block| block.block_type());
+        block_type.is_some_and(|block_type| matches!(block_type, BlockType::OakLog | BlockType::BirchLog))
+    }
+
+    #[test]
+    fn trees_split_across_chunks_without_losing_blocks() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let tree = Feature::tree(TreeSpecies::Oak, BlockVector::new(15, 4, 15), &mut rng);
+
+        // The tree reaches into the four chunks around the corner it stands by
+        let chunks: Vec<Chunk> = [(0, 0), (1, 0), (0, 1), (1, 1)]
+            .into_iter()
+            .map(|(x, z)| Chunk::new(ChunkVector::new(x, 0, z)))
+            .collect();
+        for chunk in &chunks {
+            tree.place(chunk);
+        }
+
+        // Leaves the trunk grows through give way to it
+        for (position, block_type) in &tree.blocks {
+            let chunk = chunks.iter().find(|chunk| chunk.position == position.to_chunk()).unwrap();
+            let placed = chunk.get_block_at(position).and_then(|block| block.block_type());
+            let expected = if is_leaves(*block_type) { [*block_type, BlockType::OakLog] } else { [*block_type; 2] };
+            assert!(placed.is_some_and(|placed| expected.contains(&placed)), "missing at {:?}", position);
+        }
+        let placed: usize = chunks.iter().map(Chunk::count_non_air_blocks).sum();
+        let distinct: std::collections::HashSet<_> = tree.blocks.iter().map(|(position, _)| *position).collect();
+        assert_eq!(placed, distinct.len());
+    }
+
+    #[tokio::test]
+    async fn border_trees_match_whichever_neighbour_generates_first() {
+        // Find a forest tree standing on the east border of a chunk
+        let generator = OverworldGenerator::new("test", SEED);
+        let biomes = BiomeSource::new(SEED);
+        let mut found = None;
+        'search: for chunk_x in 0..64 {
+            if biomes.biome_at(chunk_x * 16 + 15, 8) != Biome::Forest {
+                continue;
+            }
+            for chunk_y in 3..7 {
+                let chunk = generator.generate_chunk(ChunkVector::new(chunk_x, chunk_y, 0)).await.unwrap();
+                for y in 0..16 {
+                    for z in 0..16 {
+                        if is_log(&chunk, 15, y, z) {
+                            found = Some(ChunkVector::new(chunk_x, chunk_y, 0));
+                            break 'search;
+                        }
+                    }
+                }
+            }
+        }
+        let west = found.expect("no tree on a chunk border found");
+        let east = ChunkVector::new(west.x + 1, west.y, west.z);
+        let above = |position: ChunkVector| ChunkVector::new(position.x, position.y + 1, position.z);
+        let area = [west, east, above(west), above(east)];
+
+        // The leaves reach over the border
+        let east_chunk = generator.generate_chunk(east).await.unwrap();
+        let east_above = generator.generate_chunk(above(east)).await.unwrap();
+        let has_leaves = |chunk: &Chunk| {
+            (0..16).flat_map(|y| (0..16).map(move |z| (y, z))).any(|(y, z)| {
+                let block = chunk.get_block(0, y, z).unwrap_or_default();
+                block.block_type().is_some_and(is_leaves)
+            })
+        };
+        assert!(has_leaves(&east_chunk) || has_leaves(&east_above));
+
+        let forwards = generate_in_order(&area).await;
+        let mut reversed_area = area;
+        reversed_area.reverse();
+        let backwards = generate_in_order(&reversed_area).await;
+        assert!(forwards == backwards, "chunks differ with generation order");
+    }
+}
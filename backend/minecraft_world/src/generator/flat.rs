@@ -41,7 +41,7 @@ impl FlatWorldGenerator {
 impl TerrainGenerator for FlatWorldGenerator {
     async fn generate_chunk(&self, position: ChunkVector) -> Result<Chunk> {
         // Create a new chunk
-        let mut chunk = Chunk::new(position);
+        let chunk = Chunk::new(position);
         
         // Only generate terrain for chunks at y=0
         if position.y != 0 {
@@ -53,7 +53,7 @@ impl TerrainGenerator for FlatWorldGenerator {
         let mut section = ChunkSection::new();
         
         // Calculate total height
-        let total_height: u32 = self.layers.iter().map(|(_, thickness)| thickness).sum();
+        let _total_height: u32 = self.layers.iter().map(|(_, thickness)| thickness).sum();
         
         // Fill the chunk with the layers
         let mut current_height = 0;
@@ -1,64 +1,67 @@
-use noise::{NoiseFn, Perlin, Fbm, Seedable, MultiFractal};
-use rand::{Rng, SeedableRng};
+use noise::{NoiseFn, Perlin, Fbm, MultiFractal};
+use minecraft_core::math::ChunkVector;
+use rand::SeedableRng;
 use rand::rngs::StdRng;
 
 /// Utility functions for generating noise
+///
+/// Noise is sampled by world position and random numbers come from
+/// `chunk_rng`, so the generator holds no mutable state and can be shared
+/// between threads without locking.
 pub struct NoiseGenerator {
+    /// The world seed
+    seed: i64,
     /// The Perlin noise generator
     perlin: Perlin,
     /// The fractal Brownian motion noise generator
-    fbm: Fbm,
-    /// The random number generator
-    rng: StdRng,
+    fbm: Fbm<Perlin>,
 }
 
 impl NoiseGenerator {
     /// Creates a new noise generator with the specified seed
     pub fn new(seed: i64) -> Self {
         // Use the seed to initialize our noise functions
-        let perlin = Perlin::new().set_seed(seed as u32);
+        let perlin = Perlin::new(seed as u32);
         
-        let mut fbm = Fbm::new();
-        fbm.set_seed(seed as u32);
-        fbm.set_octaves(6);
-        fbm.set_frequency(1.0);
-        fbm.set_lacunarity(2.0);
-        fbm.set_persistence(0.5);
-        
-        // Create a random number generator from the seed
-        let rng_seed = u64::from_ne_bytes([
-            (seed & 0xff) as u8,
-            ((seed >> 8) & 0xff) as u8,
-            ((seed >> 16) & 0xff) as u8,
-            ((seed >> 24) & 0xff) as u8,
-            ((seed >> 32) & 0xff) as u8,
-            ((seed >> 40) & 0xff) as u8,
-            ((seed >> 48) & 0xff) as u8,
-            ((seed >> 56) & 0xff) as u8,
-        ]);
-        
-        let rng = StdRng::seed_from_u64(rng_seed);
+        let fbm = Fbm::<Perlin>::new(seed as u32)
+            .set_octaves(6)
+            .set_frequency(1.0)
+            .set_lacunarity(2.0)
+            .set_persistence(0.5);
         
         Self {
+            seed,
             perlin,
             fbm,
-            rng,
         }
     }
     
-    /// Gets a random integer in the specified range
-    pub fn random_int(&mut self, min: i32, max: i32) -> i32 {
-        self.rng.gen_range(min..=max)
-    }
-    
-    /// Gets a random float in the specified range
-    pub fn random_float(&mut self, min: f64, max: f64) -> f64 {
-        self.rng.gen_range(min..=max)
+    /// Creates a random number generator for one feature of one chunk
+    ///
+    /// The generator is seeded from the world seed, the chunk position and a
+    /// salt naming the feature, so the same chunk always gets the same numbers
+    /// no matter which chunks were generated before it, and different features
+    /// of a chunk don't share a sequence.
+    pub fn chunk_rng(&self, position: ChunkVector, salt: u64) -> StdRng {
+        let mut hash = mix(self.seed as u64 ^ salt);
+        for coordinate in [position.x, position.y, position.z] {
+            hash = mix(hash ^ coordinate as i64 as u64);
+        }
+        
+        StdRng::seed_from_u64(hash)
     }
     
-    /// Gets a random bool with the specified probability of being true
-    pub fn random_bool(&mut self, probability: f64) -> bool {
-        self.rng.gen_bool(probability)
+    /// Creates a random number generator for one feature of one world column
+    ///
+    /// Decorations that cross chunk borders are seeded per column instead of
+    /// per chunk, so every chunk they reach into can rebuild them exactly.
+    pub fn column_rng(&self, x: i32, z: i32, salt: u64) -> StdRng {
+        let mut hash = mix(self.seed as u64 ^ salt);
+        for coordinate in [x, z] {
+            hash = mix(hash ^ coordinate as i64 as u64);
+        }
+        
+        StdRng::seed_from_u64(hash)
     }
     
     /// Gets a 2D Perlin noise value at the specified coordinates
@@ -129,4 +132,12 @@ impl NoiseGenerator {
         
         noise_array
     }
-} 
\ No newline at end of file
+}
+
+/// Scrambles a 64-bit value (the SplitMix64 finalizer)
+fn mix(value: u64) -> u64 {
+    let mut value = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
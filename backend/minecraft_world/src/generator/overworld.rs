@@ -1,19 +1,53 @@
-use crate::chunk::{Chunk, section::ChunkSection};
+use crate::chunk::Chunk;
 use crate::generator::{TerrainGenerator, noise::NoiseGenerator};
+use crate::generator::biome::{Biome, BiomeSource};
+use crate::generator::feature::{Feature, MAX_TREE_HEIGHT, TREE_RADIUS};
 use minecraft_core::block::{Block, BlockType};
-use minecraft_core::math::ChunkVector;
+use minecraft_core::math::{BlockVector, ChunkVector};
 use anyhow::Result;
 use async_trait::async_trait;
-use std::sync::{Arc, Mutex};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+/// Salt for the random numbers deciding where bedrock is placed
+const BEDROCK_SALT: u64 = 0x6265_6472_6f63_6b00;
+
+/// Salt for the random numbers choosing ore types
+const ORE_SALT: u64 = 0x6f72_6500_0000_0000;
+
+/// Salt for the random numbers placing and shaping trees
+const TREE_SALT: u64 = 0x7472_6565_0000_0000;
+
+/// The spacing (blocks) of the grid biome height modifiers are sampled on
+const BLEND_SPACING: i32 = 4;
+
+/// How many grid cells around a column are averaged to blend biome heights
+const BLEND_RADIUS: i32 = 2;
+
+/// How far (blocks) the surface strays from its average height at a height scale of 1
+const HEIGHT_AMPLITUDE: f64 = 30.0;
+
+/// How many blocks of filler lie under the surface block
+const FILLER_DEPTH: i32 = 3;
 
 /// A realistic terrain generator for the overworld
+///
+/// Every random decision is derived from the seed, the chunk or column
+/// position and a per-feature salt, so a chunk is identical however many
+/// chunks are generated before it or alongside it.
+///
+/// Trees are decorated without looking at neighbouring chunks: each chunk
+/// works out every tree rooted within reach of its borders and places the
+/// part of it that falls inside, so trees span chunk borders seamlessly.
 pub struct OverworldGenerator {
     /// The name of this generator
     name: String,
     /// The seed for terrain generation
     seed: i64,
     /// The noise generator
-    noise: Arc<Mutex<NoiseGenerator>>,
+    noise: NoiseGenerator,
+    /// Chooses the biome of each column
+    biomes: BiomeSource,
     /// Sea level (blocks above this are air by default)
     sea_level: i32,
     /// World scale factor (higher = more varied terrain)
@@ -26,7 +60,8 @@ impl OverworldGenerator {
         Self {
             name: name.to_string(),
             seed,
-            noise: Arc::new(Mutex::new(NoiseGenerator::new(seed))),
+            noise: NoiseGenerator::new(seed),
+            biomes: BiomeSource::new(seed),
             sea_level: 64,
             scale: 1.0,
         }
@@ -51,50 +86,43 @@ impl TerrainGenerator for OverworldGenerator {
         // Create a new chunk
         let mut chunk = Chunk::new(position);
         
-        // Generate heightmap for this chunk
-        let heightmap = {
-            let noise = self.noise.lock().unwrap();
-            noise.generate_heightmap(position.x, position.z, self.scale)
-        };
+        // Generate the biomes and heightmap for this chunk and the border trees can reach in from
+        let base_x = position.x * 16;
+        let base_z = position.z * 16;
+        let columns = self.generate_columns(base_x - TREE_RADIUS, base_z - TREE_RADIUS, 16 + 2 * TREE_RADIUS as usize);
+        for x in 0..16 {
+            for z in 0..16 {
+                let (biome, _) = columns.get(base_x + x as i32, base_z + z as i32);
+                chunk.set_biome(x, z, biome);
+            }
+        }
         
-        // Calculate the range of sections that need to be filled in this chunk
-        let min_height = *heightmap.iter()
-            .flat_map(|row| row.iter())
-            .min()
-            .unwrap_or(&0);
-            
-        let max_height = *heightmap.iter()
-            .flat_map(|row| row.iter())
-            .max()
-            .unwrap_or(&0);
+        // Calculate the highest section that needs to be filled in this chunk
+        let max_height = columns.heights.iter().copied().max().unwrap_or(0);
+        let min_height = columns.heights.iter().copied().min().unwrap_or(0);
             
-        let min_section = (min_height / 16).max(0);
-        let max_section = (max_height / 16 + 1).min(15);
+        let max_section = (max_height.max(self.sea_level) / 16 + 1).min(15);
         
-        // Skip if this chunk is outside the height range
-        if position.y < min_section || position.y > max_section {
+        // Skip if this chunk is outside the world or above the terrain
+        if position.y < 0 || position.y > max_section {
             return Ok(chunk);
         }
         
         // Generate 3D noise for caves and other features
-        let cave_noise = {
-            let noise = self.noise.lock().unwrap();
-            noise.generate_3d_noise(position.x, position.y, position.z, self.scale)
-        };
+        let cave_noise = self.noise.generate_3d_noise(position.x, position.y, position.z, self.scale);
+        
+        // Each feature draws from its own sequence for this chunk
+        let mut bedrock_rng = self.noise.chunk_rng(position, BEDROCK_SALT);
+        let mut ore_rng = self.noise.chunk_rng(position, ORE_SALT);
         
         // Get the y-offset within the chunk
         let chunk_y_offset = position.y * 16;
         
-        // Create a section for this chunk
-        let section_y = position.y;
-        if !chunk.has_section(section_y) {
-            chunk.set_section(section_y, ChunkSection::new());
-        }
-        
         // Generate the terrain in this chunk section
         for x in 0..16 {
             for z in 0..16 {
-                let height = heightmap[x][z];
+                let (biome, height) = columns.get(base_x + x as i32, base_z + z as i32);
+                let (surface, filler) = self.surface_blocks(biome, height);
                 
                 for y in 0..16 {
                     let world_y = chunk_y_offset + y;
@@ -111,24 +139,15 @@ impl TerrainGenerator for OverworldGenerator {
                         }
                     } else if world_y == height {
                         // Surface block
-                        if world_y <= self.sea_level - 2 {
-                            // Underwater, use sand
-                            BlockType::Sand
-                        } else if world_y <= self.sea_level + 2 {
-                            // Beach
-                            BlockType::Sand
-                        } else {
-                            // Regular surface, use grass
-                            BlockType::Grass
-                        }
-                    } else if world_y >= height - 3 {
-                        // Just below surface, use dirt
-                        BlockType::Dirt
+                        surface
+                    } else if world_y >= height - FILLER_DEPTH {
+                        // Just below surface
+                        filler
                     } else if world_y <= 20 {
                         // Deep underground
                         if world_y <= 5 {
                             // Bedrock layer
-                            let noise_value = self.noise.lock().unwrap().random_float(0.0, 1.0);
+                            let noise_value: f64 = bedrock_rng.gen();
                             if world_y == 0 || (world_y <= 4 && noise_value < 0.5) {
                                 BlockType::Bedrock
                             } else {
@@ -138,22 +157,18 @@ impl TerrainGenerator for OverworldGenerator {
                             // Stone with occasional ores
                             let noise_value = cave_noise[x][y as usize][z];
                             if noise_value > 0.7 {
-                                let ore_type = {
-                                    let mut noise = self.noise.lock().unwrap();
-                                    let random = noise.random_float(0.0, 1.0);
-                                    if random < 0.4 {
-                                        BlockType::CoalOre
-                                    } else if random < 0.7 {
-                                        BlockType::IronOre
-                                    } else if random < 0.85 {
-                                        BlockType::GoldOre
-                                    } else if random < 0.95 {
-                                        BlockType::RedstoneOre
-                                    } else {
-                                        BlockType::DiamondOre
-                                    }
-                                };
-                                ore_type
+                                let random: f64 = ore_rng.gen();
+                                if random < 0.4 {
+                                    BlockType::CoalOre
+                                } else if random < 0.7 {
+                                    BlockType::IronOre
+                                } else if random < 0.85 {
+                                    BlockType::GoldOre
+                                } else if random < 0.95 {
+                                    BlockType::RedstoneOre
+                                } else {
+                                    BlockType::DiamondOre
+                                }
                             } else {
                                 BlockType::Stone
                             }
@@ -163,8 +178,8 @@ impl TerrainGenerator for OverworldGenerator {
                         BlockType::Stone
                     };
                     
-                    // Check for caves (if not bedrock)
-                    let place_block = if block_type != BlockType::Bedrock && block_type != BlockType::Air {
+                    // Check for caves (if not bedrock, and not flooding the sea with air)
+                    let place_block = if !matches!(block_type, BlockType::Bedrock | BlockType::Air | BlockType::Water) {
                         let noise_value = cave_noise[x][y as usize][z];
                         noise_value > -0.3 // Cave threshold
                     } else {
@@ -179,22 +194,9 @@ impl TerrainGenerator for OverworldGenerator {
             }
         }
         
-        // Plant some trees on grass blocks
-        if position.y == 0 {
-            let mut noise = self.noise.lock().unwrap();
-            for x in 2..14 {
-                for z in 2..14 {
-                    let height = heightmap[x][z];
-                    
-                    // Only place trees on grass
-                    if height > self.sea_level + 2 && height < 100 {
-                        // Random chance for a tree
-                        if noise.random_bool(0.02) {
-                            self.generate_tree(&mut chunk, x, height as usize + 1, z);
-                        }
-                    }
-                }
-            }
+        // Plant trees if any could reach into this chunk
+        if chunk_y_offset + 15 > min_height && chunk_y_offset <= max_height + MAX_TREE_HEIGHT {
+            self.plant_trees(&chunk, &columns);
         }
         
         Ok(chunk)
@@ -210,54 +212,194 @@ impl TerrainGenerator for OverworldGenerator {
 }
 
 impl OverworldGenerator {
-    /// Generates a tree at the specified position
-    fn generate_tree(&self, chunk: &mut Chunk, x: usize, y: usize, z: usize) {
-        // Tree trunk (3-5 blocks tall)
-        let tree_height = {
-            let mut noise = self.noise.lock().unwrap();
-            noise.random_int(4, 6) as usize
-        };
-        
-        for i in 0..tree_height {
-            if y + i >= 256 {
-                break;
+    /// Picks the biome and surface height of each column in a square area
+    ///
+    /// Biome height modifiers are averaged over the surrounding columns, so
+    /// the terrain slopes smoothly from one biome into the next.
+    fn generate_columns(&self, min_x: i32, min_z: i32, size: usize) -> Columns {
+        // Sample the height modifiers on a coarse grid covering the area and its surroundings
+        let grid_min_x = min_x.div_euclid(BLEND_SPACING) - BLEND_RADIUS;
+        let grid_min_z = min_z.div_euclid(BLEND_SPACING) - BLEND_RADIUS;
+        let grid_size = ((min_x + size as i32 - 1).div_euclid(BLEND_SPACING) + BLEND_RADIUS - grid_min_x + 1)
+            .max((min_z + size as i32 - 1).div_euclid(BLEND_SPACING) + BLEND_RADIUS - grid_min_z + 1) as usize;
+        let mut grid = vec![(0.0, 0.0); grid_size * grid_size];
+        for grid_x in 0..grid_size {
+            for grid_z in 0..grid_size {
+                let properties = self.biomes
+                    .biome_at(
+                        (grid_min_x + grid_x as i32) * BLEND_SPACING,
+                        (grid_min_z + grid_z as i32) * BLEND_SPACING,
+                    )
+                    .properties();
+                grid[grid_x * grid_size + grid_z] = (properties.height_offset, properties.height_scale);
             }
-            
-            chunk.set_block(x, y + i, z, Block::new(BlockType::OakLog));
         }
         
-        // Tree leaves
-        let leaf_radius = 2;
-        let leaf_start = y + tree_height - 3;
-        let leaf_end = y + tree_height + 1;
+        let mut columns = Columns {
+            min_x,
+            min_z,
+            size,
+            biomes: Vec::with_capacity(size * size),
+            heights: Vec::with_capacity(size * size),
+        };
+        for x in 0..size {
+            for z in 0..size {
+                let world_x = min_x + x as i32;
+                let world_z = min_z + z as i32;
+                
+                // Average the modifiers of the grid cells around this column
+                let cell_x = (world_x.div_euclid(BLEND_SPACING) - grid_min_x) as usize;
+                let cell_z = (world_z.div_euclid(BLEND_SPACING) - grid_min_z) as usize;
+                let radius = BLEND_RADIUS as usize;
+                let (mut offset, mut scale, mut samples) = (0.0, 0.0, 0.0);
+                for grid_x in (cell_x - radius)..=(cell_x + radius) {
+                    for grid_z in (cell_z - radius)..=(cell_z + radius) {
+                        let (cell_offset, cell_scale) = grid[grid_x * grid_size + grid_z];
+                        offset += cell_offset;
+                        scale += cell_scale;
+                        samples += 1.0;
+                    }
+                }
+                
+                // Shape the base terrain noise with the blended modifiers
+                let noise = self.noise.fbm_2d(world_x as f64 * self.scale * 0.01, world_z as f64 * self.scale * 0.01);
+                let height = self.sea_level as f64 + offset / samples + noise * HEIGHT_AMPLITUDE * scale / samples;
+                columns.biomes.push(self.biomes.biome_at(world_x, world_z));
+                columns.heights.push((height as i32).clamp(1, 250));
+            }
+        }
         
-        for leaf_y in leaf_start..leaf_end {
-            if leaf_y >= 256 {
-                break;
+        columns
+    }
+    
+    /// Returns the surface and filler blocks for a column
+    ///
+    /// Grassy biomes turn to sand along the shore and under water, and
+    /// biomes with a treeline turn to bare stone above it.
+    fn surface_blocks(&self, biome: Biome, height: i32) -> (BlockType, BlockType) {
+        let properties = biome.properties();
+        if properties.treeline.is_some_and(|treeline| height > self.sea_level + treeline) {
+            (BlockType::Stone, BlockType::Stone)
+        } else if properties.surface == BlockType::Grass && height <= self.sea_level + 1 {
+            (BlockType::Sand, BlockType::Sand)
+        } else {
+            (properties.surface, properties.filler)
+        }
+    }
+    
+    /// Places the parts of every tree rooted in an area of columns that fall inside a chunk
+    ///
+    /// Whether a column grows a tree, and its shape, depend only on the seed and
+    /// the column, so neighbouring chunks agree on every tree they share.
+    fn plant_trees(&self, chunk: &Chunk, columns: &Columns) {
+        for x in 0..columns.size as i32 {
+            for z in 0..columns.size as i32 {
+                let world_x = columns.min_x + x;
+                let world_z = columns.min_z + z;
+                let (biome, height) = columns.get(world_x, world_z);
+                let properties = biome.properties();
+                
+                // Only place trees on grass
+                let (surface, _) = self.surface_blocks(biome, height);
+                if surface != BlockType::Grass || height >= 250 || properties.tree_density <= 0.0 {
+                    continue;
+                }
+                
+                // Random chance for a tree, of one of the biome's species
+                let mut tree_rng = self.noise.column_rng(world_x, world_z, TREE_SALT);
+                if tree_rng.gen_bool(properties.tree_density) {
+                    if let Some(species) = properties.trees.choose(&mut tree_rng) {
+                        let base = BlockVector::new(world_x, height + 1, world_z);
+                        Feature::tree(*species, base, &mut tree_rng).place(chunk);
+                    }
+                }
             }
-            
-            let radius = if leaf_y == leaf_end - 1 { 1 } else { leaf_radius };
-            
-            for dx in -radius..=radius {
-                for dz in -radius..=radius {
-                    // Skip corners for a more natural shape
-                    if dx.abs() == radius && dz.abs() == radius {
-                        continue;
+        }
+    }
+}
+
+/// The biomes and surface heights of a square area of world columns
+struct Columns {
+    /// The lowest world x coordinate in the area
+    min_x: i32,
+    /// The lowest world z coordinate in the area
+    min_z: i32,
+    /// The width of the area in columns
+    size: usize,
+    /// The biome of each column, indexed by x * size + z
+    biomes: Vec<Biome>,
+    /// The surface height of each column, indexed by x * size + z
+    heights: Vec<i32>,
+}
+
+impl Columns {
+    /// Returns the biome and surface height of a world column in the area
+    fn get(&self, x: i32, z: i32) -> (Biome, i32) {
+        let index = (x - self.min_x) as usize * self.size + (z - self.min_z) as usize;
+        (self.biomes[index], self.heights[index])
+    }
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::collections::hash_map::Entry;
+
+    #[test]
+    fn surface_blocks_depend_on_biome() {
+        let generator = OverworldGenerator::new("test", 1).with_sea_level(64);
+        assert_eq!(generator.surface_blocks(Biome::Plains, 80), (BlockType::Grass, BlockType::Dirt));
+        assert_eq!(generator.surface_blocks(Biome::Desert, 80), (BlockType::Sand, BlockType::Sand));
+        assert_eq!(generator.surface_blocks(Biome::Ocean, 40), (BlockType::Sand, BlockType::Gravel));
+
+        // Grass turns to sand along the shore
+        assert_eq!(generator.surface_blocks(Biome::Forest, 65), (BlockType::Sand, BlockType::Sand));
+        assert_eq!(generator.surface_blocks(Biome::Forest, 66), (BlockType::Grass, BlockType::Dirt));
+
+        // Mountains are bare above their treeline
+        assert_eq!(generator.surface_blocks(Biome::Mountains, 104), (BlockType::Grass, BlockType::Dirt));
+        assert_eq!(generator.surface_blocks(Biome::Mountains, 105), (BlockType::Stone, BlockType::Stone));
+    }
+
+    #[tokio::test]
+    async fn generated_surfaces_match_their_biomes() {
+        let generator = OverworldGenerator::new("test", 42);
+        let source = BiomeSource::new(42);
+
+        for biome in Biome::ALL {
+            // Find a chunk in the middle of the biome
+            let (chunk_x, chunk_z) = (-64..64)
+                .flat_map(|x| (-64..64).map(move |z| (x * 4, z * 4)))
+                .find(|(x, z)| {
+                    (0..16).all(|dx| (0..16).all(|dz| source.biome_at(x * 16 + dx, z * 16 + dz) == biome))
+                })
+                .unwrap_or_else(|| panic!("no chunk of {:?} found", biome));
+            let columns = generator.generate_columns(chunk_x * 16, chunk_z * 16, 16);
+
+            // Every surface block that caves and trees left in place is the biome's
+            let mut chunks = HashMap::new();
+            let mut checked = 0;
+            for x in 0..16 {
+                for z in 0..16 {
+                    let (column_biome, height) = columns.get(chunk_x * 16 + x, chunk_z * 16 + z);
+                    assert_eq!(column_biome, biome);
+                    let chunk_y = height / 16;
+                    if let Entry::Vacant(entry) = chunks.entry(chunk_y) {
+                        let position = ChunkVector::new(chunk_x, chunk_y, chunk_z);
+                        entry.insert(generator.generate_chunk(position).await.unwrap());
                     }
-                    
-                    let leaf_x = x as i32 + dx;
-                    let leaf_z = z as i32 + dz;
-                    
-                    // Make sure the leaf position is valid
-                    if leaf_x >= 0 && leaf_x < 16 && leaf_z >= 0 && leaf_z < 16 {
-                        // Random chance to skip leaf placement for irregular shape
-                        let mut noise = self.noise.lock().unwrap();
-                        if noise.random_bool(0.9) {
-                            chunk.set_block(leaf_x as usize, leaf_y, leaf_z as usize, Block::new(BlockType::OakLeaves));
-                        }
+                    let block = chunks[&chunk_y].get_block(x as usize, height as usize % 16, z as usize).unwrap();
+                    // Logs and leaves are the only flammable blocks generated
+                    let is_tree = block.properties().is_some_and(|properties| properties.flammable);
+                    if block.is_air() || is_tree {
+                        continue;
                     }
+                    let (surface, _) = generator.surface_blocks(biome, height);
+                    assert_eq!(block.block_type(), Some(surface), "{:?} at height {}", biome, height);
+                    checked += 1;
                 }
             }
+            assert!(checked > 128, "only {} surface blocks of {:?} checked", checked, biome);
         }
     }
-} 
\ No newline at end of file
+}
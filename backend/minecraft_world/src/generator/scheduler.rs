@@ -0,0 +1,247 @@
+use super::TerrainGenerator;
+use crate::chunk::Chunk;
+use minecraft_core::math::ChunkVector;
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, OnceCell};
+use anyhow::{Context, Result};
+
+/// A snapshot of the generation scheduler's counters
+#[derive(Debug, Clone, Copy)]
+pub struct GenerationMetrics {
+    /// Jobs waiting for a free worker
+    pub queued: usize,
+    /// Jobs currently being generated
+    pub running: usize,
+    /// Chunks generated so far
+    pub completed: u64,
+    /// Requests that joined a generation already in flight
+    pub deduplicated: u64,
+    /// The total time spent generating chunks
+    pub generation_time: Duration,
+    /// How long the scheduler has been running
+    pub uptime: Duration,
+}
+
+impl GenerationMetrics {
+    /// Returns the number of chunks generated per second since the scheduler started
+    pub fn chunks_per_second(&self) -> f64 {
+        self.completed as f64 / self.uptime.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Returns the average time taken to generate one chunk
+    pub fn average_time(&self) -> Duration {
+        match self.completed {
+            0 => Duration::ZERO,
+            completed => self.generation_time / completed as u32,
+        }
+    }
+}
+
+/// The shared result of a generation, filled in once by whichever request started it
+type InFlight = Arc<OnceCell<Arc<Chunk>>>;
+
+/// Runs chunk generation on a worker pool, one job per chunk
+///
+/// Concurrent requests for the same chunk share a single in-flight job, so a
+/// chunk is never generated twice at once. Generation itself runs on a
+/// dedicated rayon pool to keep CPU-heavy terrain work off the async runtime.
+pub struct GenerationScheduler {
+    /// The generator jobs are run with
+    generator: Arc<dyn TerrainGenerator>,
+    /// The worker pool
+    pool: rayon::ThreadPool,
+    /// The generations currently in flight, keyed by chunk position
+    in_flight: DashMap<i64, InFlight>,
+    /// Jobs waiting for a free worker
+    queued: Arc<AtomicUsize>,
+    /// Jobs currently being generated
+    running: Arc<AtomicUsize>,
+    /// Chunks generated so far
+    completed: Arc<AtomicU64>,
+    /// Requests that joined a generation already in flight
+    deduplicated: AtomicU64,
+    /// The total time spent generating, in microseconds
+    generation_micros: Arc<AtomicU64>,
+    /// When the scheduler was created
+    started_at: Instant,
+}
+
+impl GenerationScheduler {
+    /// Creates a scheduler with one worker per CPU
+    pub fn new(generator: Arc<dyn TerrainGenerator>) -> Result<Self> {
+        Self::with_threads(generator, 0)
+    }
+
+    /// Creates a scheduler with the specified number of workers (0 for one per CPU)
+    ///
+    /// Fails if the worker threads can't be started.
+    pub fn with_threads(generator: Arc<dyn TerrainGenerator>, threads: usize) -> Result<Self> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("chunk-gen-{}", index))
+            .build()
+            .context("Failed to create the chunk generation pool")?;
+
+        Ok(Self {
+            generator,
+            pool,
+            in_flight: DashMap::new(),
+            queued: Arc::new(AtomicUsize::new(0)),
+            running: Arc::new(AtomicUsize::new(0)),
+            completed: Arc::new(AtomicU64::new(0)),
+            deduplicated: AtomicU64::new(0),
+            generation_micros: Arc::new(AtomicU64::new(0)),
+            started_at: Instant::now(),
+        })
+    }
+
+    /// Returns the generator jobs are run with
+    pub fn generator(&self) -> &Arc<dyn TerrainGenerator> {
+        &self.generator
+    }
+
+    /// Generates a chunk, joining the in-flight generation if there is one
+    ///
+    /// `store` is called exactly once per generation with the new chunk, before
+    /// the job stops being in flight, and returns the chunk every waiting
+    /// request receives. Providers use it to insert the chunk into their map,
+    /// so a request arriving afterwards finds it there instead of generating
+    /// it again.
+    pub async fn generate<F>(&self, position: ChunkVector, store: F) -> Result<Arc<Chunk>>
+    where
+        F: FnOnce(Chunk) -> Arc<Chunk>,
+    {
+        let key = position.to_key();
+        let cell = self.in_flight.entry(key).or_default().clone();
+
+        if cell.initialized() {
+            self.deduplicated.fetch_add(1, Ordering::Relaxed);
+            return Ok(cell.get().unwrap().clone());
+        }
+
+        let mut started = false;
+        let result = cell
+            .get_or_try_init(|| {
+                started = true;
+                async move { Ok::<_, anyhow::Error>(store(self.spawn(position).await?)) }
+            })
+            .await
+            .cloned();
+
+        if started {
+            // Only the request that ran the job retires it; a failed job is
+            // retired too, so the next request starts a fresh one
+            self.in_flight.remove_if(&key, |_, current| Arc::ptr_eq(current, &cell));
+        } else {
+            self.deduplicated.fetch_add(1, Ordering::Relaxed);
+        }
+
+        result
+    }
+
+    /// Returns a snapshot of the scheduler's counters
+    pub fn metrics(&self) -> GenerationMetrics {
+        GenerationMetrics {
+            queued: self.queued.load(Ordering::Relaxed),
+            running: self.running.load(Ordering::Relaxed),
+            completed: self.completed.load(Ordering::Relaxed),
+            deduplicated: self.deduplicated.load(Ordering::Relaxed),
+            generation_time: Duration::from_micros(self.generation_micros.load(Ordering::Relaxed)),
+            uptime: self.started_at.elapsed(),
+        }
+    }
+
+    /// Runs one generation job on the pool and waits for the result
+    async fn spawn(&self, position: ChunkVector) -> Result<Chunk> {
+        let (sender, receiver) = oneshot::channel();
+        let generator = self.generator.clone();
+        let queued = self.queued.clone();
+        let running = self.running.clone();
+        let completed = self.completed.clone();
+        let generation_micros = self.generation_micros.clone();
+
+        queued.fetch_add(1, Ordering::Relaxed);
+        self.pool.spawn(move || {
+            queued.fetch_sub(1, Ordering::Relaxed);
+            running.fetch_add(1, Ordering::Relaxed);
+
+            // Generators don't wait on I/O, so the future completes on this worker
+            let start = Instant::now();
+            let result = futures::executor::block_on(generator.generate_chunk(position));
+            generation_micros.fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+
+            running.fetch_sub(1, Ordering::Relaxed);
+            if result.is_ok() {
+                completed.fetch_add(1, Ordering::Relaxed);
+            }
+            let _ = sender.send(result);
+        });
+
+        receiver.await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::provider::{ChunkProvider, MemoryChunkProvider};
+    use async_trait::async_trait;
+    use futures::future::join_all;
+
+    /// A generator that counts its generations and takes a while over each
+    struct CountingGenerator {
+        generated: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl TerrainGenerator for CountingGenerator {
+        async fn generate_chunk(&self, position: ChunkVector) -> Result<Chunk> {
+            self.generated.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(50));
+            Ok(Chunk::new(position))
+        }
+
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn seed(&self) -> i64 {
+            0
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_requests_generate_a_chunk_once() {
+        let generator = Arc::new(CountingGenerator { generated: AtomicUsize::new(0) });
+        let provider = Arc::new(MemoryChunkProvider::new(generator.clone()).unwrap());
+        let position = ChunkVector::new(3, 0, -2);
+
+        let requests = (0..16).map(|_| {
+            let provider = provider.clone();
+            tokio::spawn(async move { provider.get_chunk(position).await.unwrap() })
+        });
+        let chunks: Vec<Arc<Chunk>> = join_all(requests).await.into_iter().map(Result::unwrap).collect();
+
+        assert_eq!(generator.generated.load(Ordering::SeqCst), 1);
+        assert!(chunks.iter().all(|chunk| Arc::ptr_eq(chunk, &chunks[0])));
+        let metrics = provider.generation_metrics().unwrap();
+        assert_eq!(metrics.completed, 1);
+        assert_eq!(metrics.queued + metrics.running, 0);
+    }
+
+    #[tokio::test]
+    async fn different_chunks_are_generated_separately() {
+        let generator = Arc::new(CountingGenerator { generated: AtomicUsize::new(0) });
+        let scheduler = GenerationScheduler::with_threads(generator.clone(), 2).unwrap();
+
+        let first = scheduler.generate(ChunkVector::new(0, 0, 0), Arc::new);
+        let second = scheduler.generate(ChunkVector::new(1, 0, 0), Arc::new);
+        let (first, second) = futures::join!(first, second);
+        assert_eq!(first.unwrap().position, ChunkVector::new(0, 0, 0));
+        assert_eq!(second.unwrap().position, ChunkVector::new(1, 0, 0));
+        assert_eq!(generator.generated.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,260 +1,9 @@
 pub mod chunk;
 pub mod world;
 pub mod generator;
-pub mod loader;
-
-use minecraft_core::block::Block;
-use minecraft_util::log;
+pub mod storage;
 
 /// Initialize the world components
 pub fn init() {
     // Initialize components as needed
 }
-
-/// Module for chunk-related functionality
-pub mod chunk {
-    use minecraft_core::block::Block;
-    use minecraft_core::math::vector::ChunkVector;
-    use serde::{Serialize, Deserialize};
-    
-    /// The size of a chunk in blocks (X dimension)
-    pub const CHUNK_SIZE_X: usize = 16;
-    /// The size of a chunk in blocks (Y dimension)
-    pub const CHUNK_SIZE_Y: usize = 16;
-    /// The size of a chunk in blocks (Z dimension)
-    pub const CHUNK_SIZE_Z: usize = 16;
-    /// The total number of blocks in a chunk
-    pub const CHUNK_VOLUME: usize = CHUNK_SIZE_X * CHUNK_SIZE_Y * CHUNK_SIZE_Z;
-    
-    /// A section of a chunk containing blocks
-    #[derive(Clone, Serialize, Deserialize)]
-    pub struct ChunkSection {
-        /// Blocks in this section
-        blocks: [Block; CHUNK_VOLUME],
-        /// Block light levels (0-15)
-        block_light: [u8; CHUNK_VOLUME],
-        /// Sky light levels (0-15)
-        sky_light: [u8; CHUNK_VOLUME],
-    }
-    
-    impl ChunkSection {
-        /// Create a new empty chunk section
-        pub fn new() -> Self {
-            Self {
-                blocks: [Block::new(0); CHUNK_VOLUME],
-                block_light: [0; CHUNK_VOLUME],
-                sky_light: [0; CHUNK_VOLUME],
-            }
-        }
-        
-        /// Get a block at the specified coordinates
-        pub fn get_block(&self, x: usize, y: usize, z: usize) -> Block {
-            let index = self.get_index(x, y, z);
-            self.blocks[index]
-        }
-        
-        /// Set a block at the specified coordinates
-        pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: Block) {
-            let index = self.get_index(x, y, z);
-            self.blocks[index] = block;
-        }
-        
-        /// Get the index for the specified coordinates
-        fn get_index(&self, x: usize, y: usize, z: usize) -> usize {
-            (y * CHUNK_SIZE_Z * CHUNK_SIZE_X) + (z * CHUNK_SIZE_X) + x
-        }
-    }
-    
-    /// A chunk in the game world
-    #[derive(Clone, Serialize, Deserialize)]
-    pub struct Chunk {
-        /// The position of this chunk
-        position: ChunkVector,
-        /// Sections of this chunk
-        sections: Vec<Option<ChunkSection>>,
-        /// Whether this chunk is modified and needs saving
-        dirty: bool,
-    }
-    
-    impl Chunk {
-        /// Create a new empty chunk at the specified position
-        pub fn new(position: ChunkVector) -> Self {
-            let mut sections = Vec::with_capacity(16);
-            for _ in 0..16 {
-                sections.push(None);
-            }
-            
-            Self {
-                position,
-                sections,
-                dirty: false,
-            }
-        }
-        
-        /// Get the position of this chunk
-        pub fn position(&self) -> &ChunkVector {
-            &self.position
-        }
-        
-        /// Mark this chunk as needing to be saved
-        pub fn mark_dirty(&mut self) {
-            self.dirty = true;
-        }
-        
-        /// Check if this chunk needs to be saved
-        pub fn is_dirty(&self) -> bool {
-            self.dirty
-        }
-    }
-}
-
-/// Module for world-related functionality
-pub mod world {
-    use std::sync::Arc;
-    use dashmap::DashMap;
-    use minecraft_core::block::Block;
-    use minecraft_core::math::vector::{ChunkVector, BlockVector};
-    use crate::chunk::Chunk;
-    
-    /// A collection of chunks making up a world
-    pub struct ChunkCollection {
-        /// Map of chunk positions to chunks
-        chunks: DashMap<ChunkVector, Arc<Chunk>>,
-    }
-    
-    impl ChunkCollection {
-        /// Create a new empty chunk collection
-        pub fn new() -> Self {
-            Self {
-                chunks: DashMap::new(),
-            }
-        }
-        
-        /// Get a chunk at the specified position, or None if it doesn't exist
-        pub fn get_chunk(&self, position: &ChunkVector) -> Option<Arc<Chunk>> {
-            self.chunks.get(position).map(|c| c.clone())
-        }
-        
-        /// Set a chunk at the specified position
-        pub fn set_chunk(&self, position: ChunkVector, chunk: Arc<Chunk>) {
-            self.chunks.insert(position, chunk);
-        }
-        
-        /// Remove a chunk at the specified position
-        pub fn remove_chunk(&self, position: &ChunkVector) -> Option<Arc<Chunk>> {
-            self.chunks.remove(position).map(|(_, c)| c)
-        }
-    }
-    
-    /// A world in the game
-    pub struct World {
-        /// The chunks in this world
-        chunks: ChunkCollection,
-        /// The name of this world
-        name: String,
-        /// The seed for this world's generation
-        seed: i64,
-    }
-    
-    impl World {
-        /// Create a new empty world with the specified name and seed
-        pub fn new(name: String, seed: i64) -> Self {
-            Self {
-                chunks: ChunkCollection::new(),
-                name,
-                seed,
-            }
-        }
-        
-        /// Get the name of this world
-        pub fn name(&self) -> &str {
-            &self.name
-        }
-        
-        /// Get the seed for this world's generation
-        pub fn seed(&self) -> i64 {
-            self.seed
-        }
-    }
-}
-
-/// Module for world generation
-pub mod generator {
-    use minecraft_core::math::vector::ChunkVector;
-    use crate::chunk::Chunk;
-    
-    /// A trait for world generators
-    pub trait WorldGenerator: Send + Sync {
-        /// Generate a chunk at the specified position
-        fn generate_chunk(&self, position: ChunkVector) -> Chunk;
-        
-        /// Get the name of this generator
-        fn name(&self) -> &str;
-    }
-}
-
-/// Module for world loading and saving
-pub mod loader {
-    use std::path::{Path, PathBuf};
-    use anyhow::Result;
-    use crate::world::World;
-    
-    /// Configuration for a world
-    pub struct WorldConfig {
-        /// The name of the world
-        pub name: String,
-        /// The seed for world generation
-        pub seed: i64,
-        /// The generator to use
-        pub generator: String,
-    }
-    
-    /// A world loader/saver
-    pub struct WorldLoader {
-        /// The base directory for world data
-        base_dir: PathBuf,
-    }
-    
-    impl WorldLoader {
-        /// Create a new world loader with the specified base directory
-        pub fn new<P: AsRef<Path>>(base_dir: P) -> Self {
-            Self {
-                base_dir: base_dir.as_ref().to_path_buf(),
-            }
-        }
-        
-        /// Create a new world from the specified configuration
-        pub fn create_world(&self, config: WorldConfig) -> Result<World> {
-            let world_dir = self.base_dir.join(&config.name);
-            
-            // Create the world directory if it doesn't exist
-            if !world_dir.exists() {
-                std::fs::create_dir_all(&world_dir)?;
-            }
-            
-            // Create and return the world
-            Ok(World::new(config.name, config.seed))
-        }
-        
-        /// Load a world with the specified name
-        pub fn load_world(&self, name: &str) -> Result<World> {
-            let world_dir = self.base_dir.join(name);
-            
-            // Check if the world directory exists
-            if !world_dir.exists() {
-                anyhow::bail!("World '{}' does not exist", name);
-            }
-            
-            // Load world metadata
-            // For simplicity, just create a world with a default seed
-            Ok(World::new(name.to_string(), 0))
-        }
-        
-        /// Save a world
-        pub fn save_world(&self, world: &World) -> Result<()> {
-            // Save world metadata
-            
-            Ok(())
-        }
-    }
-} 
\ No newline at end of file
@@ -0,0 +1,30 @@
+pub mod sqlite;
+
+pub use sqlite::{SqliteChunkProvider, SqliteStorage};
+
+use minecraft_core::math::WorldVector;
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// The saved profile of a player
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerData {
+    /// The player's unique ID
+    pub id: Uuid,
+    /// The player's name
+    pub name: String,
+    /// Where the player was when they left
+    pub position: WorldVector,
+}
+
+/// A trait for storing player profiles
+#[async_trait]
+pub trait PlayerStore: Send + Sync {
+    /// Loads a player's profile by name (case-insensitive)
+    async fn load_player(&self, name: &str) -> Result<Option<PlayerData>>;
+
+    /// Saves player profiles in a single batch
+    async fn save_players(&self, players: &[PlayerData]) -> Result<()>;
+}
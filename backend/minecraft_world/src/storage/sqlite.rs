@@ -0,0 +1,454 @@
+use super::{PlayerData, PlayerStore};
+use crate::chunk::Chunk;
+use crate::chunk::provider::ChunkProvider;
+use crate::generator::TerrainGenerator;
+use crate::generator::scheduler::{GenerationMetrics, GenerationScheduler};
+use minecraft_core::math::ChunkVector;
+use dashmap::DashMap;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sqlx::{ConnectOptions, Row};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// The zstd compression level used for chunk data
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The most connections kept open to the database
+const MAX_CONNECTIONS: u32 = 4;
+
+/// A world stored in a single SQLite file
+///
+/// Holds the chunks, the player profiles and the world metadata. The schema
+/// is migrated when the file is opened, and batch writes run in a single
+/// transaction so a crash never leaves a half-written batch behind.
+#[derive(Clone)]
+pub struct SqliteStorage {
+    /// The database connection pool
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    /// Opens (or creates) the database at the specified path and migrates it
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let pool = connect(path.as_ref(), MAX_CONNECTIONS).await?;
+        Ok(Self { pool })
+    }
+
+    /// Reads a metadata value from the database at the specified path, or None if it isn't set
+    ///
+    /// This opens a single short-lived connection, so it's cheap enough for
+    /// reading a world's configuration.
+    pub async fn read_metadata<T: DeserializeOwned>(path: impl AsRef<Path>, key: &str) -> Result<Option<T>> {
+        let pool = connect(path.as_ref(), 1).await?;
+        let value = get_metadata(&pool, key).await;
+        pool.close().await;
+        value
+    }
+
+    /// Writes a metadata value to the database at the specified path
+    ///
+    /// Like `read_metadata`, this uses a single short-lived connection.
+    pub async fn write_metadata<T: Serialize>(path: impl AsRef<Path>, key: &str, value: &T) -> Result<()> {
+        let pool = connect(path.as_ref(), 1).await?;
+        let result = set_metadata(&pool, key, value).await;
+        pool.close().await;
+        result
+    }
+
+    /// Checks if a chunk is stored in the database
+    pub async fn contains_chunk(&self, position: ChunkVector) -> Result<bool> {
+        let row = sqlx::query("SELECT 1 FROM chunks WHERE x = ? AND y = ? AND z = ?")
+            .bind(position.x)
+            .bind(position.y)
+            .bind(position.z)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    /// Reads a metadata value, or None if it isn't set
+    pub async fn get_metadata<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        get_metadata(&self.pool, key).await
+    }
+
+    /// Writes a metadata value
+    pub async fn set_metadata<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        set_metadata(&self.pool, key, value).await
+    }
+
+    /// Reads and decompresses a chunk, or returns None if it isn't stored
+    pub async fn load_chunk(&self, position: ChunkVector) -> Result<Option<Chunk>> {
+        let row = sqlx::query("SELECT data FROM chunks WHERE x = ? AND y = ? AND z = ?")
+            .bind(position.x)
+            .bind(position.y)
+            .bind(position.z)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                let compressed: Vec<u8> = row.try_get("data")?;
+                let data = zstd::decode_all(compressed.as_slice())?;
+                Ok(Some(Chunk::deserialize(&data)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Compresses and writes chunks in a single transaction
+    pub async fn save_chunks(&self, chunks: &[Arc<Chunk>]) -> Result<()> {
+        // Compress on a blocking thread, then write everything in one transaction
+        let to_compress = chunks.to_vec();
+        let rows = tokio::task::spawn_blocking(move || {
+            to_compress
+                .iter()
+                .map(|chunk| {
+                    let data = zstd::encode_all(chunk.serialize()?.as_slice(), COMPRESSION_LEVEL)?;
+                    Ok((chunk.position, data, chunk.last_modified()))
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .await??;
+
+        let mut transaction = self.pool.begin().await?;
+        for (position, data, last_modified) in &rows {
+            sqlx::query(
+                "INSERT INTO chunks (x, y, z, data, last_modified) VALUES (?, ?, ?, ?, ?) \
+                 ON CONFLICT (x, y, z) DO UPDATE SET data = excluded.data, last_modified = excluded.last_modified",
+            )
+            .bind(position.x)
+            .bind(position.y)
+            .bind(position.z)
+            .bind(data)
+            .bind(*last_modified as i64)
+            .execute(&mut transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
+
+/// A chunk provider backed by a SQLite world database
+///
+/// Chunks are loaded from the database when first requested and generated
+/// if they have never been saved.
+#[derive(Clone)]
+pub struct SqliteChunkProvider {
+    /// The world database
+    storage: SqliteStorage,
+    /// The loaded chunks
+    chunks: Arc<DashMap<i64, Arc<Chunk>>>,
+    /// Generates chunks that haven't been saved
+    scheduler: Arc<GenerationScheduler>,
+}
+
+impl SqliteChunkProvider {
+    /// Creates a chunk provider for the specified database
+    pub fn new(storage: SqliteStorage, generator: Arc<dyn TerrainGenerator>) -> Result<Self> {
+        Ok(Self {
+            storage,
+            chunks: Arc::new(DashMap::new()),
+            scheduler: Arc::new(GenerationScheduler::new(generator)?),
+        })
+    }
+}
+
+#[async_trait]
+impl ChunkProvider for SqliteChunkProvider {
+    async fn chunk_exists(&self, position: ChunkVector) -> bool {
+        if self.chunks.contains_key(&position.to_key()) {
+            return true;
+        }
+
+        match self.storage.contains_chunk(position).await {
+            Ok(stored) => stored,
+            Err(err) => {
+                log::warn!("Failed to look up chunk {:?}: {}", position, err);
+                false
+            }
+        }
+    }
+
+    async fn get_chunk(&self, position: ChunkVector) -> Result<Arc<Chunk>> {
+        let key = position.to_key();
+
+        // Try to get an already loaded chunk
+        if let Some(chunk) = self.chunks.get(&key) {
+            return Ok(chunk.clone());
+        }
+
+        // Load it from the database, or generate it if it was never saved.
+        // Another task may have loaded the same chunk in the meantime
+        let store = |chunk| self.chunks.entry(key).or_insert_with(|| Arc::new(chunk)).clone();
+        match self.storage.load_chunk(position).await? {
+            Some(chunk) => Ok(store(chunk)),
+            None => self.scheduler.generate(position, store).await,
+        }
+    }
+
+    fn get_chunk_if_loaded(&self, position: ChunkVector) -> Option<Arc<Chunk>> {
+        self.chunks.get(&position.to_key()).map(|chunk| chunk.clone())
+    }
+
+    fn loaded_chunks(&self) -> Vec<Arc<Chunk>> {
+        self.chunks.iter().map(|entry| entry.value().clone()).collect()
+    }
+
+    fn unload_chunk(&self, position: ChunkVector) -> bool {
+        self.chunks.remove(&position.to_key()).is_some()
+    }
+
+    fn generation_metrics(&self) -> Option<GenerationMetrics> {
+        Some(self.scheduler.metrics())
+    }
+
+    async fn save_chunk(&self, chunk: &Chunk) -> Result<()> {
+        self.storage.save_chunks(&[Arc::new(chunk.clone())]).await
+    }
+
+    async fn save_chunks(&self, chunks: &[Arc<Chunk>]) -> Result<()> {
+        self.storage.save_chunks(chunks).await
+    }
+
+    async fn force_generate_chunk(&self, position: ChunkVector, generator: &dyn TerrainGenerator) -> Result<Arc<Chunk>> {
+        let chunk = Arc::new(generator.generate_chunk(position).await?);
+        self.chunks.insert(position.to_key(), chunk.clone());
+
+        Ok(chunk)
+    }
+}
+
+#[async_trait]
+impl PlayerStore for SqliteStorage {
+    async fn load_player(&self, name: &str) -> Result<Option<PlayerData>> {
+        let row = sqlx::query("SELECT data FROM players WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => Ok(Some(serde_json::from_str(row.try_get("data")?)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn save_players(&self, players: &[PlayerData]) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+
+        let mut transaction = self.pool.begin().await?;
+        for player in players {
+            sqlx::query(
+                "INSERT INTO players (name, id, data, updated_at) VALUES (?, ?, ?, ?) \
+                 ON CONFLICT (name) DO UPDATE SET id = excluded.id, data = excluded.data, updated_at = excluded.updated_at",
+            )
+            .bind(&player.name)
+            .bind(player.id.to_string())
+            .bind(serde_json::to_string(player)?)
+            .bind(now)
+            .execute(&mut transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
+
+/// Opens a connection pool to the database at the specified path and migrates it
+async fn connect(path: &Path, max_connections: u32) -> Result<SqlitePool> {
+    let mut options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal);
+    // Every chunk load is a query, so keep them out of the normal log
+    options.log_statements(log::LevelFilter::Trace);
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(max_connections)
+        .connect_with(options)
+        .await?;
+
+    sqlx::migrate!("./migrations").run(&pool).await?;
+    Ok(pool)
+}
+
+/// Reads a metadata value, or None if it isn't set
+async fn get_metadata<T: DeserializeOwned>(pool: &SqlitePool, key: &str) -> Result<Option<T>> {
+    let row = sqlx::query("SELECT value FROM world_meta WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+
+    match row {
+        Some(row) => Ok(Some(serde_json::from_str(row.try_get("value")?)?)),
+        None => Ok(None),
+    }
+}
+
+/// Writes a metadata value
+async fn set_metadata<T: Serialize>(pool: &SqlitePool, key: &str, value: &T) -> Result<()> {
+    sqlx::query("INSERT INTO world_meta (key, value) VALUES (?, ?) ON CONFLICT (key) DO UPDATE SET value = excluded.value")
+        .bind(key)
+        .bind(serde_json::to_string(value)?)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::flat::FlatWorldGenerator;
+    use minecraft_core::block::{Block, BlockType};
+    use minecraft_core::math::WorldVector;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    /// A database in a fresh temporary directory, removed when dropped
+    struct TempDatabase {
+        directory: PathBuf,
+    }
+
+    impl TempDatabase {
+        /// Creates the directory
+        fn new() -> Self {
+            let directory = std::env::temp_dir().join(format!("sqlite-test-{}", Uuid::new_v4()));
+            std::fs::create_dir_all(&directory).unwrap();
+            Self { directory }
+        }
+
+        /// Returns the path of the database file
+        fn path(&self) -> PathBuf {
+            self.directory.join("world.db")
+        }
+
+        /// Opens the database, creating it the first time
+        async fn open(&self) -> SqliteStorage {
+            SqliteStorage::open(self.path()).await.unwrap()
+        }
+    }
+
+    impl Drop for TempDatabase {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.directory);
+        }
+    }
+
+    /// Creates a chunk with a couple of blocks set
+    fn chunk(position: ChunkVector) -> Arc<Chunk> {
+        let chunk = Chunk::new(position);
+        chunk.set_block(1, 2, 3, Block::new(BlockType::Stone));
+        chunk.set_block(15, 15, 15, Block::new(BlockType::Torch));
+        Arc::new(chunk)
+    }
+
+    #[tokio::test]
+    async fn chunks_round_trip() {
+        let database = TempDatabase::new();
+        let storage = database.open().await;
+        let chunks = [chunk(ChunkVector::new(0, 0, 0)), chunk(ChunkVector::new(-3, 4, 7))];
+        storage.save_chunks(&chunks).await.unwrap();
+
+        // Another connection sees them too
+        let reopened = database.open().await;
+        for chunk in &chunks {
+            assert!(reopened.contains_chunk(chunk.position).await.unwrap());
+            let loaded = reopened.load_chunk(chunk.position).await.unwrap().unwrap();
+            assert_eq!(loaded.position, chunk.position);
+            assert_eq!(loaded.get_block(1, 2, 3), Some(Block::new(BlockType::Stone)));
+            assert_eq!(loaded.get_block(15, 15, 15), Some(Block::new(BlockType::Torch)));
+        }
+
+        let missing = ChunkVector::new(1, 0, 0);
+        assert!(!reopened.contains_chunk(missing).await.unwrap());
+        assert!(reopened.load_chunk(missing).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn saving_a_chunk_again_replaces_it() {
+        let database = TempDatabase::new();
+        let storage = database.open().await;
+        let position = ChunkVector::new(2, 0, 2);
+        storage.save_chunks(&[chunk(position)]).await.unwrap();
+
+        let changed = chunk(position);
+        changed.set_block(1, 2, 3, Block::new(BlockType::Dirt));
+        storage.save_chunks(&[changed]).await.unwrap();
+
+        let loaded = storage.load_chunk(position).await.unwrap().unwrap();
+        assert_eq!(loaded.get_block(1, 2, 3), Some(Block::new(BlockType::Dirt)));
+    }
+
+    #[tokio::test]
+    async fn provider_checks_the_database_for_chunks() {
+        let database = TempDatabase::new();
+        let position = ChunkVector::new(5, 0, 5);
+        database.open().await.save_chunks(&[chunk(position)]).await.unwrap();
+
+        let generator = Arc::new(FlatWorldGenerator::new("flat", 1));
+        let provider = SqliteChunkProvider::new(database.open().await, generator).unwrap();
+        assert!(provider.chunk_exists(position).await);
+        assert!(!provider.chunk_exists(ChunkVector::new(6, 0, 5)).await);
+
+        let loaded = provider.get_chunk(position).await.unwrap();
+        assert_eq!(loaded.get_block(15, 15, 15), Some(Block::new(BlockType::Torch)));
+    }
+
+    #[tokio::test]
+    async fn player_profiles_round_trip() {
+        let database = TempDatabase::new();
+        let storage = database.open().await;
+        let player = PlayerData {
+            id: Uuid::new_v4(),
+            name: "Steve".to_string(),
+            position: WorldVector::new(1.5, 70.0, -3.25),
+        };
+        storage.save_players(std::slice::from_ref(&player)).await.unwrap();
+
+        // Names are looked up case-insensitively
+        let loaded = storage.load_player("steve").await.unwrap().unwrap();
+        assert_eq!(loaded.id, player.id);
+        assert_eq!(loaded.name, player.name);
+        assert_eq!(loaded.position, player.position);
+        assert!(storage.load_player("Alex").await.unwrap().is_none());
+
+        // Saving again updates the profile
+        let moved = PlayerData {
+            position: WorldVector::new(0.0, 64.0, 0.0),
+            ..player
+        };
+        storage.save_players(std::slice::from_ref(&moved)).await.unwrap();
+        let loaded = database.open().await.load_player("STEVE").await.unwrap().unwrap();
+        assert_eq!(loaded.position, moved.position);
+    }
+
+    #[tokio::test]
+    async fn metadata_round_trips() {
+        let database = TempDatabase::new();
+        let storage = database.open().await;
+        assert_eq!(storage.get_metadata::<u64>("time").await.unwrap(), None);
+
+        storage.set_metadata("time", &1234u64).await.unwrap();
+        storage.set_metadata("name", &"World").await.unwrap();
+        storage.set_metadata("time", &5678u64).await.unwrap();
+        assert_eq!(storage.get_metadata::<u64>("time").await.unwrap(), Some(5678));
+        assert_eq!(storage.get_metadata::<String>("name").await.unwrap().as_deref(), Some("World"));
+
+        // The single-connection helpers see the same values
+        assert_eq!(SqliteStorage::read_metadata::<u64>(database.path(), "time").await.unwrap(), Some(5678));
+        SqliteStorage::write_metadata(database.path(), "time", &42u64).await.unwrap();
+        assert_eq!(storage.get_metadata::<u64>("time").await.unwrap(), Some(42));
+    }
+}
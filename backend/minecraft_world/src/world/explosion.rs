@@ -0,0 +1,335 @@
+use super::World;
+use minecraft_core::block::Block;
+use minecraft_core::math::{BlockVector, WorldVector};
+use minecraft_core::physics::{AABB, PhysicsState};
+use rand::Rng;
+use std::collections::HashSet;
+
+/// The number of ray end points along each edge of the cube rays are cast towards
+const RAY_GRID: i32 = 16;
+
+/// How far (blocks) a ray advances per step
+const RAY_STEP: f32 = 0.3;
+
+/// How much intensity a ray loses per step just by travelling
+const RAY_DECAY: f32 = 0.225;
+
+/// How much blast resistance a block adds on top of its own
+const RESISTANCE_BIAS: f32 = 0.3;
+
+/// The speed (blocks per second) a body right at the centre of an explosion is pushed at
+const KNOCKBACK_SPEED: f32 = 20.0;
+
+/// The number of sample points along each axis of a body when working out its exposure
+const EXPOSURE_SAMPLES: i32 = 3;
+
+/// An explosion that has happened in a world
+///
+/// `World::explode` removes the blocks; pushing bodies away is left to
+/// whoever owns them, through `knockback`.
+#[derive(Debug, Clone)]
+pub struct Explosion {
+    /// The centre of the explosion
+    pub center: WorldVector,
+    /// The power of the explosion (TNT is 4)
+    pub power: f32,
+    /// The blocks the explosion removed, as they were before it
+    pub destroyed: Vec<(BlockVector, Block)>,
+    /// The items the removed blocks dropped (position, item ID, count)
+    pub drops: Vec<(BlockVector, u16, u8)>,
+}
+
+impl Explosion {
+    /// Returns how far (blocks) the explosion pushes bodies
+    pub fn radius(&self) -> f32 {
+        self.power * 2.0
+    }
+
+    /// Pushes a body away from the explosion
+    ///
+    /// The push fades with distance and with how much of the body is hidden
+    /// from the centre behind solid blocks. Returns whether the body moved.
+    pub fn knockback(&self, world: &World, state: &mut PhysicsState) -> bool {
+        let body = state.bounding_box();
+        let middle = (body.min + body.max) * 0.5;
+        let offset = middle - self.center;
+        let distance = offset.length();
+        if distance >= self.radius() {
+            return false;
+        }
+
+        let impact = (1.0 - distance / self.radius()) * exposure(world, self.center, &body);
+        if impact <= 0.0 {
+            return false;
+        }
+
+        // A body right on the centre is thrown straight up
+        let direction = if distance > f32::EPSILON {
+            offset / distance
+        } else {
+            WorldVector::new(0.0, 1.0, 0.0)
+        };
+        state.velocity = state.velocity + direction * (impact * KNOCKBACK_SPEED);
+        state.on_ground = false;
+        true
+    }
+}
+
+/// Works out which blocks an explosion destroys
+///
+/// Rays are cast from the centre towards every point on the surface of a
+/// cube. Each starts with an intensity around the explosion's power and loses
+/// some per step, plus more for each block it passes through depending on the
+/// block's blast resistance; blocks it still has intensity left for are
+/// destroyed. Rays stop at unloaded chunks.
+pub(super) fn affected_blocks(world: &World, center: WorldVector, power: f32) -> Vec<(BlockVector, Block)> {
+    let mut rng = rand::thread_rng();
+    let mut seen = HashSet::new();
+    let mut affected = Vec::new();
+
+    let last = (RAY_GRID - 1) as f32;
+    for i in 0..RAY_GRID {
+        for j in 0..RAY_GRID {
+            for k in 0..RAY_GRID {
+                let on_surface = [i, j, k].iter().any(|n| *n == 0 || *n == RAY_GRID - 1);
+                if !on_surface {
+                    continue;
+                }
+
+                let direction = WorldVector::new(
+                    i as f32 / last * 2.0 - 1.0,
+                    j as f32 / last * 2.0 - 1.0,
+                    k as f32 / last * 2.0 - 1.0,
+                )
+                .normalize();
+                let mut intensity = power * rng.gen_range(0.7..1.3);
+                let mut point = center;
+
+                while intensity > 0.0 {
+                    let position = point.to_block();
+                    let Some(block) = world.get_block_if_loaded(position) else {
+                        break;
+                    };
+
+                    if !block.is_air() {
+                        let resistance = block.properties().map_or(f32::INFINITY, |props| props.blast_resistance);
+                        intensity -= (resistance + RESISTANCE_BIAS) * RAY_STEP;
+                        if intensity > 0.0 && seen.insert(position) {
+                            affected.push((position, block));
+                        }
+                    }
+
+                    point = point + direction * RAY_STEP;
+                    intensity -= RAY_DECAY;
+                }
+            }
+        }
+    }
+
+    affected
+}
+
+/// Works out the items destroyed blocks drop
+///
+/// Each block drops with a chance of one over the power, so bigger blasts
+/// leave proportionally less behind.
+pub(super) fn drops(destroyed: &[(BlockVector, Block)], power: f32) -> Vec<(BlockVector, u16, u8)> {
+    let mut rng = rand::thread_rng();
+    let chance = (1.0 / power).min(1.0);
+
+    let mut drops = Vec::new();
+    for (position, block) in destroyed {
+        if !rng.gen_bool(chance as f64) {
+            continue;
+        }
+        let Some(properties) = block.properties() else {
+            continue;
+        };
+        drops.extend(properties.drops.iter().map(|(item, count)| (*position, *item, *count)));
+    }
+    drops
+}
+
+/// Returns the share (0 to 1) of a body that has a clear line to a point
+///
+/// Lines are traced to a small grid of points spread over the body's box.
+fn exposure(world: &World, center: WorldVector, body: &AABB) -> f32 {
+    let size = body.max - body.min;
+    let last = (EXPOSURE_SAMPLES - 1) as f32;
+
+    let mut clear = 0;
+    let mut total = 0;
+    for i in 0..EXPOSURE_SAMPLES {
+        for j in 0..EXPOSURE_SAMPLES {
+            for k in 0..EXPOSURE_SAMPLES {
+                let sample = WorldVector::new(
+                    body.min.x + size.x * i as f32 / last,
+                    body.min.y + size.y * j as f32 / last,
+                    body.min.z + size.z * k as f32 / last,
+                );
+                if is_clear(world, center, sample) {
+                    clear += 1;
+                }
+                total += 1;
+            }
+        }
+    }
+
+    clear as f32 / total as f32
+}
+
+/// Checks whether a straight line between two points passes no solid blocks
+fn is_clear(world: &World, from: WorldVector, to: WorldVector) -> bool {
+    let offset = to - from;
+    let steps = (offset.length() / RAY_STEP).ceil() as i32;
+
+    (1..steps).all(|step| {
+        let point = from + offset * (step as f32 / steps as f32);
+        !world.get_block_if_loaded(point.to_block()).is_some_and(|block| block.is_solid())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::provider::MemoryChunkProvider;
+    use crate::generator::flat::FlatWorldGenerator;
+    use minecraft_core::block::BlockType;
+    use minecraft_core::math::ChunkVector;
+    use std::sync::Arc;
+
+    /// The centre of the explosions: in the air above the ground of a flat world
+    const CENTER: WorldVector = WorldVector { x: 8.5, y: 10.5, z: 8.5 };
+
+    /// Creates a flat world with the chunks around the origin loaded
+    async fn flat_world() -> World {
+        let generator = Arc::new(FlatWorldGenerator::new("flat", 1));
+        let world = World::new("test", 1, Box::new(MemoryChunkProvider::new(generator.clone()).unwrap()), generator);
+        for x in -1..=1 {
+            for z in -1..=1 {
+                for y in 0..=1 {
+                    world.get_chunk(ChunkVector::new(x, y, z)).await.unwrap();
+                }
+            }
+        }
+        world
+    }
+
+    /// Fills the plane x = 9 around the centre with a block, between it and everything further along x
+    async fn build_wall(world: &World, block_type: BlockType) {
+        for y in 6..16 {
+            for z in 3..14 {
+                world.set_block(BlockVector::new(9, y, z), Block::new(block_type)).await.unwrap();
+            }
+        }
+    }
+
+    /// Returns the type of the block at a position
+    fn block_type_at(world: &World, position: BlockVector) -> Option<BlockType> {
+        world.get_block_if_loaded(position).unwrap().block_type()
+    }
+
+    /// Returns an explosion at the centre that hasn't touched any blocks
+    fn explosion(power: f32) -> Explosion {
+        Explosion {
+            center: CENTER,
+            power,
+            destroyed: Vec::new(),
+            drops: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn destroys_stone_within_reach() {
+        let world = flat_world().await;
+        let near = BlockVector::new(9, 10, 8);
+        world.set_block(near, Block::new(BlockType::Stone)).await.unwrap();
+
+        let explosion = world.explode(CENTER, 4.0).await.unwrap();
+        assert!(explosion.destroyed.contains(&(near, Block::new(BlockType::Stone))));
+        assert!(world.get_block_if_loaded(near).unwrap().is_air());
+    }
+
+    #[tokio::test]
+    async fn leaves_blocks_out_of_reach() {
+        let world = flat_world().await;
+        let far = BlockVector::new(20, 10, 8);
+        world.set_block(far, Block::new(BlockType::Dirt)).await.unwrap();
+
+        let explosion = world.explode(CENTER, 4.0).await.unwrap();
+        assert!(explosion.destroyed.iter().all(|(position, _)| *position != far));
+        assert_eq!(block_type_at(&world, far), Some(BlockType::Dirt));
+    }
+
+    #[tokio::test]
+    async fn resistant_blocks_shield_the_blocks_behind_them() {
+        for shield in [BlockType::Bedrock, BlockType::Water] {
+            let world = flat_world().await;
+            build_wall(&world, shield).await;
+            let behind = BlockVector::new(10, 10, 8);
+            world.set_block(behind, Block::new(BlockType::Dirt)).await.unwrap();
+
+            world.explode(CENTER, 4.0).await.unwrap();
+            assert_eq!(block_type_at(&world, BlockVector::new(9, 10, 8)), Some(shield));
+            assert_eq!(block_type_at(&world, behind), Some(BlockType::Dirt), "{:?} didn't shield", shield);
+        }
+    }
+
+    #[tokio::test]
+    async fn unshielded_blocks_the_same_distance_away_are_destroyed() {
+        let world = flat_world().await;
+        let exposed = BlockVector::new(10, 10, 8);
+        world.set_block(exposed, Block::new(BlockType::Dirt)).await.unwrap();
+
+        world.explode(CENTER, 4.0).await.unwrap();
+        assert!(world.get_block_if_loaded(exposed).unwrap().is_air());
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_power() {
+        let world = flat_world().await;
+        assert!(world.explode(CENTER, 0.0).await.is_err());
+        assert!(world.explode(CENTER, f32::NAN).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn knockback_pushes_bodies_away_and_fades_with_distance() {
+        let world = flat_world().await;
+        let explosion = explosion(4.0);
+
+        let mut near = PhysicsState::new(WorldVector::new(10.5, 10.0, 8.5), (0.6, 1.8, 0.6));
+        let mut far = PhysicsState::new(WorldVector::new(13.5, 10.0, 8.5), (0.6, 1.8, 0.6));
+        assert!(explosion.knockback(&world, &mut near));
+        assert!(explosion.knockback(&world, &mut far));
+        assert!(near.velocity.x > far.velocity.x);
+        assert!(far.velocity.x > 0.0);
+        assert!(!near.on_ground);
+
+        let mut out_of_reach = PhysicsState::new(WorldVector::new(20.5, 10.0, 8.5), (0.6, 1.8, 0.6));
+        assert!(!explosion.knockback(&world, &mut out_of_reach));
+        assert_eq!(out_of_reach.velocity, WorldVector::new(0.0, 0.0, 0.0));
+    }
+
+    #[tokio::test]
+    async fn knockback_is_blocked_by_solid_blocks() {
+        let world = flat_world().await;
+        build_wall(&world, BlockType::Stone).await;
+        let explosion = explosion(4.0);
+
+        let mut hidden = PhysicsState::new(WorldVector::new(10.5, 10.0, 8.5), (0.6, 1.8, 0.6));
+        assert!(!explosion.knockback(&world, &mut hidden));
+        assert_eq!(hidden.velocity, WorldVector::new(0.0, 0.0, 0.0));
+
+        // Half a wall still lets part of the blast through
+        for y in 10..16 {
+            for z in 3..14 {
+                world.set_block(BlockVector::new(9, y, z), Block::default()).await.unwrap();
+            }
+        }
+        let mut exposed = PhysicsState::new(WorldVector::new(10.5, 9.0, 8.5), (0.6, 1.8, 0.6));
+        let mut open = PhysicsState::new(WorldVector::new(10.5, 9.0, 8.5), (0.6, 1.8, 0.6));
+        assert!(explosion.knockback(&world, &mut exposed));
+        assert!(explosion.knockback(&flat_world().await, &mut open));
+        assert!(exposed.velocity.x < open.velocity.x);
+    }
+}
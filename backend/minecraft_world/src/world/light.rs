@@ -0,0 +1,502 @@
+use crate::chunk::Chunk;
+use crate::chunk::light::LightKind;
+use crate::chunk::provider::ChunkProvider;
+use minecraft_core::block::{Block, BlockRegistry, BlockType};
+use minecraft_core::constants::MAX_BUILD_HEIGHT;
+use minecraft_core::math::{BlockVector, ChunkVector};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// The brightest light level
+pub const MAX_LIGHT: u8 = 15;
+
+/// The six directions light spreads in
+const DIRECTIONS: [(i32, i32, i32); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+/// Straight down, the direction sky light travels without fading
+const DOWN: (i32, i32, i32) = (0, -1, 0);
+
+/// Spreads block light and sky light through the loaded chunks of a world
+///
+/// Light is flood-filled with a queue of blocks whose light increased, and
+/// removed with a second queue that clears everything a changed block used to
+/// light before filling the gap back in from the light around it. Both cross
+/// chunk borders freely, but only into chunks the engine has already lit.
+///
+/// Chunks are cubic, so the chunk above may not be loaded yet. Until it is,
+/// the top of a chunk is treated as open sky; when it loads, the sky light it
+/// blocks is removed from the chunks below.
+pub struct LightEngine {
+    /// Held while light is being changed, so updates don't interleave
+    lock: Mutex<()>,
+}
+
+impl LightEngine {
+    /// Creates a new light engine
+    pub fn new() -> Self {
+        Self {
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Lights a chunk that was just loaded or generated
+    ///
+    /// Light from the lit chunks around it spreads in, and light from the
+    /// chunk spreads out into them. Does nothing if the chunk is already lit.
+    pub fn light_chunk(&self, provider: &dyn ChunkProvider, chunk: &Arc<Chunk>) {
+        let _guard = self.lock.lock().unwrap();
+        if chunk.is_lit() {
+            return;
+        }
+
+        let mut view = LightView::new(provider);
+        view.chunks.insert(chunk.position, Some(chunk.clone()));
+
+        let origin = BlockVector::new(chunk.position.x * 16, chunk.position.y * 16, chunk.position.z * 16);
+        for kind in LightKind::ALL {
+            let mut queue = VecDeque::new();
+
+            // Start with the light the chunk's own blocks give off, or the sky above it
+            for x in 0..16 {
+                for y in 0..16 {
+                    for z in 0..16 {
+                        let position = BlockVector::new(origin.x + x, origin.y + y, origin.z + z);
+                        let block = chunk.get_block(x as usize, y as usize, z as usize).unwrap_or_default();
+                        let source = view.source(kind, position, block);
+                        if source > 0 {
+                            view.set_light(kind, position, source);
+                            queue.push_back(position);
+                        }
+                    }
+                }
+            }
+
+            // Let the light along the faces of the lit chunks around it shine in
+            for (dx, dy, dz) in DIRECTIONS {
+                let neighbour = ChunkVector::new(chunk.position.x + dx, chunk.position.y + dy, chunk.position.z + dz);
+                if view.chunk(neighbour).is_none() {
+                    continue;
+                }
+                for (a, b) in (0..16).flat_map(|a| (0..16).map(move |b| (a, b))) {
+                    let local = match (dx, dy, dz) {
+                        (1, _, _) => (0, a, b),
+                        (-1, _, _) => (15, a, b),
+                        (_, 1, _) => (a, 0, b),
+                        (_, -1, _) => (a, 15, b),
+                        (_, _, 1) => (a, b, 0),
+                        _ => (a, b, 15),
+                    };
+                    let position = BlockVector::new(
+                        neighbour.x * 16 + local.0,
+                        neighbour.y * 16 + local.1,
+                        neighbour.z * 16 + local.2,
+                    );
+                    if view.light(kind, position).is_some_and(|level| level > 1) {
+                        queue.push_back(position);
+                    }
+                }
+            }
+
+            view.spread(kind, queue);
+        }
+
+        // The chunk below may have been lit as if it were under open sky
+        let below = ChunkVector::new(chunk.position.x, chunk.position.y - 1, chunk.position.z);
+        if view.chunk(below).is_some() {
+            let mut removals = VecDeque::new();
+            for x in 0..16 {
+                for z in 0..16 {
+                    let bottom = BlockVector::new(origin.x + x, origin.y, origin.z + z);
+                    let top = BlockVector::new(origin.x + x, origin.y - 1, origin.z + z);
+                    let (Some(level), Some(current), Some(block)) =
+                        (view.light(LightKind::Sky, bottom), view.light(LightKind::Sky, top), view.block(top))
+                    else {
+                        continue;
+                    };
+
+                    if current > propagate(LightKind::Sky, level, DOWN, block) {
+                        view.set_light(LightKind::Sky, top, 0);
+                        removals.push_back((top, current));
+                    }
+                }
+            }
+
+            if !removals.is_empty() {
+                let relight = view.unspread(LightKind::Sky, removals);
+                view.spread(LightKind::Sky, relight);
+            }
+        }
+
+        chunk.mark_lit();
+    }
+
+    /// Sets a block and updates the light around it
+    ///
+    /// The write happens under the engine's lock, so the light always ends up
+    /// matching the last block written.
+    pub fn set_block(&self, provider: &dyn ChunkProvider, chunk: &Arc<Chunk>, position: BlockVector, block: Block) {
+        let _guard = self.lock.lock().unwrap();
+
+        let (x, y, z) = local(position);
+        let old = chunk.get_block(x, y, z).unwrap_or_default();
+        chunk.set_block(x, y, z, block);
+
+        if !chunk.is_lit() || light_properties(old) == light_properties(block) {
+            return;
+        }
+
+        let mut view = LightView::new(provider);
+        for kind in LightKind::ALL {
+            let Some(current) = view.light(kind, position) else {
+                continue;
+            };
+
+            // Clear the light the old block had, and everything it lit
+            let source = view.source(kind, position, block);
+            view.set_light(kind, position, source);
+            let mut relight = view.unspread(kind, VecDeque::from([(position, current)]));
+            if source > 0 {
+                relight.push_back(position);
+            }
+
+            // Fill the gap back in, including from light the new block lets through
+            for (dx, dy, dz) in DIRECTIONS {
+                let neighbour = BlockVector::new(position.x + dx, position.y + dy, position.z + dz);
+                if view.light(kind, neighbour).is_some_and(|level| level > 0) {
+                    relight.push_back(neighbour);
+                }
+            }
+
+            view.spread(kind, relight);
+        }
+    }
+}
+
+impl Default for LightEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The loaded, lit chunks a light update touches, looked up once each
+struct LightView<'a> {
+    /// Where the chunks come from
+    provider: &'a dyn ChunkProvider,
+    /// The chunks looked up so far, or None if a chunk isn't loaded and lit
+    chunks: HashMap<ChunkVector, Option<Arc<Chunk>>>,
+    /// The chunk looked up last, since neighbouring blocks are usually in the same chunk
+    last: Option<(ChunkVector, Option<Arc<Chunk>>)>,
+}
+
+impl<'a> LightView<'a> {
+    /// Creates a view over a provider's loaded chunks
+    fn new(provider: &'a dyn ChunkProvider) -> Self {
+        Self {
+            provider,
+            chunks: HashMap::new(),
+            last: None,
+        }
+    }
+
+    /// Gets a chunk if it's loaded and lit
+    fn chunk(&mut self, position: ChunkVector) -> Option<Arc<Chunk>> {
+        if let Some((last_position, chunk)) = &self.last {
+            if *last_position == position {
+                return chunk.clone();
+            }
+        }
+
+        let provider = self.provider;
+        let chunk = self.chunks
+            .entry(position)
+            .or_insert_with(|| provider.get_chunk_if_loaded(position).filter(|chunk| chunk.is_lit()))
+            .clone();
+        self.last = Some((position, chunk.clone()));
+        chunk
+    }
+
+    /// Gets a block, if its chunk is loaded and lit
+    fn block(&mut self, position: BlockVector) -> Option<Block> {
+        let (x, y, z) = local(position);
+        let chunk = self.chunk(position.to_chunk())?;
+        Some(chunk.get_block(x, y, z).unwrap_or_default())
+    }
+
+    /// Gets a light level, if its chunk is loaded and lit
+    fn light(&mut self, kind: LightKind, position: BlockVector) -> Option<u8> {
+        let (x, y, z) = local(position);
+        let chunk = self.chunk(position.to_chunk())?;
+        Some(chunk.get_light(kind, x, y, z))
+    }
+
+    /// Sets a light level, if its chunk is loaded and lit
+    fn set_light(&mut self, kind: LightKind, position: BlockVector, level: u8) {
+        let (x, y, z) = local(position);
+        if let Some(chunk) = self.chunk(position.to_chunk()) {
+            chunk.set_light(kind, x, y, z, level);
+        }
+    }
+
+    /// Returns the light a block has of its own, before any spreads to it
+    ///
+    /// That is the light a block gives off, or for sky light, the sky shining
+    /// straight down on a block with nothing loaded above it.
+    fn source(&mut self, kind: LightKind, position: BlockVector, block: Block) -> u8 {
+        match kind {
+            LightKind::Block => light_properties(block).emission,
+            LightKind::Sky => {
+                let above = BlockVector::new(position.x, position.y + 1, position.z);
+                let open = above.y >= MAX_BUILD_HEIGHT
+                    || (above.to_chunk() != position.to_chunk() && self.chunk(above.to_chunk()).is_none());
+                if open {
+                    propagate(kind, MAX_LIGHT, DOWN, block)
+                } else {
+                    0
+                }
+            }
+        }
+    }
+
+    /// Spreads light outwards from every block in the queue
+    fn spread(&mut self, kind: LightKind, mut queue: VecDeque<BlockVector>) {
+        while let Some(position) = queue.pop_front() {
+            let Some(level) = self.light(kind, position) else {
+                continue;
+            };
+            if level <= 1 {
+                continue;
+            }
+
+            for direction in DIRECTIONS {
+                let neighbour = BlockVector::new(position.x + direction.0, position.y + direction.1, position.z + direction.2);
+                let (Some(current), Some(block)) = (self.light(kind, neighbour), self.block(neighbour)) else {
+                    continue;
+                };
+
+                let level = propagate(kind, level, direction, block);
+                if level > current {
+                    self.set_light(kind, neighbour, level);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+    }
+
+    /// Removes the light that blocks which went dark had spread to their surroundings
+    ///
+    /// Each entry is a block that has already been darkened, along with the
+    /// light it used to have. Returns the blocks whose light must be spread
+    /// again to fill the gap: neighbours lit by something else, and blocks
+    /// with a light of their own.
+    fn unspread(&mut self, kind: LightKind, mut removals: VecDeque<(BlockVector, u8)>) -> VecDeque<BlockVector> {
+        let mut relight = VecDeque::new();
+
+        while let Some((position, level)) = removals.pop_front() {
+            for direction in DIRECTIONS {
+                let neighbour = BlockVector::new(position.x + direction.0, position.y + direction.1, position.z + direction.2);
+                let (Some(current), Some(block)) = (self.light(kind, neighbour), self.block(neighbour)) else {
+                    continue;
+                };
+                if current == 0 {
+                    continue;
+                }
+
+                if current <= propagate(kind, level, direction, block) {
+                    // The neighbour may have been lit by the removed light
+                    let source = self.source(kind, neighbour, block);
+                    self.set_light(kind, neighbour, source);
+                    removals.push_back((neighbour, current));
+                    if source > 0 {
+                        relight.push_back(neighbour);
+                    }
+                } else {
+                    // The neighbour is lit by something else, which can fill the gap
+                    relight.push_back(neighbour);
+                }
+            }
+        }
+
+        relight
+    }
+}
+
+/// How a block type interacts with light
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct LightProperties {
+    /// How much light the block absorbs, on top of the usual one level per block
+    /// (15 blocks light completely)
+    opacity: u8,
+    /// The light the block gives off
+    emission: u8,
+}
+
+/// Returns how a block interacts with light
+///
+/// The answers for every block in the registry are worked out once and kept
+/// in a table, since this runs for every block light passes through.
+fn light_properties(block: Block) -> LightProperties {
+    static TABLE: OnceLock<Vec<LightProperties>> = OnceLock::new();
+
+    let table = TABLE.get_or_init(|| {
+        let registry = BlockRegistry::global();
+        let size = registry.iter().map(|(id, _)| id as usize + 1).max().unwrap_or(0);
+        let mut table = vec![LightProperties::default(); size];
+        for (id, properties) in registry.iter() {
+            let opacity = if !properties.transparent {
+                MAX_LIGHT
+            } else if properties.fluid
+                || matches!(
+                    BlockType::from_id(id),
+                    Some(BlockType::OakLeaves | BlockType::BirchLeaves | BlockType::SpruceLeaves)
+                )
+            {
+                // Water, lava and leaves let light through, but dim it
+                1
+            } else {
+                0
+            };
+            table[id as usize] = LightProperties {
+                opacity,
+                emission: properties.light_emission,
+            };
+        }
+        table
+    });
+
+    table.get(block.block_type as usize).copied().unwrap_or_default()
+}
+
+/// Returns the light a block receives from a neighbour with the specified level
+///
+/// Light fades by one level per block, and more through blocks that dim it.
+/// Full sky light shining straight down through clear blocks doesn't fade.
+fn propagate(kind: LightKind, level: u8, direction: (i32, i32, i32), block: Block) -> u8 {
+    let opacity = light_properties(block).opacity;
+    if opacity >= MAX_LIGHT {
+        0
+    } else if kind == LightKind::Sky && direction == DOWN && level == MAX_LIGHT && opacity == 0 {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(opacity.max(1))
+    }
+}
+
+/// Converts a world position to coordinates within its chunk
+fn local(position: BlockVector) -> (usize, usize, usize) {
+    (
+        position.x.rem_euclid(16) as usize,
+        position.y.rem_euclid(16) as usize,
+        position.z.rem_euclid(16) as usize,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::provider::MemoryChunkProvider;
+    use crate::generator::flat::FlatWorldGenerator;
+
+    /// Creates a provider of flat chunks: ground up to y = 5 in the chunk at y = 0, and air everywhere else
+    fn flat_provider() -> MemoryChunkProvider {
+        MemoryChunkProvider::new(Arc::new(FlatWorldGenerator::new("flat", 1))).unwrap()
+    }
+
+    /// Loads a chunk, lets a function change its blocks, then lights it
+    async fn load(
+        engine: &LightEngine,
+        provider: &MemoryChunkProvider,
+        position: ChunkVector,
+        build: impl FnOnce(&Chunk),
+    ) -> Arc<Chunk> {
+        let chunk = provider.get_chunk(position).await.unwrap();
+        build(&chunk);
+        engine.light_chunk(provider, &chunk);
+        chunk
+    }
+
+    /// Returns every light level of one kind in a chunk
+    fn snapshot(chunk: &Chunk, kind: LightKind) -> Vec<u8> {
+        (0..16)
+            .flat_map(|x| (0..16).flat_map(move |y| (0..16).map(move |z| (x, y, z))))
+            .map(|(x, y, z)| chunk.get_light(kind, x, y, z))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn chunk_above_blocks_sky_light() {
+        let engine = LightEngine::new();
+        let provider = flat_provider();
+        let ground = load(&engine, &provider, ChunkVector::new(0, 0, 0), |_| {}).await;
+        assert_eq!(ground.get_light(LightKind::Sky, 8, 10, 8), MAX_LIGHT);
+        assert_eq!(ground.get_light(LightKind::Sky, 8, 5, 8), 0);
+
+        // A roof with a single hole in it, right above the ground chunk
+        load(&engine, &provider, ChunkVector::new(0, 1, 0), |chunk| {
+            for x in 0..16 {
+                for z in 0..16 {
+                    if (x, z) != (8, 8) {
+                        chunk.set_block(x, 0, z, Block::new(BlockType::Stone));
+                    }
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(ground.get_light(LightKind::Sky, 8, 15, 8), MAX_LIGHT);
+        assert_eq!(ground.get_light(LightKind::Sky, 8, 6, 8), MAX_LIGHT);
+        assert_eq!(ground.get_light(LightKind::Sky, 4, 10, 8), MAX_LIGHT - 4);
+        assert_eq!(ground.get_light(LightKind::Sky, 0, 10, 0), 0);
+    }
+
+    #[tokio::test]
+    async fn opaque_block_placed_then_removed() {
+        let engine = LightEngine::new();
+        let provider = flat_provider();
+        let chunk = load(&engine, &provider, ChunkVector::new(0, 0, 0), |_| {}).await;
+        let before = snapshot(&chunk, LightKind::Sky);
+
+        let position = BlockVector::new(8, 10, 8);
+        engine.set_block(&provider, &chunk, position, Block::new(BlockType::Stone));
+        assert_eq!(chunk.get_light(LightKind::Sky, 8, 10, 8), 0);
+        // Shaded from straight above, but lit from the sides
+        assert_eq!(chunk.get_light(LightKind::Sky, 8, 9, 8), MAX_LIGHT - 1);
+        assert_eq!(chunk.get_light(LightKind::Sky, 8, 6, 8), MAX_LIGHT - 1);
+        assert_eq!(chunk.get_light(LightKind::Sky, 7, 9, 8), MAX_LIGHT);
+
+        engine.set_block(&provider, &chunk, position, Block::default());
+        assert_eq!(snapshot(&chunk, LightKind::Sky), before);
+    }
+
+    #[tokio::test]
+    async fn removed_emitter_leaves_light_of_its_neighbour() {
+        let torch = Block::new(BlockType::Torch);
+        let kept = BlockVector::new(6, 10, 8);
+        let removed = BlockVector::new(4, 10, 8);
+
+        // Two torches, then one taken away again
+        let engine = LightEngine::new();
+        let provider = flat_provider();
+        let chunk = load(&engine, &provider, ChunkVector::new(0, 0, 0), |_| {}).await;
+        engine.set_block(&provider, &chunk, kept, torch);
+        engine.set_block(&provider, &chunk, removed, torch);
+        assert_eq!(chunk.get_light(LightKind::Block, 4, 10, 8), 14);
+        engine.set_block(&provider, &chunk, removed, Block::default());
+
+        // Only ever the one torch
+        let expected_engine = LightEngine::new();
+        let expected_provider = flat_provider();
+        let expected = load(&expected_engine, &expected_provider, ChunkVector::new(0, 0, 0), |_| {}).await;
+        expected_engine.set_block(&expected_provider, &expected, kept, torch);
+
+        assert_eq!(chunk.get_light(LightKind::Block, 4, 10, 8), 12);
+        assert_eq!(chunk.get_light(LightKind::Block, 2, 10, 8), 10);
+        assert_eq!(snapshot(&chunk, LightKind::Block), snapshot(&expected, LightKind::Block));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::world::World;
 use crate::generator::{TerrainGenerator, flat::FlatWorldGenerator, overworld::OverworldGenerator};
 use crate::chunk::provider::{ChunkProvider, MemoryChunkProvider};
+use crate::chunk::region::RegionChunkProvider;
+use crate::storage::{PlayerStore, SqliteChunkProvider, SqliteStorage};
 use minecraft_core::block::BlockType;
 use minecraft_util::config::{load_config, save_config};
 use serde::{Serialize, Deserialize};
@@ -20,6 +22,9 @@ pub struct WorldConfig {
     pub seed: i64,
     /// The type of generator to use
     pub generator_type: String,
+    /// Where chunks are stored ("region", "sqlite" or "memory")
+    #[serde(default = "default_storage_type")]
+    pub storage_type: String,
     /// The spawn position (x, y, z)
     pub spawn_position: (i32, i32, i32),
     /// The game mode (0 = survival, 1 = creative)
@@ -43,6 +48,7 @@ impl Default for WorldConfig {
             name: "New World".to_string(),
             seed: rand::random(),
             generator_type: "overworld".to_string(),
+            storage_type: default_storage_type(),
             spawn_position: (0, 64, 0),
             game_mode: 0,
             generate_structures: true,
@@ -54,6 +60,20 @@ impl Default for WorldConfig {
     }
 }
 
+/// The name of the world configuration file
+const CONFIG_FILE: &str = "world.json";
+
+/// The name of the database file for SQLite worlds
+const DATABASE_FILE: &str = "world.db";
+
+/// The metadata key holding the configuration of SQLite worlds
+const CONFIG_KEY: &str = "config";
+
+/// The storage type for worlds whose config doesn't specify one
+fn default_storage_type() -> String {
+    "region".to_string()
+}
+
 /// Manages loading and creating worlds
 pub struct WorldLoader {
     /// The base directory for world data
@@ -74,7 +94,7 @@ impl WorldLoader {
     }
     
     /// Lists all available worlds
-    pub fn list_worlds(&self) -> Result<Vec<WorldConfig>> {
+    pub async fn list_worlds(&self) -> Result<Vec<WorldConfig>> {
         let mut worlds = Vec::new();
         
         // Iterate through directories in the worlds dir
@@ -82,15 +102,13 @@ impl WorldLoader {
             let entry = entry?;
             let path = entry.path();
             
-            // Check if it's a directory and has a config file
+            // Check if it's a directory with a configuration
             if path.is_dir() {
-                let config_path = path.join("world.json");
-                if config_path.exists() {
-                    match load_config::<WorldConfig>(&config_path) {
-                        Ok(config) => worlds.push(config),
-                        Err(err) => {
-                            log::warn!("Failed to load world config from {}: {}", config_path.display(), err);
-                        }
+                match Self::read_config(&path).await {
+                    Ok(Some(config)) => worlds.push(config),
+                    Ok(None) => {},
+                    Err(err) => {
+                        log::warn!("Failed to load world config from {}: {}", path.display(), err);
                     }
                 }
             }
@@ -99,6 +117,33 @@ impl WorldLoader {
         Ok(worlds)
     }
     
+    /// Reads the configuration of the world in a directory
+    ///
+    /// SQLite worlds keep their configuration in the database; all other
+    /// worlds use `world.json`. Returns None if the directory holds no world.
+    async fn read_config(world_dir: &Path) -> Result<Option<WorldConfig>> {
+        let config_path = world_dir.join(CONFIG_FILE);
+        if config_path.exists() {
+            return Ok(Some(load_config(&config_path)?));
+        }
+        
+        let database_path = world_dir.join(DATABASE_FILE);
+        if database_path.exists() {
+            return SqliteStorage::read_metadata(&database_path, CONFIG_KEY).await;
+        }
+        
+        Ok(None)
+    }
+    
+    /// Writes the configuration of the world in a directory
+    async fn write_config(world_dir: &Path, config: &WorldConfig) -> Result<()> {
+        if config.storage_type == "sqlite" {
+            SqliteStorage::write_metadata(world_dir.join(DATABASE_FILE), CONFIG_KEY, config).await
+        } else {
+            save_config(config, &world_dir.join(CONFIG_FILE))
+        }
+    }
+    
     /// Creates a new world with the specified configuration
     pub async fn create_world(&self, config: WorldConfig) -> Result<World> {
         // Create the world directory
@@ -107,10 +152,6 @@ impl WorldLoader {
             std::fs::create_dir_all(&world_dir)?;
         }
         
-        // Save the world configuration
-        let config_path = world_dir.join("world.json");
-        save_config(&config, &config_path)?;
-        
         // Create the appropriate generator
         let generator: Arc<dyn TerrainGenerator + Send + Sync> = match config.generator_type.as_str() {
             "flat" => {
@@ -132,19 +173,39 @@ impl WorldLoader {
             },
         };
         
-        // Create the chunk provider
-        let chunk_provider = MemoryChunkProvider::new(generator.as_ref().clone());
+        // Create the chunk provider, and save the configuration next to the chunks
+        let mut player_store: Option<Arc<dyn PlayerStore>> = None;
+        let chunk_provider: Box<dyn ChunkProvider> = match config.storage_type.as_str() {
+            "memory" => {
+                save_config(&config, &world_dir.join(CONFIG_FILE))?;
+                Box::new(MemoryChunkProvider::new(generator.clone())?)
+            },
+            "sqlite" => {
+                let storage = SqliteStorage::open(world_dir.join(DATABASE_FILE)).await?;
+                storage.set_metadata(CONFIG_KEY, &config).await?;
+                player_store = Some(Arc::new(storage.clone()));
+                Box::new(SqliteChunkProvider::new(storage, generator.clone())?)
+            },
+            "region" => {
+                save_config(&config, &world_dir.join(CONFIG_FILE))?;
+                Box::new(RegionChunkProvider::new(world_dir.join("region"), generator.clone())?)
+            },
+            other => {
+                return Err(anyhow::anyhow!("Unknown storage type '{}' for world {}", other, config.id));
+            },
+        };
         
         // Create the world
         let mut world = World::new(
             &config.name,
             config.seed,
-            Box::new(chunk_provider),
+            chunk_provider,
             generator.clone(),
         );
         
         // Set the world's properties from the config
         world.id = config.id;
+        world.player_store = player_store;
         world.time.store(config.time, std::sync::atomic::Ordering::Relaxed);
         world.is_raining = config.is_raining;
         world.is_thundering = config.is_thundering;
@@ -155,7 +216,7 @@ impl WorldLoader {
         );
         
         // Generate the spawn area
-        let spawn_chunks = generator.create_spawn_area(8).await?;
+        let _spawn_chunks = generator.create_spawn_area(8).await?;
         
         // Find a suitable spawn position if not specified
         if config.spawn_position == (0, 64, 0) {
@@ -176,8 +237,9 @@ impl WorldLoader {
         }
         
         // Load the world configuration
-        let config_path = world_dir.join("world.json");
-        let config = load_config::<WorldConfig>(&config_path)?;
+        let config = Self::read_config(&world_dir)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("World {} has no configuration", id))?;
         
         // Create the world from the config
         self.create_world(config).await
@@ -191,28 +253,28 @@ impl WorldLoader {
             std::fs::create_dir_all(&world_dir)?;
         }
         
-        // Create the world configuration
+        // Start from the stored configuration so settings the world doesn't
+        // track itself (generator, storage, game mode) are kept
+        let stored = Self::read_config(&world_dir).await.ok().flatten().unwrap_or_default();
+        
+        // Update the configuration from the world
         let config = WorldConfig {
             id: world.id,
             name: world.name.clone(),
             seed: world.seed,
-            generator_type: world.generator.name().to_string(),
             spawn_position: (
                 world.spawn_position.x,
                 world.spawn_position.y,
                 world.spawn_position.z,
             ),
-            game_mode: 0, // Default to survival
-            generate_structures: true,
-            hardcore: false,
             time: world.time.load(std::sync::atomic::Ordering::Relaxed),
             is_raining: world.is_raining,
             is_thundering: world.is_thundering,
+            ..stored
         };
         
         // Save the configuration
-        let config_path = world_dir.join("world.json");
-        save_config(&config, &config_path)?;
+        Self::write_config(&world_dir, &config).await?;
         
         // Save all dirty chunks
         world.save_dirty_chunks().await?;
@@ -229,4 +291,23 @@ impl WorldLoader {
         
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rejects_unknown_storage_types() {
+        let worlds_dir = std::env::temp_dir().join(format!("loader-test-{}", Uuid::new_v4()));
+        let loader = WorldLoader::new(&worlds_dir);
+        let config = WorldConfig {
+            generator_type: "flat".to_string(),
+            storage_type: "regoin".to_string(),
+            ..WorldConfig::default()
+        };
+
+        let result = loader.create_world(config).await;
+        std::fs::remove_dir_all(&worlds_dir).unwrap();
+        assert!(result.err().unwrap().to_string().contains("Unknown storage type 'regoin'"));
+    }
+}
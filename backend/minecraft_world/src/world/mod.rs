@@ -1,11 +1,18 @@
+pub mod explosion;
+pub mod light;
 pub mod loader;
-pub mod query;
+pub mod updates;
 
-use crate::chunk::{Chunk, provider::ChunkProvider};
+use crate::chunk::{Chunk, light::LightKind, provider::ChunkProvider};
 use crate::generator::TerrainGenerator;
+use crate::generator::scheduler::GenerationMetrics;
+use crate::storage::PlayerStore;
+use explosion::Explosion;
+use light::LightEngine;
+use updates::{BlockUpdateQueue, BlockUpdates};
 use minecraft_core::block::Block;
-use minecraft_core::math::{BlockVector, ChunkVector, block_to_chunk};
-use minecraft_util::log;
+use minecraft_core::math::{BlockVector, ChunkVector, WorldVector, block_to_local};
+use dashmap::DashMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -24,6 +31,8 @@ pub struct World {
     pub chunk_provider: Box<dyn ChunkProvider>,
     /// The generator used to create new terrain
     pub generator: Arc<dyn TerrainGenerator + Send + Sync>,
+    /// Where player profiles are stored, if the world's storage keeps them
+    pub player_store: Option<Arc<dyn PlayerStore>>,
     /// The time of day in ticks (0-24000)
     pub time: AtomicU64,
     /// Whether it is currently raining
@@ -36,6 +45,15 @@ pub struct World {
     pub created_at: u64,
     /// The last time the world was played
     pub last_played: AtomicU64,
+    /// The chunks modified since they were last saved, keyed by position
+    ///
+    /// Holding the chunk keeps its changes alive even if it is unloaded
+    /// before the next save.
+    dirty_chunks: DashMap<i64, Arc<Chunk>>,
+    /// Keeps the light of loaded chunks up to date
+    light: LightEngine,
+    /// The neighbour updates and scheduled ticks waiting to be processed
+    block_updates: BlockUpdateQueue,
 }
 
 impl World {
@@ -57,28 +75,46 @@ impl World {
             seed,
             chunk_provider,
             generator,
+            player_store: None,
             time: AtomicU64::new(0),
             is_raining: false,
             is_thundering: false,
             spawn_position: BlockVector::new(0, 64, 0), // Will be set during generation
             created_at: now,
             last_played: AtomicU64::new(now),
+            dirty_chunks: DashMap::new(),
+            light: LightEngine::new(),
+            block_updates: BlockUpdateQueue::new(),
         }
     }
     
     /// Gets a chunk at the specified position, loading or generating it if necessary
+    ///
+    /// A chunk is lit the first time it is fetched after loading.
     pub async fn get_chunk(&self, position: ChunkVector) -> Result<Arc<Chunk>> {
-        self.chunk_provider.get_chunk(position).await
+        let chunk = self.chunk_provider.get_chunk(position).await?;
+        if !chunk.is_lit() {
+            self.light.light_chunk(self.chunk_provider.as_ref(), &chunk);
+        }
+        chunk.touch();
+        Ok(chunk)
+    }
+    
+    /// Returns the chunk generation counters, if the chunk provider schedules generation
+    pub fn generation_metrics(&self) -> Option<GenerationMetrics> {
+        self.chunk_provider.generation_metrics()
     }
     
     /// Checks if a chunk exists at the specified position
-    pub fn chunk_exists(&self, position: ChunkVector) -> bool {
-        self.chunk_provider.chunk_exists(position)
+    pub async fn chunk_exists(&self, position: ChunkVector) -> bool {
+        self.chunk_provider.chunk_exists(position).await
     }
     
     /// Gets a chunk if it's already loaded
     pub fn get_chunk_if_loaded(&self, position: ChunkVector) -> Option<Arc<Chunk>> {
-        self.chunk_provider.get_chunk_if_loaded(position)
+        let chunk = self.chunk_provider.get_chunk_if_loaded(position)?;
+        chunk.touch();
+        Some(chunk)
     }
     
     /// Gets the block at the specified position
@@ -93,29 +129,87 @@ impl World {
         Ok(chunk.get_block(local_x, local_y, local_z).unwrap_or_default())
     }
     
+    /// Gets the block at the specified position if its chunk is loaded, without loading it
+    pub fn get_block_if_loaded(&self, position: BlockVector) -> Option<Block> {
+        let chunk = self.chunk_provider.get_chunk_if_loaded(position.to_chunk())?;
+        let (local_x, local_y, local_z) = block_to_local(position.x, position.y, position.z);
+        Some(chunk.get_block(local_x, local_y, local_z).unwrap_or_default())
+    }
+    
     /// Sets the block at the specified position
     pub async fn set_block(&self, position: BlockVector, block: Block) -> Result<()> {
         let chunk_pos = position.to_chunk();
         let chunk = self.get_chunk(chunk_pos).await?;
         
-        // Convert to a mutable reference if possible
-        // This is a simplification - in a real implementation, we would need
-        // a more complex system to handle concurrent chunk modifications
-        let chunk_ptr = Arc::into_raw(chunk) as *mut Chunk;
-        let chunk_mut = unsafe { &mut *chunk_ptr };
+        // Set the block in the chunk and relight around it; the chunk locks the affected section
+        self.light.set_block(self.chunk_provider.as_ref(), &chunk, position, block);
         
-        // Convert world coordinates to chunk-local coordinates
-        let (local_x, local_y, local_z) = block_to_local(position.x, position.y, position.z);
+        // Remember the chunk for the next save
+        self.dirty_chunks.insert(chunk_pos.to_key(), chunk);
         
-        // Set the block in the chunk
-        chunk_mut.set_block(local_x, local_y, local_z, block);
-        
-        // Prevent the drop of the Arc
-        let _ = unsafe { Arc::from_raw(chunk_ptr) };
+        // Let the block and its neighbours react to the change
+        self.block_updates.queue_neighbours(position);
         
         Ok(())
     }
     
+    /// Schedules a block tick after the specified number of game ticks
+    ///
+    /// Returns false if the block already has a tick scheduled.
+    pub fn schedule_block_tick(&self, position: BlockVector, delay: u64) -> bool {
+        self.block_updates.schedule(position, delay)
+    }
+    
+    /// Advances the block update queue by one game tick and takes the updates due
+    pub fn take_block_updates(&self) -> BlockUpdates {
+        self.block_updates.take()
+    }
+    
+    /// Sets off an explosion of the specified power (TNT is 4)
+    ///
+    /// The blocks it destroys are replaced with air, which blast-resistant
+    /// blocks shield the blocks behind them from. The returned explosion lists
+    /// the destroyed blocks and their drops, and pushes bodies away from it.
+    ///
+    /// Each destroyed block goes through `set_block` on its own, so light is
+    /// updated once per block rather than once for the whole blast.
+    pub async fn explode(&self, center: WorldVector, power: f32) -> Result<Explosion> {
+        if !power.is_finite() || power <= 0.0 {
+            return Err(anyhow::anyhow!("Invalid explosion power: {}", power));
+        }
+        
+        let destroyed = explosion::affected_blocks(self, center, power);
+        for (position, _) in &destroyed {
+            self.set_block(*position, Block::default()).await?;
+        }
+        let drops = explosion::drops(&destroyed, power);
+        
+        Ok(Explosion {
+            center,
+            power,
+            destroyed,
+            drops,
+        })
+    }
+    
+    /// Gets the light level of one kind at the specified position, if its chunk is loaded and lit
+    pub fn get_light(&self, kind: LightKind, position: BlockVector) -> Option<u8> {
+        let chunk = self.chunk_provider.get_chunk_if_loaded(position.to_chunk())?;
+        if !chunk.is_lit() {
+            return None;
+        }
+        
+        let (local_x, local_y, local_z) = block_to_local(position.x, position.y, position.z);
+        Some(chunk.get_light(kind, local_x, local_y, local_z))
+    }
+    
+    /// Gets the brightest of the block light and sky light at the specified position
+    pub fn light_level(&self, position: BlockVector) -> Option<u8> {
+        let block = self.get_light(LightKind::Block, position)?;
+        let sky = self.get_light(LightKind::Sky, position)?;
+        Some(block.max(sky))
+    }
+    
     /// Sets the time of day
     pub fn set_time(&self, time: u64) {
         self.time.store(time % 24000, Ordering::Relaxed);
@@ -148,79 +242,239 @@ impl World {
         self.spawn_position = position;
     }
     
-    /// Saves all dirty chunks
+    /// Returns the number of chunks modified since they were last saved
+    pub fn dirty_chunk_count(&self) -> usize {
+        self.dirty_chunks.len()
+    }
+    
+    /// Saves the chunks modified since they were last saved, returning how many were written
     pub async fn save_dirty_chunks(&self) -> Result<usize> {
-        // This is a simplified implementation - in a real game, we would track
-        // dirty chunks and only save those
-        let mut count = 0;
-        
-        if let Some(chunks) = self.chunk_provider.get_chunk_if_loaded(ChunkVector::new(0, 0, 0)) {
-            for chunk_pos in chunks.position.to_block_min().to_chunk().manhattan_radius(10) {
-                if let Some(chunk) = self.chunk_provider.get_chunk_if_loaded(chunk_pos) {
-                    self.chunk_provider.save_chunk(&chunk).await?;
-                    count += 1;
-                }
+        // Take the dirty set first, so chunks modified during the save are
+        // added back and written next time
+        let keys: Vec<i64> = self.dirty_chunks.iter().map(|entry| *entry.key()).collect();
+        let chunks: Vec<Arc<Chunk>> = keys
+            .iter()
+            .filter_map(|key| self.dirty_chunks.remove(key).map(|(_, chunk)| chunk))
+            .collect();
+        
+        if chunks.is_empty() {
+            return Ok(0);
+        }
+        
+        if let Err(err) = self.chunk_provider.save_chunks(&chunks).await {
+            // Put the chunks back so the next save retries them
+            for chunk in chunks {
+                self.dirty_chunks.entry(chunk.position.to_key()).or_insert(chunk);
             }
+            return Err(err);
         }
         
-        Ok(count)
+        for chunk in &chunks {
+            if !self.dirty_chunks.contains_key(&chunk.position.to_key()) {
+                chunk.mark_clean();
+            }
+        }
+        
+        Ok(chunks.len())
     }
     
-    /// Unloads chunks that are too far from any player
-    pub fn unload_distant_chunks(&self, active_chunk_positions: &[ChunkVector], radius: i32) -> usize {
-        let mut count = 0;
+    /// Unloads chunks to bring the world back within its budget
+    ///
+    /// Every loaded chunk is considered. Chunks within `simulation_distance`
+    /// (horizontally, at any height) of a player chunk are always kept; the
+    /// others are evicted least recently used first until the budget is met.
+    /// Modified chunks are saved before they are dropped, and a chunk modified
+    /// again while that save runs stays loaded. Returns the number of chunks
+    /// unloaded.
+    pub async fn unload_chunks(&self, player_chunks: &[ChunkVector], simulation_distance: i32, budget: ChunkBudget) -> Result<usize> {
+        let loaded = self.chunk_provider.loaded_chunks();
+        let mut chunk_count = loaded.len();
+        let mut memory: usize = loaded.iter().map(|chunk| chunk.memory_usage()).sum();
         
-        // Get all loaded chunks
-        if let Some(chunks) = self.chunk_provider.get_chunk_if_loaded(ChunkVector::new(0, 0, 0)) {
-            for chunk_pos in chunks.position.to_block_min().to_chunk().manhattan_radius(50) {
-                // Skip if the chunk is not loaded
-                if !self.chunk_provider.chunk_exists(chunk_pos) {
-                    continue;
-                }
-                
-                // Check if any player is near this chunk
-                let mut is_near_player = false;
-                for &player_chunk in active_chunk_positions {
-                    let distance = chunk_pos.manhattan_distance(&player_chunk);
-                    if distance <= radius {
-                        is_near_player = true;
-                        break;
-                    }
+        if !budget.is_exceeded(chunk_count, memory) {
+            return Ok(0);
+        }
+        
+        // Only chunks outside every player's simulation distance may go
+        let radius_squared = simulation_distance * simulation_distance;
+        let mut candidates: Vec<Arc<Chunk>> = loaded
+            .into_iter()
+            .filter(|chunk| {
+                !player_chunks.iter().any(|player| {
+                    let dx = chunk.position.x - player.x;
+                    let dz = chunk.position.z - player.z;
+                    dx * dx + dz * dz <= radius_squared
+                })
+            })
+            .collect();
+        candidates.sort_by_key(|chunk| chunk.last_accessed());
+        
+        // Pick the least recently used chunks until the budget is met
+        let mut evicted = Vec::new();
+        for chunk in candidates {
+            if !budget.is_exceeded(chunk_count, memory) {
+                break;
+            }
+            chunk_count -= 1;
+            memory = memory.saturating_sub(chunk.memory_usage());
+            evicted.push(chunk);
+        }
+        
+        // Save the modified ones first, so their changes aren't lost
+        let dirty: Vec<Arc<Chunk>> = evicted
+            .iter()
+            .filter_map(|chunk| self.dirty_chunks.remove(&chunk.position.to_key()).map(|(_, chunk)| chunk))
+            .collect();
+        if !dirty.is_empty() {
+            if let Err(err) = self.chunk_provider.save_chunks(&dirty).await {
+                for chunk in dirty {
+                    self.dirty_chunks.entry(chunk.position.to_key()).or_insert(chunk);
                 }
-                
-                // Unload the chunk if it's too far from all players
-                if !is_near_player {
-                    if self.chunk_provider.unload_chunk(chunk_pos) {
-                        count += 1;
-                    }
+                return Err(err);
+            }
+            for chunk in &dirty {
+                chunk.mark_clean();
+            }
+        }
+        
+        // A set_block racing with the save may have dirtied a chunk again; keep those loaded
+        let unloaded: Vec<Arc<Chunk>> = evicted
+            .into_iter()
+            .filter(|chunk| !self.dirty_chunks.contains_key(&chunk.position.to_key()))
+            .filter(|chunk| self.chunk_provider.unload_chunk(chunk.position))
+            .collect();
+        
+        // One that landed between that check and the unload wrote to the evicted chunk,
+        // which is no longer loaded, so save it now rather than lose the change
+        let raced: Vec<Arc<Chunk>> = unloaded
+            .iter()
+            .filter_map(|chunk| {
+                self.dirty_chunks
+                    .remove_if(&chunk.position.to_key(), |_, dirty| Arc::ptr_eq(dirty, chunk))
+                    .map(|(_, chunk)| chunk)
+            })
+            .collect();
+        if !raced.is_empty() {
+            if let Err(err) = self.chunk_provider.save_chunks(&raced).await {
+                for chunk in raced {
+                    self.dirty_chunks.entry(chunk.position.to_key()).or_insert(chunk);
                 }
+                return Err(err);
+            }
+            for chunk in &raced {
+                chunk.mark_clean();
             }
         }
         
-        count
+        Ok(unloaded.len())
     }
 }
 
-/// Extension trait for ChunkVector to get blocks in a radius
-trait ManhattanRadius {
-    fn manhattan_radius(&self, radius: i32) -> Vec<ChunkVector>;
+/// Limits on the chunks a world keeps loaded
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChunkBudget {
+    /// The most chunks to keep loaded (0 for no limit)
+    pub max_chunks: usize,
+    /// The most memory, in bytes, loaded chunks may use (0 for no limit)
+    pub max_memory: usize,
 }
 
-impl ManhattanRadius for ChunkVector {
-    fn manhattan_radius(&self, radius: i32) -> Vec<ChunkVector> {
-        let mut result = Vec::new();
-        
-        for x in (self.x - radius)..=(self.x + radius) {
-            for y in (self.y - radius)..=(self.y + radius) {
-                for z in (self.z - radius)..=(self.z + radius) {
-                    let pos = ChunkVector::new(x, y, z);
-                    if pos.manhattan_distance(self) <= radius {
-                        result.push(pos);
-                    }
-                }
-            }
+impl ChunkBudget {
+    /// Checks whether the specified chunk count or memory use is over the budget
+    pub fn is_exceeded(&self, chunks: usize, memory: usize) -> bool {
+        (self.max_chunks > 0 && chunks > self.max_chunks)
+            || (self.max_memory > 0 && memory > self.max_memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::provider::MemoryChunkProvider;
+    use crate::generator::flat::FlatWorldGenerator;
+    use minecraft_core::block::BlockType;
+    use std::time::Duration;
+
+    /// Creates a flat world with the chunks x = 0..5 of a row loaded, oldest first
+    async fn world_with_row() -> (World, Vec<ChunkVector>) {
+        let generator = Arc::new(FlatWorldGenerator::new("flat", 1));
+        let world = World::new("test", 1, Box::new(MemoryChunkProvider::new(generator.clone()).unwrap()), generator);
+        let row: Vec<ChunkVector> = (0..5).map(|x| ChunkVector::new(x, 0, 0)).collect();
+        for position in &row {
+            world.get_chunk(*position).await.unwrap();
+            // Access times are in milliseconds, so keep them apart
+            tokio::time::sleep(Duration::from_millis(5)).await;
         }
-        
-        result
+        (world, row)
+    }
+
+    /// Returns whether the chunk at a position is loaded
+    fn is_loaded(world: &World, position: ChunkVector) -> bool {
+        world.chunk_provider.get_chunk_if_loaded(position).is_some()
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn budget_limits_chunks_and_memory() {
+        let unlimited = ChunkBudget::default();
+        assert!(!unlimited.is_exceeded(usize::MAX, usize::MAX));
+
+        let budget = ChunkBudget { max_chunks: 4, max_memory: 1000 };
+        assert!(!budget.is_exceeded(4, 1000));
+        assert!(budget.is_exceeded(5, 0));
+        assert!(budget.is_exceeded(0, 1001));
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_chunks_are_evicted_first() {
+        let (world, row) = world_with_row().await;
+
+        // Touching the oldest chunk makes it the most recent
+        world.get_chunk(row[0]).await.unwrap();
+
+        let budget = ChunkBudget { max_chunks: 3, max_memory: 0 };
+        let far_away = [ChunkVector::new(100, 0, 100)];
+        assert_eq!(world.unload_chunks(&far_away, 2, budget).await.unwrap(), 2);
+
+        assert!(is_loaded(&world, row[0]));
+        assert!(!is_loaded(&world, row[1]));
+        assert!(!is_loaded(&world, row[2]));
+        assert!(is_loaded(&world, row[3]));
+        assert!(is_loaded(&world, row[4]));
+
+        // Within budget, nothing more goes
+        assert_eq!(world.unload_chunks(&far_away, 2, budget).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn chunks_near_players_are_kept() {
+        let (world, row) = world_with_row().await;
+
+        // The player keeps the two oldest chunks, however far over budget the world is
+        let budget = ChunkBudget { max_chunks: 1, max_memory: 0 };
+        assert_eq!(world.unload_chunks(&[row[0]], 1, budget).await.unwrap(), 3);
+
+        assert!(is_loaded(&world, row[0]));
+        assert!(is_loaded(&world, row[1]));
+        assert!(row[2..].iter().all(|position| !is_loaded(&world, *position)));
+    }
+
+    #[tokio::test]
+    async fn modified_chunks_are_saved_before_eviction() {
+        let (world, row) = world_with_row().await;
+        world.set_block(BlockVector::new(1, 10, 1), Block::new(BlockType::Stone)).await.unwrap();
+        let modified = world.get_chunk(row[0]).await.unwrap();
+        assert!(modified.is_dirty());
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        for position in &row[1..] {
+            world.get_chunk(*position).await.unwrap();
+        }
+
+        let budget = ChunkBudget { max_chunks: 4, max_memory: 0 };
+        assert_eq!(world.unload_chunks(&[], 2, budget).await.unwrap(), 1);
+        assert!(!is_loaded(&world, row[0]));
+
+        // The change was handed to the provider rather than left waiting for a save
+        assert!(!modified.is_dirty());
+        assert_eq!(world.save_dirty_chunks().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,217 @@
+use minecraft_core::math::BlockVector;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+/// The six blocks touching a block
+const NEIGHBOURS: [(i32, i32, i32); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+/// The block updates due on one game tick
+#[derive(Debug, Clone, Default)]
+pub struct BlockUpdates {
+    /// The game tick the updates are for
+    pub tick: u64,
+    /// Blocks that changed, or had a neighbour change, since the last tick
+    pub neighbour_updates: Vec<BlockVector>,
+    /// Blocks whose scheduled ticks are due
+    pub scheduled_ticks: Vec<BlockVector>,
+}
+
+impl BlockUpdates {
+    /// Checks whether there is nothing to update
+    pub fn is_empty(&self) -> bool {
+        self.neighbour_updates.is_empty() && self.scheduled_ticks.is_empty()
+    }
+}
+
+/// Queues the block updates a world has to process
+///
+/// Changing a block queues an update for it and its six neighbours, and
+/// block behaviours can schedule a tick for a block some game ticks ahead.
+/// Both kinds are collected once per game tick by `take`; anything queued
+/// while they are being processed waits for the next tick, so a chain of
+/// updates spreads out over time instead of running away within one tick.
+#[derive(Debug, Default)]
+pub struct BlockUpdateQueue {
+    /// The queued updates
+    state: Mutex<QueueState>,
+}
+
+/// The contents of a block update queue
+#[derive(Debug, Default)]
+struct QueueState {
+    /// The game tick the queue has reached
+    tick: u64,
+    /// Blocks waiting for a neighbour update, in the order they were queued
+    neighbour_updates: VecDeque<BlockVector>,
+    /// The blocks in `neighbour_updates`, so each is only queued once
+    queued: HashSet<BlockVector>,
+    /// Scheduled ticks, keyed by the game tick they're due and then the order they were scheduled
+    scheduled: BTreeMap<(u64, u64), BlockVector>,
+    /// The blocks with a tick in `scheduled`, so each only has one at a time
+    scheduled_positions: HashSet<BlockVector>,
+    /// The number of ticks scheduled so far, used to keep ticks due together in order
+    sequence: u64,
+}
+
+impl BlockUpdateQueue {
+    /// Creates an empty queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues an update for a block that changed and for each of its neighbours
+    pub fn queue_neighbours(&self, position: BlockVector) {
+        let mut state = self.state.lock().unwrap();
+        state.queue(position);
+        for (dx, dy, dz) in NEIGHBOURS {
+            state.queue(BlockVector::new(position.x + dx, position.y + dy, position.z + dz));
+        }
+    }
+
+    /// Schedules a tick for a block after the specified number of game ticks
+    ///
+    /// Returns false if the block already has a tick scheduled, in which case
+    /// that one is kept.
+    pub fn schedule(&self, position: BlockVector, delay: u64) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if !state.scheduled_positions.insert(position) {
+            return false;
+        }
+
+        let due = state.tick + delay.max(1);
+        let sequence = state.sequence;
+        state.sequence += 1;
+        state.scheduled.insert((due, sequence), position);
+        true
+    }
+
+    /// Checks whether a block has a tick scheduled
+    pub fn is_scheduled(&self, position: BlockVector) -> bool {
+        self.state.lock().unwrap().scheduled_positions.contains(&position)
+    }
+
+    /// Returns the number of neighbour updates and scheduled ticks waiting
+    pub fn len(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.neighbour_updates.len() + state.scheduled.len()
+    }
+
+    /// Checks whether no updates are waiting
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Advances to the next game tick and takes the updates due on it
+    pub fn take(&self) -> BlockUpdates {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+
+        let neighbour_updates: Vec<BlockVector> = state.neighbour_updates.drain(..).collect();
+        state.queued.clear();
+
+        let later = state.scheduled.split_off(&(tick + 1, 0));
+        let due = std::mem::replace(&mut state.scheduled, later);
+        let scheduled_ticks: Vec<BlockVector> = due.into_values().collect();
+        for position in &scheduled_ticks {
+            state.scheduled_positions.remove(position);
+        }
+
+        BlockUpdates {
+            tick,
+            neighbour_updates,
+            scheduled_ticks,
+        }
+    }
+}
+
+impl QueueState {
+    /// Queues a neighbour update for a block unless it already has one
+    fn queue(&mut self, position: BlockVector) {
+        if self.queued.insert(position) {
+            self.neighbour_updates.push_back(position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbour_updates_are_queued_once_per_tick() {
+        let queue = BlockUpdateQueue::new();
+        let a = BlockVector::new(0, 0, 0);
+        let b = BlockVector::new(1, 0, 0);
+        queue.queue_neighbours(a);
+        queue.queue_neighbours(b);
+
+        // Each block and its six neighbours, minus the two they share
+        let updates = queue.take();
+        assert_eq!(updates.neighbour_updates.len(), 12);
+        let unique: HashSet<BlockVector> = updates.neighbour_updates.iter().copied().collect();
+        assert_eq!(unique.len(), updates.neighbour_updates.len());
+        assert_eq!(updates.neighbour_updates[0], a);
+
+        // Once taken, the same block can be queued again
+        queue.queue_neighbours(a);
+        assert_eq!(queue.take().neighbour_updates.len(), 7);
+    }
+
+    #[test]
+    fn updates_queued_while_processing_wait_for_the_next_tick() {
+        let queue = BlockUpdateQueue::new();
+        queue.queue_neighbours(BlockVector::new(0, 0, 0));
+
+        let first = queue.take();
+        assert_eq!(first.tick, 1);
+        for position in &first.neighbour_updates {
+            queue.queue_neighbours(*position);
+            queue.schedule(*position, 0);
+        }
+        assert!(!queue.is_empty());
+
+        let second = queue.take();
+        assert_eq!(second.tick, 2);
+        assert!(second.neighbour_updates.len() > first.neighbour_updates.len());
+        assert_eq!(second.scheduled_ticks.len(), first.neighbour_updates.len());
+        assert!(queue.take().is_empty());
+    }
+
+    #[test]
+    fn one_scheduled_tick_per_block() {
+        let queue = BlockUpdateQueue::new();
+        let position = BlockVector::new(5, 5, 5);
+        assert!(queue.schedule(position, 3));
+        assert!(!queue.schedule(position, 1));
+        assert!(queue.is_scheduled(position));
+
+        // The first schedule is the one kept
+        assert!(queue.take().scheduled_ticks.is_empty());
+        assert!(queue.take().scheduled_ticks.is_empty());
+        assert_eq!(queue.take().scheduled_ticks, vec![position]);
+        assert!(!queue.is_scheduled(position));
+        assert!(queue.schedule(position, 1));
+    }
+
+    #[test]
+    fn scheduled_ticks_come_due_in_order() {
+        let queue = BlockUpdateQueue::new();
+        let positions: Vec<BlockVector> = (0..4).map(|x| BlockVector::new(x, 0, 0)).collect();
+        queue.schedule(positions[0], 2);
+        queue.schedule(positions[1], 1);
+        queue.schedule(positions[2], 2);
+        queue.schedule(positions[3], 1);
+
+        assert_eq!(queue.take().scheduled_ticks, vec![positions[1], positions[3]]);
+        assert_eq!(queue.take().scheduled_ticks, vec![positions[0], positions[2]]);
+        assert!(queue.is_empty());
+    }
+}
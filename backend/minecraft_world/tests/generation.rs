@@ -0,0 +1,72 @@
+use minecraft_core::math::ChunkVector;
+use minecraft_world::chunk::Chunk;
+use minecraft_world::generator::TerrainGenerator;
+use minecraft_world::generator::overworld::OverworldGenerator;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The seed both generators are created with
+const SEED: i64 = 20240917;
+
+/// The chunks compared: a few columns from bedrock up past the surface
+fn positions() -> Vec<ChunkVector> {
+    let mut positions = Vec::new();
+    for x in -1..=1 {
+        for z in -1..=1 {
+            for y in 0..8 {
+                positions.push(ChunkVector::new(x, y, z));
+            }
+        }
+    }
+    positions
+}
+
+/// Returns every section of a chunk as packed blocks, keyed by section
+fn blocks(chunk: &Chunk) -> Vec<(i32, Vec<u32>)> {
+    (-1..=16)
+        .filter_map(|section_y| {
+            let section = chunk.get_section(section_y)?;
+            let blocks = section.read().unwrap().to_packed();
+            Some((section_y, blocks))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn shuffled_generation_matches_sequential() {
+    let positions = positions();
+
+    // Generate in order with one generator
+    let sequential_generator = OverworldGenerator::new("Overworld", SEED);
+    let mut sequential = HashMap::new();
+    for &position in &positions {
+        let chunk = sequential_generator.generate_chunk(position).await.unwrap();
+        sequential.insert(position, blocks(&chunk));
+    }
+
+    // Generate in a shuffled order, concurrently, with a fresh generator
+    let mut shuffled_positions = positions.clone();
+    shuffled_positions.shuffle(&mut StdRng::seed_from_u64(7));
+
+    let shuffled_generator = Arc::new(OverworldGenerator::new("Overworld", SEED));
+    let tasks: Vec<_> = shuffled_positions
+        .into_iter()
+        .map(|position| {
+            let generator = shuffled_generator.clone();
+            tokio::spawn(async move { (position, generator.generate_chunk(position).await.unwrap()) })
+        })
+        .collect();
+
+    for task in tasks {
+        let (position, chunk) = task.await.unwrap();
+        assert_eq!(
+            blocks(&chunk),
+            sequential[&position],
+            "chunk {:?} differs between sequential and shuffled generation",
+            position
+        );
+    }
+}